@@ -0,0 +1,103 @@
+//! `--fast`で使う`scan_law_header`と、本文全体を解析する`parse_law_xml`の速さを比べる
+//!
+//! ```sh
+//! cargo bench --bench scan_header
+//! LISTUP_LAW_BENCH_WORK=data/法令データ一式 cargo bench --bench scan_header
+//! ```
+//!
+//! 環境変数`LISTUP_LAW_BENCH_WORK`に作業ディレクトリを指定した場合は、その中のXMLファイルをすべて読んで比べる。
+//! 指定しない場合は、条の数を変えて作った法令XMLで比べる
+
+use listup_law::metadata::{parse_law_xml, scan_law_header};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 計測を繰り返す回数（最も速かった回を使う）
+const ROUNDS: usize = 5;
+
+/// `articles`個の条を持つ法令XML
+fn synthetic_law(articles: usize) -> Vec<u8> {
+  let mut xml = String::from(
+    r#"<?xml version="1.0" encoding="UTF-8"?>
+<Law Era="Heisei" Year="15" Num="57" LawType="Act" Lang="ja" PromulgateMonth="05" PromulgateDay="30">
+  <LawNum>平成十五年法律第五十七号</LawNum>
+  <LawBody>
+    <LawTitle Kana="こじんじょうほうのほごにかんするほうりつ">個人情報の保護に関する法律</LawTitle>
+    <MainProvision>
+"#,
+  );
+  for i in 1..=articles {
+    xml.push_str(&format!(
+      r#"      <Article Num="{i}">
+        <ArticleCaption>（目的）</ArticleCaption>
+        <ArticleTitle>第{i}条</ArticleTitle>
+        <Paragraph Num="1">
+          <ParagraphNum/>
+          <ParagraphSentence>
+            <Sentence Num="1">この法律は、個人情報の有用性に配慮しつつ、個人の権利利益を保護することを目的とする。</Sentence>
+          </ParagraphSentence>
+        </Paragraph>
+      </Article>
+"#
+    ));
+  }
+  xml.push_str(
+    r#"    </MainProvision>
+  </LawBody>
+</Law>
+"#,
+  );
+  xml.into_bytes()
+}
+
+/// 作業ディレクトリの中のXMLファイルをすべて読み込む
+fn read_work_dir(dir: &Path, files: &mut Vec<Vec<u8>>) {
+  for entry in std::fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+    if path.is_dir() {
+      read_work_dir(&path, files);
+    } else if path.extension().is_some_and(|ext| ext == "xml") {
+      files.push(std::fs::read(&path).unwrap());
+    }
+  }
+}
+
+/// `f`ですべてのファイルを読むのにかかった時間のうち、最も短いもの
+fn measure(files: &[Vec<u8>], f: impl Fn(&[u8]) -> bool) -> (Duration, usize) {
+  let mut best = Duration::MAX;
+  let mut ok = 0;
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    ok = files.iter().filter(|buf| f(buf)).count();
+    best = best.min(start.elapsed());
+  }
+  (best, ok)
+}
+
+fn compare(label: &str, files: &[Vec<u8>]) {
+  let bytes = files.iter().map(Vec::len).sum::<usize>();
+  let (scan, scanned) = measure(files, |buf| scan_law_header(buf).is_ok());
+  let (parse, parsed) = measure(files, |buf| parse_law_xml(buf).is_ok());
+  println!(
+    "{label}: {} files, {} KiB; scan_law_header {scan:?} ({scanned} ok), parse_law_xml {parse:?} ({parsed} ok), {:.1}x faster",
+    files.len(),
+    bytes / 1024,
+    parse.as_secs_f64() / scan.as_secs_f64()
+  );
+}
+
+fn main() {
+  match std::env::var("LISTUP_LAW_BENCH_WORK") {
+    Ok(dir) => {
+      let mut files = Vec::new();
+      read_work_dir(Path::new(&dir), &mut files);
+      compare(&dir, &files);
+    }
+    Err(_) => {
+      for articles in [10, 100, 1000] {
+        let files = vec![synthetic_law(articles); 100];
+        compare(&format!("{articles} articles"), &files);
+      }
+    }
+  }
+}
@@ -0,0 +1,48 @@
+//! 法令の略称
+
+use crate::record::LawRecord;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// `LawTitle`タグの`Abbrev`属性の値を略称ごとに分ける
+///
+/// 複数の略称は"，"や"、"などで区切られている
+pub fn split_abbrev(attr: &str) -> Vec<String> {
+  attr
+    .split([',', '，', '、'])
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// 1列目に法令ID、2列目に略称を書いたCSVファイルを読み込む
+///
+/// 1行目は見出しとして読み飛ばす。同じ法令IDの行が複数ある場合は、そのすべての略称を使う
+pub fn read_abbrev_csv(path: &str) -> Result<HashMap<String, Vec<String>>> {
+  let mut rdr = csv::Reader::from_path(path)?;
+  let mut map: HashMap<String, Vec<String>> = HashMap::new();
+  for record in rdr.records() {
+    let record = record?;
+    if let (Some(id), Some(abbrev)) = (record.get(0), record.get(1)) {
+      map
+        .entry(id.trim().to_string())
+        .or_default()
+        .extend(split_abbrev(abbrev));
+    }
+  }
+  Ok(map)
+}
+
+/// 外部の略称の一覧を法令の一覧に加える
+pub fn apply_abbrev(laws: &mut [LawRecord], abbrevs: &HashMap<String, Vec<String>>) {
+  for law in laws.iter_mut() {
+    if let Some(lst) = abbrevs.get(&law.id.to_string()) {
+      for abbrev in lst {
+        if !law.abbrev.contains(abbrev) {
+          law.abbrev.push(abbrev.clone());
+        }
+      }
+    }
+  }
+}
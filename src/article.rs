@@ -0,0 +1,86 @@
+//! 法令の本則の条ごとに1行とする出力
+
+use crate::record::LawRecord;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 本則の条を読み取るのに使う要素
+pub const READ_TAGS: &[&str] = &["MainProvision", "Article", "ArticleTitle", "ArticleCaption"];
+
+/// XMLファイルの本則から読み取った条1つ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArticleEntry {
+  /// `Article`タグの`Num`属性（"90_2"のように枝番号は`_`でつなぐ）
+  pub num: String,
+  /// 条名（"第九十条の二"など）
+  pub title: String,
+  /// 見出し（"（公序良俗）"など、無い場合は`None`）
+  pub caption: Option<String>,
+}
+
+/// 条1つを1行とする出力の項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArticleRecord {
+  /// 法令ID
+  pub id: String,
+  /// `Article`タグの`Num`属性
+  pub num: String,
+  /// 条名
+  pub title: String,
+  /// 見出し
+  pub caption: Option<String>,
+  /// 条を読み取ったXMLファイルへのpath
+  pub file: String,
+}
+
+/// 法令XMLの本則（`MainProvision`タグの中）にある条を順に読み取る
+///
+/// 附則の条は含めない。条名と見出しの中のルビなどのタグは取り除く
+pub fn articles_of(xml: &str) -> Vec<ArticleEntry> {
+  static ARTICLE_RE: OnceLock<Regex> = OnceLock::new();
+  static TAG_RE: OnceLock<Regex> = OnceLock::new();
+  let article_re = ARTICLE_RE.get_or_init(|| {
+    Regex::new(
+      r#"(?s)<Article\s[^>]*?Num="(?P<num>[^"]+)"[^>]*>\s*(?:<ArticleCaption[^>]*>(?P<caption>.*?)</ArticleCaption>\s*)?<ArticleTitle[^>]*>(?P<title>.*?)</ArticleTitle>"#,
+    )
+    .unwrap()
+  });
+  let tag_re = TAG_RE.get_or_init(|| Regex::new(r"(?s)<Rt>.*?</Rt>|<[^>]*>").unwrap());
+  let Some(start) = xml.find("<MainProvision") else {
+    return Vec::new();
+  };
+  let end = xml[start..]
+    .find("</MainProvision>")
+    .map_or(xml.len(), |end| start + end);
+  let strip = |s: &str| tag_re.replace_all(s, "").trim().to_string();
+  article_re
+    .captures_iter(&xml[start..end])
+    .map(|caps| ArticleEntry {
+      num: caps["num"].to_string(),
+      title: strip(&caps["title"]),
+      caption: caps.name("caption").map(|m| strip(m.as_str())),
+    })
+    .collect()
+}
+
+/// 法令の一覧から、条ごとに1行とする一覧を作る
+///
+/// 条は一覧を作るときに読み取ったもの（`ListupOptions::articles`を指定した場合のみ）を使う
+pub fn article_records(laws: &[LawRecord]) -> Vec<ArticleRecord> {
+  let mut lst = Vec::new();
+  for law in laws {
+    for patch in &law.patch {
+      for article in &patch.articles {
+        lst.push(ArticleRecord {
+          id: law.id.to_string(),
+          num: article.num.clone(),
+          title: article.title.clone(),
+          caption: article.caption.clone(),
+          file: patch.file.clone(),
+        });
+      }
+    }
+  }
+  lst
+}
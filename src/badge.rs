@@ -0,0 +1,147 @@
+//! READMEやダッシュボードに表示するための、法令の一覧の件数と作成日時のまとめ
+
+use crate::record::LawRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 法令の一覧のまとめ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Badge {
+  /// 法令の数
+  pub laws: usize,
+  /// XMLファイル（改正ごとの版）の数
+  pub snapshots: usize,
+  /// 一覧を作成した日時（RFC 3339形式）
+  pub generated_at: String,
+  /// 法令の一覧（all_law_list.csv）に載っている法令の数（`with_coverage`を呼んだ場合のみ）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub listed: Option<usize>,
+  /// 法令の一覧に載っている法令のうち、手元にXMLファイルがある法令の数
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub listed_found: Option<usize>,
+  /// `listed_found`の`listed`に対する割合（0から1まで、一覧が空の場合は`None`）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub coverage: Option<f64>,
+}
+
+/// shields.ioのendpoint badgeの形式
+///
+/// <https://shields.io/badges/endpoint-badge>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShieldsEndpoint {
+  pub schema_version: u8,
+  pub label: String,
+  pub message: String,
+  pub color: String,
+}
+
+impl Badge {
+  pub fn new(laws: &[LawRecord]) -> Self {
+    Badge {
+      laws: laws.len(),
+      snapshots: laws.iter().map(|law| law.patch.len()).sum(),
+      generated_at: chrono::Utc::now().to_rfc3339(),
+      listed: None,
+      listed_found: None,
+      coverage: None,
+    }
+  }
+
+  /// 法令の一覧に載っている法令IDの集合と突き合わせ、手元にXMLファイルがある割合を加える
+  pub fn with_coverage(mut self, laws: &[LawRecord], listed: &HashSet<String>) -> Self {
+    let local = laws
+      .iter()
+      .map(|law| law.id.to_string())
+      .collect::<HashSet<_>>();
+    let found = listed.iter().filter(|id| local.contains(*id)).count();
+    self.listed = Some(listed.len());
+    self.listed_found = Some(found);
+    self.coverage = (!listed.is_empty()).then(|| found as f64 / listed.len() as f64);
+    self
+  }
+
+  /// 法令の数を表示するshields.ioのendpoint badge
+  ///
+  /// 法令の一覧と突き合わせた場合は、手元にある割合も表示する
+  pub fn shields(&self) -> ShieldsEndpoint {
+    let message = match self.coverage {
+      Some(coverage) => format!("{} ({:.1}%)", self.laws, coverage * 100.0),
+      None => self.laws.to_string(),
+    };
+    ShieldsEndpoint {
+      schema_version: 1,
+      label: "laws".to_string(),
+      message,
+      color: "blue".to_string(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::{date, law, patch};
+
+  #[test]
+  fn reports_coverage_against_law_list() {
+    let laws = vec![
+      law("415AC0000000057", "個人情報の保護に関する法律"),
+      law("129AC0000000089", "民法"),
+    ];
+    let listed = [
+      "415AC0000000057",
+      "129AC0000000089",
+      "132AC0000000048",
+      "140AC0000000045",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+    let badge = Badge::new(&laws).with_coverage(&laws, &listed);
+
+    assert_eq!(badge.listed, Some(4));
+    assert_eq!(badge.listed_found, Some(2));
+    assert_eq!(badge.coverage, Some(0.5));
+    assert_eq!(badge.shields().message, "2 (50.0%)");
+    assert_eq!(Badge::new(&laws).shields().message, "2");
+  }
+
+  #[test]
+  fn leaves_coverage_out_without_law_list() {
+    let mut privacy = law("415AC0000000057", "個人情報の保護に関する法律");
+    privacy.patch.push(patch(
+      "415AC0000000057",
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    let laws = vec![privacy, law("129AC0000000089", "民法")];
+    let badge = Badge::new(&laws);
+    assert_eq!(badge.snapshots, 3);
+    assert!(chrono::DateTime::parse_from_rfc3339(&badge.generated_at).is_ok());
+
+    let json = serde_json::to_value(&badge).unwrap();
+    let keys = json
+      .as_object()
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect::<Vec<_>>();
+    assert_eq!(keys, ["generated_at", "laws", "snapshots"]);
+    let shields = serde_json::to_value(badge.shields()).unwrap();
+    assert_eq!(shields["schemaVersion"], 1);
+    assert_eq!(shields["label"], "laws");
+
+    // 空の一覧と突き合わせても割合は求めない
+    let badge = Badge::new(&laws).with_coverage(&laws, &HashSet::new());
+    assert_eq!((badge.listed, badge.listed_found), (Some(0), Some(0)));
+    assert_eq!(badge.coverage, None);
+    assert_eq!(badge.shields().message, "2");
+
+    // 一覧に無い手元の法令は数えない
+    let listed = ["129AC0000000089".to_string()].into_iter().collect();
+    let badge = Badge::new(&laws).with_coverage(&laws, &listed);
+    assert_eq!(badge.coverage, Some(1.0));
+    assert_eq!(badge.shields().message, "2 (100.0%)");
+  }
+}
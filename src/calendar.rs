@@ -0,0 +1,512 @@
+//! 元号と西暦の変換
+//!
+//! 元号の開始日を表として持ち、西暦への変換や元号の範囲の判定はすべてこの表から計算する。
+
+use crate::kansuji::parse_kansuji;
+use anyhow::{anyhow, Result};
+use japanese_law_xml_schema::law::Era;
+use jplaw_data_types::law::Date;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// 西暦（グレゴリオ暦）の日付
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AdDate {
+  pub year: usize,
+  pub month: usize,
+  pub day: usize,
+}
+
+impl AdDate {
+  pub const fn new(year: usize, month: usize, day: usize) -> Self {
+    AdDate { year, month, day }
+  }
+
+  /// 1970年1月1日からの日数
+  pub fn days_since_epoch(&self) -> i64 {
+    let (month, day) = (self.month as i64, self.day as i64);
+    let year = self.year as i64 - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+  }
+}
+
+impl std::fmt::Display for AdDate {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+  }
+}
+
+impl FromStr for AdDate {
+  type Err = anyhow::Error;
+  /// "YYYY-MM-DD"形式の文字列を読む
+  fn from_str(s: &str) -> Result<Self> {
+    let err = || anyhow!("date must be written as YYYY-MM-DD: {s}");
+    let mut parts = s.trim().splitn(3, '-');
+    let mut next = || -> Result<usize> {
+      parts
+        .next()
+        .ok_or_else(err)?
+        .parse::<usize>()
+        .map_err(|_| err())
+    };
+    let (year, month, day) = (next()?, next()?, next()?);
+    if !(1..=12).contains(&month) || day == 0 || days_in_month(year, month) < day {
+      return Err(err());
+    }
+    Ok(AdDate::new(year, month, day))
+  }
+}
+
+/// 実行している環境の地方時での今日の日付
+#[cfg(feature = "full")]
+pub fn today() -> AdDate {
+  use chrono::Datelike;
+  let now = chrono::Local::now().date_naive();
+  AdDate::new(
+    now.year() as usize,
+    now.month() as usize,
+    now.day() as usize,
+  )
+}
+
+/// 元号の表の1行
+struct EraRow {
+  /// 漢字表記
+  kanji: &'static str,
+  /// XMLの`Era`属性での表記
+  attr: &'static str,
+  /// 元年1月1日が属する西暦年
+  first_year: usize,
+  /// 改元の日
+  start: AdDate,
+}
+
+/// 元号の表
+///
+/// 明治は慶応4年1月1日（1868年1月25日）に遡って適用されたため、その日を開始日とする。
+const ERA_TABLE: [EraRow; 5] = [
+  EraRow {
+    kanji: "明治",
+    attr: "Meiji",
+    first_year: 1868,
+    start: AdDate::new(1868, 1, 25),
+  },
+  EraRow {
+    kanji: "大正",
+    attr: "Taisho",
+    first_year: 1912,
+    start: AdDate::new(1912, 7, 30),
+  },
+  EraRow {
+    kanji: "昭和",
+    attr: "Showa",
+    first_year: 1926,
+    start: AdDate::new(1926, 12, 25),
+  },
+  EraRow {
+    kanji: "平成",
+    attr: "Heisei",
+    first_year: 1989,
+    start: AdDate::new(1989, 1, 8),
+  },
+  EraRow {
+    kanji: "令和",
+    attr: "Reiwa",
+    first_year: 2019,
+    start: AdDate::new(2019, 5, 1),
+  },
+];
+
+fn era_index(era: &Era) -> usize {
+  match era {
+    Era::Meiji => 0,
+    Era::Taisho => 1,
+    Era::Showa => 2,
+    Era::Heisei => 3,
+    Era::Reiwa => 4,
+  }
+}
+
+fn era_from_index(i: usize) -> Era {
+  match i {
+    0 => Era::Meiji,
+    1 => Era::Taisho,
+    2 => Era::Showa,
+    3 => Era::Heisei,
+    _ => Era::Reiwa,
+  }
+}
+
+fn days_in_month(year: usize, month: usize) -> usize {
+  match month {
+    1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+    4 | 6 | 9 | 11 => 30,
+    _ if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+    _ => 28,
+  }
+}
+
+fn previous_day(date: AdDate) -> AdDate {
+  if date.day > 1 {
+    AdDate::new(date.year, date.month, date.day - 1)
+  } else if date.month > 1 {
+    AdDate::new(
+      date.year,
+      date.month - 1,
+      days_in_month(date.year, date.month - 1),
+    )
+  } else {
+    AdDate::new(date.year - 1, 12, 31)
+  }
+}
+
+/// 元号についての問い合わせ
+pub trait EraExt {
+  /// 改元の日
+  fn start_date(&self) -> AdDate;
+  /// 元号の最後の日（現在の元号の場合は`None`）
+  fn end_date(&self) -> Option<AdDate>;
+  /// 日付がこの元号の期間に含まれるかどうか
+  fn contains(&self, date: &AdDate) -> bool;
+  /// "平成"のような漢字表記
+  fn kanji(&self) -> &'static str;
+  /// "Heisei"のようなXMLの`Era`属性での表記
+  fn attr(&self) -> &'static str;
+}
+
+impl EraExt for Era {
+  fn start_date(&self) -> AdDate {
+    ERA_TABLE[era_index(self)].start
+  }
+  fn end_date(&self) -> Option<AdDate> {
+    ERA_TABLE
+      .get(era_index(self) + 1)
+      .map(|next| previous_day(next.start))
+  }
+  fn contains(&self, date: &AdDate) -> bool {
+    self.start_date() <= *date && !matches!(self.end_date(), Some(end) if end < *date)
+  }
+  fn kanji(&self) -> &'static str {
+    ERA_TABLE[era_index(self)].kanji
+  }
+  fn attr(&self) -> &'static str {
+    ERA_TABLE[era_index(self)].attr
+  }
+}
+
+/// "平成"のような漢字表記から元号を得る
+pub fn era_from_kanji(s: &str) -> Option<Era> {
+  ERA_TABLE
+    .iter()
+    .position(|row| row.kanji == s)
+    .map(era_from_index)
+}
+
+/// "Heisei"のようなXMLの`Era`属性での表記から元号を得る
+pub fn era_from_attr(s: &str) -> Option<Era> {
+  ERA_TABLE
+    .iter()
+    .position(|row| row.attr == s)
+    .map(era_from_index)
+}
+
+/// 法令IDの先頭の数字（明治は1、令和は5）から元号を得る
+pub fn era_from_law_id_code(c: char) -> Option<Era> {
+  match c.to_digit(10)? {
+    n @ 1..=5 => Some(era_from_index(n as usize - 1)),
+    _ => None,
+  }
+}
+
+/// 元号の表に無い元号（改元の後に配布されたXMLファイルなどのもの）
+///
+/// `Era`は表にある元号しか表せないので、この元号の年は西暦の年を通して表の最後の元号の年に読み替える
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtraEra {
+  /// XMLの`Era`属性での表記
+  pub attr: String,
+  /// 元年1月1日が属する西暦年
+  pub first_year: usize,
+}
+
+impl ExtraEra {
+  /// この元号の`year`年を、表の最後の元号と年に読み替える
+  ///
+  /// 表の最後の元号の元年より前になる場合は`None`を返す
+  pub fn to_known_era(&self, year: usize) -> Option<(Era, usize)> {
+    let last = ERA_TABLE.len() - 1;
+    let ad = (self.first_year + year).checked_sub(1)?;
+    let first_year = ERA_TABLE[last].first_year;
+    (first_year <= ad).then(|| (era_from_index(last), ad - first_year + 1))
+  }
+}
+
+impl FromStr for ExtraEra {
+  type Err = anyhow::Error;
+  /// "Attr:2040"のように、`Era`属性での表記と元年の西暦年を`:`で区切った文字列を読む
+  fn from_str(s: &str) -> Result<Self> {
+    let err = || anyhow!("extra era must be written as ATTR:FIRST_YEAR: {s}");
+    let (attr, first_year) = s.trim().split_once(':').ok_or_else(err)?;
+    if attr.is_empty() || era_from_attr(attr).is_some() {
+      return Err(err());
+    }
+    Ok(ExtraEra {
+      attr: attr.to_string(),
+      first_year: first_year.parse().map_err(|_| err())?,
+    })
+  }
+}
+
+/// 和暦の年を西暦の年に変換する
+pub fn era_to_ad(era: &Era, year: usize) -> usize {
+  ERA_TABLE[era_index(era)].first_year + year - 1
+}
+
+/// 西暦の日付を元号と和暦の年に変換する
+///
+/// 改元の日より前の日付は前の元号の最後の年とする（1989年1月7日は昭和64年、1月8日は平成元年）。
+/// 実在しない日付や明治より前の日付の場合はエラーを返す
+pub fn ad_to_era(date: &AdDate) -> Result<(Era, usize)> {
+  if !(1..=12).contains(&date.month)
+    || date.day == 0
+    || days_in_month(date.year, date.month) < date.day
+  {
+    return Err(anyhow!("invalid date: {date}"));
+  }
+  let i = ERA_TABLE
+    .iter()
+    .rposition(|row| row.start <= *date)
+    .ok_or(anyhow!("date before Meiji: {date}"))?;
+  Ok((era_from_index(i), date.year - ERA_TABLE[i].first_year + 1))
+}
+
+/// 西暦の日付を和暦の日付に変換する
+///
+/// 実在しない日付や明治より前の日付の場合はエラーを返す
+pub fn date_from_ad(date: &AdDate) -> Result<Date> {
+  let (era, year) = ad_to_era(date)?;
+  Ok(Date::new(era, year, Some(date.month), Some(date.day)))
+}
+
+/// 和暦の日付を西暦の日付に変換する
+///
+/// 月や日が無い場合は1月や1日として扱う
+pub fn date_to_ad(date: &Date) -> AdDate {
+  AdDate::new(
+    era_to_ad(&date.era, date.year),
+    date.month.unwrap_or(1),
+    date.day.unwrap_or(1),
+  )
+}
+
+/// 和暦の日付を西暦の日付に変換する
+///
+/// `date_to_ad`と違い、その元号の期間に無い日付（"平成元年1月7日"など）や実在しない日付の場合はエラーを返す
+pub fn try_date_to_ad(date: &Date) -> Result<AdDate> {
+  if !is_valid_date(date) {
+    return Err(anyhow!("invalid date: {}", to_iso8601(date)));
+  }
+  Ok(date_to_ad(date))
+}
+
+/// グレゴリオ暦を使い始めた年（明治5年12月3日を明治6年1月1日とした）
+pub const GREGORIAN_YEAR: usize = 1873;
+
+/// 日付の月日が従う暦
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarSystem {
+  /// グレゴリオ暦
+  #[default]
+  Gregorian,
+  /// 明治5年までの太陰太陽暦（天保暦）
+  Lunisolar,
+}
+
+impl CalendarSystem {
+  pub fn is_gregorian(&self) -> bool {
+    *self == CalendarSystem::Gregorian
+  }
+}
+
+/// 和暦の日付の月日が従う暦
+///
+/// 明治5年までの月日は太陰太陽暦のものとする。年だけの日付は暦によらないのでグレゴリオ暦とする。
+/// 太陰太陽暦の月日をグレゴリオ暦に換算する表は持たないので、`date_to_ad`などは月日をそのまま使う
+pub fn calendar_of(date: &Date) -> CalendarSystem {
+  if date.month.is_some() && era_to_ad(&date.era, date.year) < GREGORIAN_YEAR {
+    CalendarSystem::Lunisolar
+  } else {
+    CalendarSystem::Gregorian
+  }
+}
+
+/// 和暦の日付を並べるときの鍵
+///
+/// 西暦の年・月・日の順に比べ、月や日が無い日付はある日付より前にする（"2003"＜"2003-01"＜"2003-01-01"）。
+/// 西暦の年月日が同じ場合は元号の順にするので、異なる日付が等しくなることはない
+pub fn date_sort_key(date: &Date) -> (usize, Option<usize>, Option<usize>, usize) {
+  (
+    era_to_ad(&date.era, date.year),
+    date.month,
+    date.day,
+    era_index(&date.era),
+  )
+}
+
+/// 和暦の日付の全順序（`date_sort_key`の順）
+///
+/// `Date`自身の比較は月や日が無い日付と比べると順序が決まらないことがあるので、並べ替えにはこちらを使う
+pub fn cmp_dates(a: &Date, b: &Date) -> Ordering {
+  date_sort_key(a).cmp(&date_sort_key(b))
+}
+
+/// 和暦の日付が実在する日付かどうか
+///
+/// 年がその元号の期間に収まっているか、月日がその月の日数に収まっているかを調べる。
+/// 太陰太陽暦が使われていた明治5年以前は、月日と元号の期間の突き合わせは行わない
+pub fn is_valid_date(date: &Date) -> bool {
+  if date.year == 0 {
+    return false;
+  }
+  let ad_year = era_to_ad(&date.era, date.year);
+  if let Some(end) = date.era.end_date() {
+    if end.year < ad_year {
+      return false;
+    }
+  }
+  match (date.month, date.day) {
+    (None, None) => true,
+    (Some(month), None) => {
+      (1..=12).contains(&month)
+        && (ad_year < GREGORIAN_YEAR
+          || date.era.contains(&AdDate::new(ad_year, month, 1))
+          || date
+            .era
+            .contains(&AdDate::new(ad_year, month, days_in_month(ad_year, month))))
+    }
+    (Some(month), Some(day)) => {
+      (1..=12).contains(&month)
+        && 1 <= day
+        && day <= days_in_month(ad_year, month)
+        && (ad_year < GREGORIAN_YEAR || date.era.contains(&AdDate::new(ad_year, month, day)))
+    }
+    (None, Some(_)) => false,
+  }
+}
+
+/// 和暦の日付を西暦の"YYYY-MM-DD"形式の文字列にする
+///
+/// 月や日が無い場合は"YYYY"や"YYYY-MM"のように、ある部分までを書く
+pub fn to_iso8601(date: &Date) -> String {
+  let year = era_to_ad(&date.era, date.year);
+  match (date.month, date.day) {
+    (Some(month), Some(day)) => format!("{year:04}-{month:02}-{day:02}"),
+    (Some(month), None) => format!("{year:04}-{month:02}"),
+    _ => format!("{year:04}"),
+  }
+}
+
+/// "平成十五年五月三十日"のような和暦の日付に合う正規表現
+///
+/// 元号・年・月・日をそれぞれ`era`・`year`・`month`・`day`として取り出す。月と日は省略できる
+pub(crate) const WAREKI_DATE_PATTERN: &str = r"(?P<era>明治|大正|昭和|平成|令和)(?P<year>[元〇一二三四五六七八九十百千\d０-９]+)年(?:(?P<month>[〇一二三四五六七八九十\d０-９]+)月)?(?:(?P<day>[〇一二三四五六七八九十\d０-９]+)日)?";
+
+/// 和暦の日付（`Date`）の変換
+///
+/// `Date`は他のcrateの型なので、このtraitを通してメソッドとして呼べるようにする
+pub trait DateExt: Sized {
+  /// 西暦の"YYYY-MM-DD"形式の文字列にする（`to_iso8601`と同じ）
+  fn to_iso8601(&self) -> String;
+  /// "令和5年6月16日"のような和暦の文字列にする
+  ///
+  /// 1年は"元年"と書き、月や日が無い場合はある部分までを書く
+  fn to_wareki_string(&self) -> String;
+  /// "令和5年6月16日"や"平成十五年五月三十日"のような和暦の文字列を読む
+  ///
+  /// 年月日は漢数字でも算用数字でもよく、月や日は省略できる。
+  /// 文字列全体が和暦の日付でない場合や、実在しない日付の場合はエラーを返す
+  fn from_wareki_str(s: &str) -> Result<Self>;
+  /// chronoの日付にする
+  ///
+  /// 月や日が無い場合や、実在しない日付の場合はエラーを返す。
+  /// 明治5年以前の月日は太陰太陽暦のものだが（`calendar_of`）、グレゴリオ暦の月日としてそのまま扱う
+  #[cfg(feature = "full")]
+  fn to_chrono(&self) -> Result<chrono::NaiveDate>;
+  /// chronoの日付から作る
+  ///
+  /// 明治より前の日付の場合はエラーを返す
+  #[cfg(feature = "full")]
+  fn from_chrono(date: chrono::NaiveDate) -> Result<Self>;
+}
+
+impl DateExt for Date {
+  fn to_iso8601(&self) -> String {
+    to_iso8601(self)
+  }
+
+  fn to_wareki_string(&self) -> String {
+    let mut s = self.era.kanji().to_string();
+    if self.year == 1 {
+      s.push_str("元年");
+    } else {
+      s.push_str(&format!("{}年", self.year));
+    }
+    if let Some(month) = self.month {
+      s.push_str(&format!("{month}月"));
+      if let Some(day) = self.day {
+        s.push_str(&format!("{day}日"));
+      }
+    }
+    s
+  }
+
+  fn from_wareki_str(s: &str) -> Result<Self> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(&format!("^{WAREKI_DATE_PATTERN}$")).unwrap());
+    let err = || anyhow!("cannot parse wareki date: {s}");
+    let caps = re.captures(s.trim()).ok_or_else(err)?;
+    let era = era_from_kanji(&caps["era"]).ok_or_else(err)?;
+    let year = parse_kansuji(&caps["year"]).ok_or_else(err)?;
+    let number = |name: &str| match caps.name(name) {
+      Some(m) => parse_kansuji(m.as_str()).map(Some).ok_or_else(err),
+      None => Ok(None),
+    };
+    let date = Date::new(era, year, number("month")?, number("day")?);
+    if !is_valid_date(&date) {
+      return Err(anyhow!("invalid date: {s}"));
+    }
+    Ok(date)
+  }
+
+  #[cfg(feature = "full")]
+  fn to_chrono(&self) -> Result<chrono::NaiveDate> {
+    let (Some(month), Some(day)) = (self.month, self.day) else {
+      return Err(anyhow!(
+        "date without month or day cannot be converted: {}",
+        to_iso8601(self)
+      ));
+    };
+    let ad = try_date_to_ad(self)?;
+    chrono::NaiveDate::from_ymd_opt(ad.year as i32, month as u32, day as u32)
+      .ok_or(anyhow!("invalid date: {}", to_iso8601(self)))
+  }
+
+  #[cfg(feature = "full")]
+  fn from_chrono(date: chrono::NaiveDate) -> Result<Self> {
+    use chrono::Datelike;
+    let ad = AdDate::new(
+      usize::try_from(date.year()).map_err(|_| anyhow!("date before Meiji: {date}"))?,
+      date.month() as usize,
+      date.day() as usize,
+    );
+    date_from_ad(&ad)
+  }
+}
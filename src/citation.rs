@@ -0,0 +1,231 @@
+//! "民法第九十条"のような文中の引用を、法令IDと条などの位置に解決する
+
+use crate::index::LawIndex;
+use crate::kansuji::parse_kansuji;
+use jplaw_data_types::law::LawId;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 条・項・号の位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArticleRef {
+  /// 条の番号
+  pub article: usize,
+  /// "第九十条の二の三"のような枝番号（この場合は`[2, 3]`）
+  pub sub_articles: Vec<usize>,
+  /// 項の番号
+  pub paragraph: Option<usize>,
+  /// 号の番号
+  pub item: Option<usize>,
+}
+
+/// 解決した引用
+#[derive(Debug, Clone)]
+pub struct Citation {
+  /// 引用された法令の法令ID
+  pub id: LawId,
+  /// 引用された条などの位置（法令全体を引用している場合は`None`）
+  pub article: Option<ArticleRef>,
+}
+
+/// 引用のうち、法令を指す部分とそれに続く部分に分ける
+///
+/// 法令番号で始まる場合はその法令番号を、それ以外の場合は"第"の前までを法令を指す部分とする。
+/// 法令名の後ろの"（明治二十九年法律第八十九号）"のような括弧書きは取り除く
+fn split_law_part(text: &str) -> Option<(&str, &str)> {
+  static NUM_RE: OnceLock<Regex> = OnceLock::new();
+  static NAME_RE: OnceLock<Regex> = OnceLock::new();
+  let num_re = NUM_RE.get_or_init(|| {
+    Regex::new(r"^(?P<law>(?:明治|大正|昭和|平成|令和)[^年]+年[^第]+第[^号]+号)(?P<rest>.*)$")
+      .unwrap()
+  });
+  let name_re = NAME_RE
+    .get_or_init(|| Regex::new(r"^(?P<law>[^第（(]+)(?:[（(][^）)]*[）)])?(?P<rest>.*)$").unwrap());
+  let caps = num_re.captures(text).or_else(|| name_re.captures(text))?;
+  Some((caps.name("law")?.as_str(), caps.name("rest")?.as_str()))
+}
+
+/// "第九十条の二第一項第三号"のような条などの位置を読み取る
+fn parse_article_ref(text: &str) -> Option<ArticleRef> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| {
+    let num = r"[〇一二三四五六七八九十百千\d０-９]+";
+    Regex::new(&format!(
+      r"^第(?P<article>{num})条(?P<subs>(?:の{num})*)(?:第(?P<paragraph>{num})項)?(?:第(?P<item>{num})号)?"
+    ))
+    .unwrap()
+  });
+  let caps = re.captures(text)?;
+  Some(ArticleRef {
+    article: parse_kansuji(&caps["article"])?,
+    sub_articles: caps["subs"]
+      .split('の')
+      .filter(|s| !s.is_empty())
+      .map(parse_kansuji)
+      .collect::<Option<Vec<_>>>()?,
+    paragraph: match caps.name("paragraph") {
+      Some(m) => Some(parse_kansuji(m.as_str())?),
+      None => None,
+    },
+    item: match caps.name("item") {
+      Some(m) => Some(parse_kansuji(m.as_str())?),
+      None => None,
+    },
+  })
+}
+
+/// "民法第九十条"や"平成十五年法律第五十七号第二条第一項"のような引用を解決する
+///
+/// 法令を指す部分は`index`の法令名・略称・通称・法令番号から探す。
+/// 法令が見つからない場合や、条などの位置が読み取れない場合は`None`を返す
+pub fn resolve_citation(index: &LawIndex, text: &str) -> Option<Citation> {
+  let text = text.trim();
+  let (law, rest) = split_law_part(text)?;
+  let id = index.lookup(law.trim())?.clone();
+  let rest = rest.trim();
+  let article = if rest.is_empty() {
+    None
+  } else {
+    Some(parse_article_ref(rest)?)
+  };
+  Some(Citation { id, article })
+}
+
+/// 法令XMLの本文に現れる法令番号のうち、`index`で法令IDに解決できるものを返す
+///
+/// 法令自身の法令番号（`self_id`に解決されるもの）は含めない。同じ法令が何度引用されても1回だけ返す
+pub fn cited_laws(index: &LawIndex, xml: &str, self_id: &LawId) -> Vec<LawId> {
+  static TAG_RE: OnceLock<Regex> = OnceLock::new();
+  static NUM_RE: OnceLock<Regex> = OnceLock::new();
+  let tag_re = TAG_RE.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+  let num_re = NUM_RE.get_or_init(|| {
+    Regex::new(r"(?:明治|大正|昭和|平成|令和)[元〇一二三四五六七八九十百千]+年[^年第（）(),、。\s]{1,20}?第[〇一二三四五六七八九十百千]+号")
+      .unwrap()
+  });
+  let text = tag_re.replace_all(xml, "");
+  let mut ids: Vec<LawId> = Vec::new();
+  for m in num_re.find_iter(&text) {
+    if let Some(id) = index.by_num(m.as_str()) {
+      if id != self_id && !ids.contains(id) {
+        ids.push(id.clone());
+      }
+    }
+  }
+  ids
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::law;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn index() -> (LawIndex, Vec<LawId>) {
+    let mut privacy = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    privacy.abbrev = vec!["個人情報保護法".to_string()];
+    let mut civil = law(CIVIL_CODE_ID, "民法");
+    civil.num = "明治二十九年法律第八十九号".to_string();
+    let laws = vec![privacy, civil];
+    let ids = laws.iter().map(|law| law.id.clone()).collect();
+    (LawIndex::new(&laws), ids)
+  }
+
+  fn article(article: usize, sub_articles: Vec<usize>) -> ArticleRef {
+    ArticleRef {
+      article,
+      sub_articles,
+      paragraph: None,
+      item: None,
+    }
+  }
+
+  #[test]
+  fn resolves_citations() {
+    let (index, ids) = index();
+    let resolve = |text: &str| {
+      resolve_citation(&index, text).map(|citation| (citation.id.to_string(), citation.article))
+    };
+
+    assert_eq!(
+      resolve("民法第九十条"),
+      Some((CIVIL_CODE_ID.to_string(), Some(article(90, vec![]))))
+    );
+    assert_eq!(
+      resolve("民法（明治二十九年法律第八十九号）第九十条の二の三第一項第三号"),
+      Some((
+        CIVIL_CODE_ID.to_string(),
+        Some(ArticleRef {
+          paragraph: Some(1),
+          item: Some(3),
+          ..article(90, vec![2, 3])
+        })
+      ))
+    );
+    assert_eq!(
+      resolve("平成十五年法律第五十七号第二条"),
+      Some((PRIVACY_ACT_ID.to_string(), Some(article(2, vec![]))))
+    );
+    assert_eq!(
+      resolve("個人情報保護法第十六条"),
+      Some((PRIVACY_ACT_ID.to_string(), Some(article(16, vec![]))))
+    );
+    assert_eq!(resolve(" 民法 "), Some((ids[1].to_string(), None)));
+    // 同梱している通称でも引ける
+    assert_eq!(
+      resolve("刑法第百九十九条"),
+      Some(("140AC0000000045".to_string(), Some(article(199, vec![]))))
+    );
+    assert_eq!(resolve("存在しない法第一条"), None);
+    assert_eq!(resolve("民法第一項"), None);
+  }
+
+  #[test]
+  fn resolves_arabic_numerals_and_half_width_brackets() {
+    let (index, _) = index();
+    let resolve = |text: &str| {
+      resolve_citation(&index, text).map(|citation| (citation.id.to_string(), citation.article))
+    };
+
+    assert_eq!(
+      resolve("民法第90条"),
+      Some((CIVIL_CODE_ID.to_string(), Some(article(90, vec![]))))
+    );
+    assert_eq!(
+      resolve("民法(明治二十九年法律第八十九号)第９０条の２第1項"),
+      Some((
+        CIVIL_CODE_ID.to_string(),
+        Some(ArticleRef {
+          paragraph: Some(1),
+          ..article(90, vec![2])
+        })
+      ))
+    );
+    // 項を飛ばして号だけを指すこともある
+    assert_eq!(
+      resolve("民法第九十条第三号"),
+      Some((
+        CIVIL_CODE_ID.to_string(),
+        Some(ArticleRef {
+          item: Some(3),
+          ..article(90, vec![])
+        })
+      ))
+    );
+    assert_eq!(resolve(""), None);
+    assert_eq!(resolve("第九十条"), None);
+  }
+
+  #[test]
+  fn lists_cited_laws_once() {
+    let (index, ids) = index();
+    let xml = "<Sentence>民法（明治二十九年法律第八十九号）及び個人情報の保護に関する法律（平成十五年法律第五十七号）</Sentence>\
+      <Sentence>明治二十九年<Ruby>法律</Ruby>第八十九号及び平成元年法律第一号</Sentence>";
+
+    let cited = cited_laws(&index, xml, &ids[0]);
+    assert_eq!(cited, [ids[1].clone()]);
+    let cited = cited_laws(&index, xml, &ids[1]);
+    assert_eq!(cited, [ids[0].clone()]);
+  }
+}
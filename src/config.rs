@@ -0,0 +1,220 @@
+//! `--config`で指定したTOMLファイルに書いた設定を、コマンドライン引数として展開する
+//!
+//! ```toml
+//! work = "data/法令データ一式"
+//! output = ["all.json", "heisei.csv,format=csv,era=Heisei"]
+//! jobs = 8
+//! skip-errors = true
+//! ```
+//!
+//! キーはコマンドラインのオプションの名前（`--`を除いたもの、`_`は`-`として扱う）とする。
+//! コマンドラインで指定したオプションは設定ファイルより優先する
+
+use anyhow::{anyhow, Result};
+use clap::Command;
+use std::path::Path;
+
+/// 設定ファイルへのpathを指定するオプション
+pub const CONFIG_FLAG: &str = "--config";
+
+/// コマンドライン引数から`--config`の値を探す
+pub fn find_config_path(args: &[String]) -> Option<&str> {
+  let mut iter = args.iter();
+  while let Some(arg) = iter.next() {
+    if arg == CONFIG_FLAG {
+      return iter.next().map(String::as_str);
+    }
+    if let Some(path) = arg.strip_prefix("--config=") {
+      return Some(path);
+    }
+  }
+  None
+}
+
+/// コマンドラインでそのオプションが指定されているかどうか
+fn given_on_command_line(args: &[String], long: &str, short: Option<char>) -> bool {
+  let long_flag = format!("--{long}");
+  let long_prefix = format!("--{long}=");
+  let short_flag = short.map(|c| format!("-{c}"));
+  args.iter().any(|arg| {
+    *arg == long_flag
+      || arg.starts_with(&long_prefix)
+      || matches!(&short_flag, Some(flag) if arg.starts_with(flag.as_str()))
+  })
+}
+
+/// TOMLの値をオプションの値の文字列にする
+fn value_to_strings(key: &str, value: &toml::Value) -> Result<Vec<String>> {
+  match value {
+    toml::Value::String(s) => Ok(vec![s.clone()]),
+    toml::Value::Integer(n) => Ok(vec![n.to_string()]),
+    toml::Value::Float(n) => Ok(vec![n.to_string()]),
+    toml::Value::Array(values) => {
+      let mut lst = Vec::new();
+      for value in values {
+        lst.extend(value_to_strings(key, value)?);
+      }
+      Ok(lst)
+    }
+    _ => Err(anyhow!("unsupported value in config: {key}")),
+  }
+}
+
+/// 設定ファイルの中身を、`cmd`のオプションとしてのコマンドライン引数にする
+///
+/// `args`で既に指定されているオプションは含めない
+pub fn config_to_args(config: &str, args: &[String], cmd: &Command) -> Result<Vec<String>> {
+  let table = config.parse::<toml::Table>()?;
+  let mut expanded = Vec::new();
+  for (key, value) in &table {
+    let long = key.replace('_', "-");
+    let arg = cmd
+      .get_arguments()
+      .find(|arg| arg.get_long() == Some(long.as_str()))
+      .ok_or(anyhow!("unknown option in config: {key}"))?;
+    if long == "config" || given_on_command_line(args, &long, arg.get_short()) {
+      continue;
+    }
+    match value {
+      toml::Value::Boolean(true) => expanded.push(format!("--{long}")),
+      toml::Value::Boolean(false) => (),
+      _ => {
+        for s in value_to_strings(key, value)? {
+          expanded.push(format!("--{long}"));
+          expanded.push(s);
+        }
+      }
+    }
+  }
+  Ok(expanded)
+}
+
+/// `--config`が指定されている場合、設定ファイルの中身をプログラム名の直後に差し込んだコマンドライン引数を返す
+pub fn expand_config_args(args: Vec<String>, cmd: &Command) -> Result<Vec<String>> {
+  let Some(path) = find_config_path(&args) else {
+    return Ok(args);
+  };
+  let config = std::fs::read_to_string(Path::new(path))
+    .map_err(|e| anyhow!("cannot read config file {path}: {e}"))?;
+  let expanded = config_to_args(&config, &args, cmd)?;
+  let mut iter = args.into_iter();
+  Ok(
+    iter
+      .next()
+      .into_iter()
+      .chain(expanded)
+      .chain(iter)
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::{Arg, ArgAction};
+
+  fn command() -> Command {
+    Command::new("listup_law")
+      .arg(Arg::new("config").long("config"))
+      .arg(Arg::new("work").long("work"))
+      .arg(Arg::new("output").short('o').long("output"))
+      .arg(Arg::new("jobs").short('j').long("jobs"))
+      .arg(
+        Arg::new("skip-errors")
+          .long("skip-errors")
+          .action(ArgAction::SetTrue),
+      )
+      .arg(Arg::new("fast").long("fast").action(ArgAction::SetTrue))
+  }
+
+  fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+  }
+
+  #[test]
+  fn finds_config_path() {
+    assert_eq!(
+      find_config_path(&args(&["listup_law", "--config", "a.toml"])),
+      Some("a.toml")
+    );
+    assert_eq!(
+      find_config_path(&args(&["listup_law", "--config=b.toml", "--fast"])),
+      Some("b.toml")
+    );
+    assert_eq!(find_config_path(&args(&["listup_law", "--fast"])), None);
+  }
+
+  #[test]
+  fn expands_config_into_args() {
+    let config = r#"
+      fast = false
+      jobs = 8
+      output = ["all.json", "heisei.csv,format=csv"]
+      skip_errors = true
+      work = "data"
+    "#;
+    // コマンドラインで指定したオプションは設定ファイルより優先する
+    let expanded = config_to_args(config, &args(&["listup_law", "-j2"]), &command()).unwrap();
+    assert_eq!(
+      expanded,
+      args(&[
+        "--output",
+        "all.json",
+        "--output",
+        "heisei.csv,format=csv",
+        "--skip-errors",
+        "--work",
+        "data",
+      ])
+    );
+    let expanded = config_to_args(config, &args(&["listup_law", "--work=other"]), &command());
+    assert!(!expanded.unwrap().contains(&"data".to_string()));
+
+    assert!(config_to_args("color = \"red\"", &[], &command()).is_err());
+    assert!(config_to_args("work = { path = \"data\" }", &[], &command()).is_err());
+  }
+
+  #[test]
+  fn inserts_config_args_after_program_name() {
+    let args = args(&["listup_law", "--fast"]);
+    assert_eq!(expand_config_args(args.clone(), &command()).unwrap(), args);
+    let missing = vec![
+      "listup_law".to_string(),
+      "--config".to_string(),
+      "/nonexistent/listup_law.toml".to_string(),
+    ];
+    assert!(expand_config_args(missing, &command()).is_err());
+
+    let path = std::env::temp_dir().join(format!("listup_law_config_{}.toml", std::process::id()));
+    std::fs::write(
+      &path,
+      "config = \"other.toml\"\njobs = 8\noutput = \"all.json\"\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap().to_string();
+    let expanded = expand_config_args(
+      vec![
+        "listup_law".to_string(),
+        "--config".to_string(),
+        path.clone(),
+        "-o".to_string(),
+        "mine.json".to_string(),
+      ],
+      &command(),
+    );
+    std::fs::remove_file(&path).unwrap();
+    // 設定ファイルの中の`config`は読まず、短い名前で指定したオプションも優先する
+    assert_eq!(
+      expanded.unwrap(),
+      [
+        "listup_law",
+        "--jobs",
+        "8",
+        "--config",
+        path.as_str(),
+        "-o",
+        "mine.json",
+      ]
+    );
+  }
+}
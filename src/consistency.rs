@@ -0,0 +1,176 @@
+//! 法令ID・法令番号・`Law`タグの属性の3か所に書かれた元号と年を突き合わせる
+//!
+//! 元データではこの3か所が食い違っていることがある。食い違っている場合は、
+//! 出力する公布の日付には法令番号から読み取った値を優先して使い、法令番号から読み取れない場合は
+//! `Law`タグの属性の値を使う。法令番号は公布された文面そのものであり、属性と法令IDは
+//! そこから機械的に作られたものであるため。法令IDは突き合わせにだけ使う。
+
+use crate::calendar::{era_from_attr, era_from_law_id_code, EraExt};
+use crate::record::PatchRecord;
+use crate::repair::parse_law_num_date;
+use japanese_law_xml_schema::law::Era;
+use jplaw_data_types::law::Date;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 元号と年が書かれている場所
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EraSource {
+  /// 法令番号の先頭
+  LawNum,
+  /// `Law`タグの`Era`属性と`Year`属性
+  Attr,
+}
+
+/// 元号（XMLの`Era`属性での表記）と年
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraYear {
+  pub era: String,
+  pub year: usize,
+}
+
+impl EraYear {
+  fn new(era: &Era, year: usize) -> Self {
+    EraYear {
+      era: era.attr().to_string(),
+      year,
+    }
+  }
+}
+
+/// 元号と年の食い違い
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraInconsistency {
+  /// 法令ID
+  pub id: String,
+  /// 食い違いが見つかったXMLファイルへのpath
+  pub file: String,
+  /// 法令番号から読み取った値（読み取れない場合は`None`）
+  pub law_num: Option<EraYear>,
+  /// `Law`タグの属性の値
+  pub attr: EraYear,
+  /// 法令IDから読み取った値（読み取れない場合は`None`）
+  pub law_id: Option<EraYear>,
+  /// 出力に使った値の場所
+  pub chosen: EraSource,
+}
+
+impl std::fmt::Display for EraYear {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} {}", self.era, self.year)
+  }
+}
+
+impl std::fmt::Display for EraInconsistency {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let show = |v: &Option<EraYear>| v.as_ref().map_or("-".to_string(), |v| v.to_string());
+    write!(
+      f,
+      "{}: Law tag says {}, LawNum says {}, law id says {} in {}",
+      self.id,
+      self.attr,
+      show(&self.law_num),
+      show(&self.law_id),
+      self.file
+    )
+  }
+}
+
+/// 法令IDの先頭3文字から元号と年を読み取る
+pub fn era_year_of_law_id(id: &str) -> Option<(Era, usize)> {
+  let mut chars = id.chars();
+  let era = era_from_law_id_code(chars.next()?)?;
+  let year = id.get(1..3)?.parse().ok()?;
+  Some((era, year))
+}
+
+/// 3か所の元号と年を突き合わせ、食い違っている場合は優先順位に従って`date`の元号と年を書き換える
+///
+/// 公布の月日は書き換えない。食い違いが無い場合は`None`を返す
+pub fn check_era(id: &str, law_num: &str, date: &mut Date, file: &str) -> Option<EraInconsistency> {
+  let attr = EraYear::new(&date.era, date.year);
+  let from_num = parse_law_num_date(law_num).map(|d| EraYear::new(&d.era, d.year));
+  let from_id = era_year_of_law_id(id).map(|(era, year)| EraYear::new(&era, year));
+  let agrees = |other: &Option<EraYear>| !matches!(other, Some(v) if *v != attr);
+  if agrees(&from_num) && agrees(&from_id) {
+    return None;
+  }
+  let chosen = match &from_num {
+    Some(v) => {
+      if let Some(era) = era_from_attr(&v.era) {
+        date.era = era;
+        date.year = v.year;
+      }
+      EraSource::LawNum
+    }
+    None => EraSource::Attr,
+  };
+  Some(EraInconsistency {
+    id: id.to_string(),
+    file: file.to_string(),
+    law_num: from_num,
+    attr,
+    law_id: from_id,
+    chosen,
+  })
+}
+
+/// 同じ法令IDを持つXMLファイルの間での法令名・法令番号の食い違い
+///
+/// 法令名は改正で変わることがあるので、同じ改正の日付と改正法令の版どうしでだけ比べる。
+/// 法令番号は改正で変わらないので、すべての版の間で比べる
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LawConflict {
+  /// 法令ID
+  pub id: String,
+  /// 後から読んだ、食い違っているXMLファイルへのpath
+  pub file: String,
+  /// 食い違っている項目（`name`か`num`）
+  pub field: String,
+  /// 先に読んだXMLファイルの値
+  pub existing: String,
+  /// 後から読んだXMLファイルの値
+  pub conflicting: String,
+}
+
+/// 読み込んだ法令にも法令の一覧にも無い法令を指している改正法令の法令ID
+///
+/// ダウンロードが途中で切れて、改正法令のXMLファイルが欠けている場合に起きることが多い
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DanglingPatch {
+  /// 改正された法令の法令ID
+  pub id: String,
+  /// 改正を反映したXMLファイルへのpath
+  pub file: String,
+  /// 見つからなかった改正法令の法令ID
+  pub patch_id: String,
+}
+
+impl std::fmt::Display for DanglingPatch {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}: amending law {} is not found in {}",
+      self.id, self.patch_id, self.file
+    )
+  }
+}
+
+/// 改正法令の法令IDが`known`（読み込んだ法令と法令の一覧の法令ID）に無い改正を探す
+pub fn find_dangling_patches<'a>(
+  patches: impl IntoIterator<Item = &'a PatchRecord>,
+  known: &HashSet<String>,
+) -> Vec<DanglingPatch> {
+  patches
+    .into_iter()
+    .filter_map(|patch| {
+      let patch_id = patch.info.patch_id.as_ref()?.to_string();
+      (!known.contains(&patch_id)).then(|| DanglingPatch {
+        id: patch.info.id.to_string(),
+        file: patch.file.clone(),
+        patch_id,
+      })
+    })
+    .collect()
+}
@@ -0,0 +1,331 @@
+//! 同じ法令についての2つの情報を比べ、変わった項目と増減した改正を求める
+
+use crate::calendar::to_iso8601;
+use crate::record::LawRecord;
+use jplaw_data_types::{law::LawPatchInfo, listup::LawInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 変わった項目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+  /// 項目名（`id`・`name`・`num`・`date`）
+  pub field: String,
+  /// 変更前の値
+  pub old: String,
+  /// 変更後の値
+  pub new: String,
+}
+
+/// 2つの法令の情報の差分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawInfoDiff {
+  /// 変更後の法令ID
+  pub id: String,
+  /// 変わった項目
+  pub changes: Vec<FieldChange>,
+  /// 変更後にだけある改正
+  pub added_patches: Vec<LawPatchInfo>,
+  /// 変更前にだけある改正
+  pub removed_patches: Vec<LawPatchInfo>,
+}
+
+impl LawInfoDiff {
+  /// 差分が無いかどうか
+  pub fn is_empty(&self) -> bool {
+    self.changes.is_empty() && self.added_patches.is_empty() && self.removed_patches.is_empty()
+  }
+}
+
+/// 改正を同一視するための鍵（改正の日付と改正法令の法令ID）
+fn patch_key(patch: &LawPatchInfo) -> (String, Option<String>) {
+  (
+    to_iso8601(&patch.patch_date),
+    patch.patch_id.as_ref().map(|id| id.to_string()),
+  )
+}
+
+fn diff_fields(old: [(&str, String); 4], new: [(&str, String); 4]) -> Vec<FieldChange> {
+  old
+    .into_iter()
+    .zip(new)
+    .filter(|((_, old), (_, new))| old != new)
+    .map(|((field, old), (_, new))| FieldChange {
+      field: field.to_string(),
+      old,
+      new,
+    })
+    .collect()
+}
+
+fn diff_patches(
+  old: &[&LawPatchInfo],
+  new: &[&LawPatchInfo],
+) -> (Vec<LawPatchInfo>, Vec<LawPatchInfo>) {
+  let old_keys = old.iter().map(|p| patch_key(p)).collect::<Vec<_>>();
+  let new_keys = new.iter().map(|p| patch_key(p)).collect::<Vec<_>>();
+  let added = new
+    .iter()
+    .zip(&new_keys)
+    .filter(|(_, key)| !old_keys.contains(key))
+    .map(|(p, _)| (*p).clone())
+    .collect();
+  let removed = old
+    .iter()
+    .zip(&old_keys)
+    .filter(|(_, key)| !new_keys.contains(key))
+    .map(|(p, _)| (*p).clone())
+    .collect();
+  (added, removed)
+}
+
+/// 法令の情報同士の差分を求める
+pub trait LawDiffExt {
+  /// `self`を変更前、`other`を変更後として差分を求める
+  fn diff(&self, other: &Self) -> LawInfoDiff;
+}
+
+impl LawDiffExt for LawInfo {
+  fn diff(&self, other: &Self) -> LawInfoDiff {
+    let fields = |law: &LawInfo| {
+      [
+        ("id", law.id.to_string()),
+        ("name", law.name.clone()),
+        ("num", law.num.clone()),
+        ("date", to_iso8601(&law.date)),
+      ]
+    };
+    let (added_patches, removed_patches) = diff_patches(
+      &self.patch.iter().collect::<Vec<_>>(),
+      &other.patch.iter().collect::<Vec<_>>(),
+    );
+    LawInfoDiff {
+      id: other.id.to_string(),
+      changes: diff_fields(fields(self), fields(other)),
+      added_patches,
+      removed_patches,
+    }
+  }
+}
+
+impl LawDiffExt for LawRecord {
+  fn diff(&self, other: &Self) -> LawInfoDiff {
+    let fields = |law: &LawRecord| {
+      [
+        ("id", law.id.to_string()),
+        ("name", law.name.clone()),
+        ("num", law.num.clone()),
+        (
+          "date",
+          to_iso8601(law.promulgation_date.as_ref().unwrap_or(&law.date)),
+        ),
+      ]
+    };
+    let (added_patches, removed_patches) = diff_patches(
+      &self.patch.iter().map(|p| &p.info).collect::<Vec<_>>(),
+      &other.patch.iter().map(|p| &p.info).collect::<Vec<_>>(),
+    );
+    LawInfoDiff {
+      id: other.id.to_string(),
+      changes: diff_fields(fields(self), fields(other)),
+      added_patches,
+      removed_patches,
+    }
+  }
+}
+
+/// 2つの法令の一覧の差分
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LawListDiff {
+  /// 新しい一覧にだけある法令の法令ID
+  pub added: Vec<String>,
+  /// 古い一覧にだけある法令の法令ID
+  pub removed: Vec<String>,
+  /// 両方にあり、内容が変わった法令
+  pub changed: Vec<LawInfoDiff>,
+}
+
+impl LawListDiff {
+  /// 法令名が変わった法令の差分
+  pub fn renamed(&self) -> impl Iterator<Item = (&LawInfoDiff, &FieldChange)> {
+    self.changed.iter().filter_map(|diff| {
+      diff
+        .changes
+        .iter()
+        .find(|change| change.field == "name")
+        .map(|change| (diff, change))
+    })
+  }
+}
+
+/// 2つの法令の一覧を比べる
+///
+/// 結果は法令IDの順に並べる
+pub fn diff_law_lists(old: &[LawRecord], new: &[LawRecord]) -> LawListDiff {
+  let old_map = old
+    .iter()
+    .map(|law| (law.id.to_string(), law))
+    .collect::<HashMap<_, _>>();
+  let new_map = new
+    .iter()
+    .map(|law| (law.id.to_string(), law))
+    .collect::<HashMap<_, _>>();
+  let mut diff = LawListDiff::default();
+  for (id, new_law) in &new_map {
+    match old_map.get(id) {
+      None => diff.added.push(id.clone()),
+      Some(old_law) => {
+        let law_diff = old_law.diff(new_law);
+        if !law_diff.is_empty() {
+          diff.changed.push(law_diff);
+        }
+      }
+    }
+  }
+  for id in old_map.keys() {
+    if !new_map.contains_key(id) {
+      diff.removed.push(id.clone());
+    }
+  }
+  diff.added.sort();
+  diff.removed.sort();
+  diff.changed.sort_by(|a, b| a.id.cmp(&b.id));
+  diff
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::{date, law, patch};
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const PRIVACY_ORDER_ID: &str = "415CO0000000507";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  #[test]
+  fn diffs_fields_and_patches() {
+    let old = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    let mut new = old.clone();
+    new.name = "個人情報保護法".to_string();
+    new.patch = vec![patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    )];
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.id, PRIVACY_ACT_ID);
+    assert_eq!(
+      diff.changes,
+      [FieldChange {
+        field: "name".to_string(),
+        old: "個人情報の保護に関する法律".to_string(),
+        new: "個人情報保護法".to_string(),
+      }]
+    );
+    let dates = |patches: &[LawPatchInfo]| {
+      patches
+        .iter()
+        .map(|p| to_iso8601(&p.patch_date))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(dates(&diff.added_patches), ["2017-05-30"]);
+    assert_eq!(dates(&diff.removed_patches), ["2003-05-30"]);
+    assert!(old.diff(&old).is_empty());
+
+    // 公布の年月日が変わった場合は`date`の差分とする
+    let mut moved = old.clone();
+    moved.promulgation_date = Some(date("Heisei", 15, 5, 23));
+    let fields = old
+      .diff(&moved)
+      .changes
+      .into_iter()
+      .map(|change| (change.field, change.new))
+      .collect::<Vec<_>>();
+    assert_eq!(fields, [("date".to_string(), "2003-05-23".to_string())]);
+  }
+
+  #[test]
+  fn identifies_patches_by_date_and_patch_id() {
+    let mut old = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    old.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    // XMLファイルの場所が変わっただけでは差分にしない
+    let mut moved = old.clone();
+    moved.patch[1].file = "elsewhere.xml".to_string();
+    assert!(old.diff(&moved).is_empty());
+
+    // 同じ日付でも改正法令が違えば別の改正とする
+    let mut new = old.clone();
+    new.patch[1] = patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("428AC0000000051"),
+    );
+    let diff = old.diff(&new);
+    assert!(diff.changes.is_empty());
+    let patch_ids = |patches: &[LawPatchInfo]| {
+      patches
+        .iter()
+        .map(|p| p.patch_id.as_ref().map(|id| id.to_string()))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      patch_ids(&diff.added_patches),
+      [Some("428AC0000000051".to_string())]
+    );
+    assert_eq!(
+      patch_ids(&diff.removed_patches),
+      [Some("427AC0000000065".to_string())]
+    );
+  }
+
+  #[test]
+  fn diffs_law_info_like_law_records() {
+    let old = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    let mut new = old.clone();
+    new.num = "平成十五年法律第五十八号".to_string();
+    new.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    let diff = LawInfo::from(old).diff(&LawInfo::from(new));
+    let fields = diff
+      .changes
+      .iter()
+      .map(|change| change.field.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(fields, ["num"]);
+    assert_eq!(diff.added_patches.len(), 1);
+    assert!(diff.removed_patches.is_empty());
+  }
+
+  #[test]
+  fn diffs_law_lists_in_id_order() {
+    let old = vec![
+      law(PRIVACY_ORDER_ID, "施行令"),
+      law(PRIVACY_ACT_ID, "個人情報の保護に関する法律"),
+      law(CIVIL_CODE_ID, "民法"),
+    ];
+    let new = vec![
+      law("345AC0000000048", "著作権法"),
+      law(PRIVACY_ACT_ID, "個人情報保護法"),
+      law(CIVIL_CODE_ID, "民法"),
+      law("140AC0000000045", "刑法"),
+    ];
+    let diff = diff_law_lists(&old, &new);
+
+    assert_eq!(diff.added, ["140AC0000000045", "345AC0000000048"]);
+    assert_eq!(diff.removed, [PRIVACY_ORDER_ID]);
+    assert_eq!(diff.changed.len(), 1);
+    let renamed = diff
+      .renamed()
+      .map(|(law, change)| (law.id.as_str(), change.new.as_str()))
+      .collect::<Vec<_>>();
+    assert_eq!(renamed, [(PRIVACY_ACT_ID, "個人情報保護法")]);
+  }
+}
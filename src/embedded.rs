@@ -0,0 +1,36 @@
+//! ビルド時に埋め込んだ法令の一覧を使う
+//!
+//! `embedded-index` featureを有効にしてビルドするときに、環境変数`LISTUP_LAW_EMBEDDED_INDEX`に
+//! このソフトウェアで書き出したJSON形式の法令の一覧への絶対pathを指定する。
+//! バイナリが大きくなるため、`--shard`などで絞り込んだ小さな一覧を埋め込むことを想定している。
+
+use crate::record::{laws_from_json, LawRecord};
+use std::sync::OnceLock;
+
+static EMBEDDED_INDEX: &[u8] = include_bytes!(env!("LISTUP_LAW_EMBEDDED_INDEX"));
+
+/// 埋め込んだ法令の一覧
+///
+/// 埋め込んだファイルが法令の一覧として読み込めない場合はpanicする
+pub fn embedded_laws() -> &'static [LawRecord] {
+  static LAWS: OnceLock<Vec<LawRecord>> = OnceLock::new();
+  LAWS.get_or_init(|| {
+    std::str::from_utf8(EMBEDDED_INDEX)
+      .ok()
+      .and_then(|s| laws_from_json(s).ok())
+      .expect("embedded index is not a valid law list")
+  })
+}
+
+/// 埋め込んだ法令の一覧から法令IDで法令を探す
+pub fn find_embedded_law(id: &str) -> Option<&'static LawRecord> {
+  embedded_laws().iter().find(|law| law.id.to_string() == id)
+}
+
+/// 埋め込んだ法令の一覧から、法令名に`query`を含む法令を探す
+pub fn search_embedded_laws(query: &str) -> Vec<&'static LawRecord> {
+  embedded_laws()
+    .iter()
+    .filter(|law| law.name.contains(query))
+    .collect()
+}
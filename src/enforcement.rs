@@ -0,0 +1,129 @@
+//! 附則（`SupplProvision`）の文言から施行日を読み取る
+//!
+//! XMLのスキーマの型を辿らず、XMLの文字列から正規表現で附則を切り出して文言を調べる。
+
+use crate::calendar::{cmp_dates, date_to_ad, era_from_kanji, to_iso8601};
+use crate::kansuji::parse_kansuji;
+use crate::repair::parse_law_num_date;
+use jplaw_data_types::law::Date;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 施行日を読み取るのに使う要素
+pub const READ_TAGS: &[&str] = &["SupplProvision", "Sentence"];
+
+/// 附則1つ
+#[derive(Debug, Clone)]
+pub struct SupplProvisionText {
+  /// 改正法令の附則の場合は、その改正法令の法令番号
+  pub amend_law_num: Option<String>,
+  /// タグを取り除いた附則の文言
+  pub text: String,
+}
+
+/// 附則の文言から読み取った施行の時期
+#[derive(Debug, Clone)]
+pub enum Enforcement {
+  /// "令和三年四月一日から施行する"のように日付が書かれている
+  Date(Date),
+  /// "公布の日から施行する"
+  OnPromulgation,
+}
+
+/// XMLの文字列から附則を順に取り出す
+pub fn suppl_provisions(xml: &str) -> Vec<SupplProvisionText> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  static ATTR_RE: OnceLock<Regex> = OnceLock::new();
+  static TAG_RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| {
+    Regex::new(r"(?s)<SupplProvision(?P<attrs>[^>]*)>(?P<body>.*?)</SupplProvision>").unwrap()
+  });
+  let attr_re = ATTR_RE.get_or_init(|| Regex::new(r#"AmendLawNum="(?P<num>[^"]*)""#).unwrap());
+  let tag_re = TAG_RE.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+  re.captures_iter(xml)
+    .map(|caps| SupplProvisionText {
+      amend_law_num: attr_re
+        .captures(&caps["attrs"])
+        .map(|attr| attr["num"].to_string()),
+      text: tag_re.replace_all(&caps["body"], "").to_string(),
+    })
+    .collect()
+}
+
+/// 附則の文言から施行の時期を読み取る
+pub fn enforcements_of(text: &str) -> Vec<Enforcement> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| {
+    Regex::new(
+      r"(?:(?P<era>明治|大正|昭和|平成|令和)(?P<year>[元〇一二三四五六七八九十百千\d０-９]+)年(?P<month>[〇一二三四五六七八九十\d０-９]+)月(?P<day>[〇一二三四五六七八九十\d０-９]+)日|(?P<promulgation>公布の日))から(?:これを)?施行",
+    )
+    .unwrap()
+  });
+  re.captures_iter(text)
+    .filter_map(|caps| {
+      if caps.name("promulgation").is_some() {
+        return Some(Enforcement::OnPromulgation);
+      }
+      Some(Enforcement::Date(Date::new(
+        era_from_kanji(&caps["era"])?,
+        parse_kansuji(&caps["year"])?,
+        Some(parse_kansuji(&caps["month"])?),
+        Some(parse_kansuji(&caps["day"])?),
+      )))
+    })
+    .collect()
+}
+
+/// XMLファイル1つが表す版の日付に、最後の附則の改正が施行済みだったかどうか
+///
+/// 最後の附則から施行の時期が読み取れない場合は`None`を返す。
+/// 施行の日付が複数ある場合は最も早いもので判定する
+pub fn in_force_at(xml: &str, snapshot_date: &Date) -> Option<bool> {
+  let last = suppl_provisions(xml).pop()?;
+  let enforcements = enforcements_of(&last.text);
+  let earliest = enforcements
+    .iter()
+    .filter_map(|e| match e {
+      Enforcement::Date(date) => Some(date_to_ad(date)),
+      Enforcement::OnPromulgation => None,
+    })
+    .min();
+  match earliest {
+    Some(date) => Some(date <= date_to_ad(snapshot_date)),
+    // 公布の日に施行される改正は、その改正を反映した版の日付には施行済み
+    None if !enforcements.is_empty() => Some(true),
+    None => None,
+  }
+}
+
+/// XMLの文字列に含まれるすべての附則から施行日を読み取り、古い順に重複なく並べる
+///
+/// "公布の日から施行する"の場合は、制定時の附則なら`promulgation`を、
+/// 改正法令の附則なら`AmendLawNum`の法令番号から読み取れる公布の日付を施行日とする
+pub fn enforcement_dates(xml: &str, promulgation: &Date) -> Vec<Date> {
+  let mut dates = Vec::new();
+  for suppl in suppl_provisions(xml) {
+    for enforcement in enforcements_of(&suppl.text) {
+      let date = match enforcement {
+        Enforcement::Date(date) => date,
+        Enforcement::OnPromulgation => match &suppl.amend_law_num {
+          None => promulgation.clone(),
+          Some(num) => {
+            let Some(d) = parse_law_num_date(num) else {
+              continue;
+            };
+            Date::new(d.era, d.year, d.month, d.day)
+          }
+        },
+      };
+      if !dates
+        .iter()
+        .any(|d: &Date| to_iso8601(d) == to_iso8601(&date))
+      {
+        dates.push(date);
+      }
+    }
+  }
+  dates.sort_by(cmp_dates);
+  dates
+}
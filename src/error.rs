@@ -0,0 +1,160 @@
+//! XMLファイルの読み込みで起きるエラー
+//!
+//! ディレクトリを読むような関数は`anyhow::Result`を返すが、
+//! XMLファイル1つの読み込みで起きたエラーはこの型で返すので`downcast_ref`で取り出せる。
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ListupError {
+  /// "foobarbaz_20230616_000000000000000.xml"の形をしていないファイル名
+  #[error("cannot parse file name: {file}")]
+  InvalidFileName { file: String },
+  /// UTF-8として読めないファイル名
+  #[error("file name is not valid UTF-8: {file}")]
+  NonUtf8FileName { file: String },
+  /// 法令IDとして読めない文字列
+  #[error("invalid law id `{id}` in {file}")]
+  InvalidLawId { file: String, id: String },
+  /// 法令IDとして読んだ結果を文字列に戻すと元の文字列と一致しない
+  #[error("law id `{id}` is read as `{parsed}` in {file}")]
+  LawIdMismatch {
+    file: String,
+    id: String,
+    parsed: String,
+  },
+  /// XMLファイルの中身が正しいUTF-8でない
+  #[error("invalid UTF-8 after byte {valid_up_to} in {file}")]
+  InvalidUtf8 { file: String, valid_up_to: usize },
+  /// XMLファイルが空（0バイトか空白だけ）
+  #[error("empty file: {file}")]
+  EmptyFile { file: String },
+  /// XMLファイルが途中で切れている（ダウンロードの中断など）
+  #[error("truncated file: {file}")]
+  TruncatedFile { file: String },
+  /// `Law`タグの`Era`属性が元号の表にも`ListupOptions::extra_eras`にも無い
+  #[error("unknown era `{era}` in {file}")]
+  UnknownEra { file: String, era: String },
+  /// XMLファイルの解析に失敗した
+  #[error("cannot parse XML {file}: {message}")]
+  Xml { file: String, message: String },
+  /// 1つの法令の改正の数が上限を超えた
+  #[error("{id} has more than {limit} patches, skipped {file}")]
+  TooManyPatches {
+    file: String,
+    id: String,
+    limit: usize,
+  },
+  /// 同じ法令IDの他のXMLファイルと法令名か法令番号が食い違っている（`--on-conflict error`の場合）
+  #[error("{id} in {file} has {field} `{conflicting}`, but `{existing}` was read before")]
+  LawConflict {
+    file: String,
+    id: String,
+    field: String,
+    existing: String,
+    conflicting: String,
+  },
+  /// ファイルを読み込めなかった
+  #[error("cannot read {file}")]
+  Io {
+    file: String,
+    #[source]
+    source: std::io::Error,
+  },
+}
+
+impl ListupError {
+  /// エラーの分類
+  pub fn kind(&self) -> &'static str {
+    match self {
+      ListupError::InvalidFileName { .. } | ListupError::NonUtf8FileName { .. } => "file-name",
+      ListupError::InvalidLawId { .. } | ListupError::LawIdMismatch { .. } => "law-id",
+      ListupError::InvalidUtf8 { .. } => "invalid-utf8",
+      ListupError::EmptyFile { .. } | ListupError::TruncatedFile { .. } => "truncated",
+      ListupError::UnknownEra { .. } => "unknown-era",
+      ListupError::Xml { .. } => "xml",
+      ListupError::TooManyPatches { .. } => "patch-limit",
+      ListupError::LawConflict { .. } => "conflict",
+      ListupError::Io { .. } => "io",
+    }
+  }
+
+  /// 読み直せば成功する可能性のある一時的なエラーかどうか
+  ///
+  /// NFSなどのネットワーク越しのファイルシステムで、まれに起きて次の実行では起きないもの。
+  /// XMLの構造に由来するエラーは読み直しても変わらないので含めない
+  pub fn is_transient(&self) -> bool {
+    use std::io::ErrorKind;
+    match self {
+      ListupError::Io { source, .. } => {
+        matches!(
+          source.kind(),
+          ErrorKind::Interrupted
+            | ErrorKind::TimedOut
+            | ErrorKind::WouldBlock
+            | ErrorKind::UnexpectedEof
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+        ) || is_stale_file_handle(source)
+      }
+      _ => false,
+    }
+  }
+}
+
+/// NFSの"Stale file handle"のエラーかどうか
+///
+/// エラー番号はOSごとに違い、`raw_os_error`はWindowsではWin32のエラーコードを返すため、Linuxでだけ調べる
+#[cfg(target_os = "linux")]
+fn is_stale_file_handle(source: &std::io::Error) -> bool {
+  /// Linuxで"Stale file handle"を表すエラー番号
+  const ESTALE: i32 = 116;
+  source.raw_os_error() == Some(ESTALE)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_stale_file_handle(_source: &std::io::Error) -> bool {
+  false
+}
+
+/// 読み込みを飛ばしたファイルの記録
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileError {
+  /// ファイルへのpath
+  pub file: String,
+  /// エラーの分類
+  pub kind: String,
+  /// エラーの内容
+  pub message: String,
+}
+
+impl FileError {
+  pub fn new(file: &str, error: &ListupError) -> Self {
+    FileError {
+      file: file.to_string(),
+      kind: error.kind().to_string(),
+      message: error.to_string(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn io_error(source: std::io::Error) -> ListupError {
+    ListupError::Io {
+      file: "415AC0000000057.xml".to_string(),
+      source,
+    }
+  }
+
+  #[test]
+  fn treats_only_linux_stale_handles_as_transient_os_errors() {
+    let stale = io_error(std::io::Error::from_raw_os_error(116));
+    assert_eq!(stale.is_transient(), cfg!(target_os = "linux"));
+    assert!(io_error(std::io::ErrorKind::TimedOut.into()).is_transient());
+    assert!(!io_error(std::io::ErrorKind::NotFound.into()).is_transient());
+  }
+}
@@ -0,0 +1,267 @@
+//! 処理する法令の絞り込みの条件
+//!
+//! コマンドラインのオプション（`#[clap(flatten)]`）としても、設定ファイルやHTTPのクエリ文字列のような
+//! キーと値の組としても同じ構造体で受け取り、同じ検証を通してから`ListupOptions`に反映する。
+
+use crate::calendar::{era_from_attr, era_from_kanji, AdDate};
+use crate::listup::ListupOptions;
+use crate::path_filter::PathFilter;
+use crate::shard::Shard;
+use serde::{de, Deserialize, Deserializer};
+use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// 絞り込みの条件のうち、1つのキーの値が正しくないこと
+#[derive(Debug, Error)]
+#[error("invalid filter `{key}`: {message}")]
+pub struct FilterError {
+  /// 正しくない値が指定されたキー
+  pub key: String,
+  /// エラーの内容
+  pub message: String,
+}
+
+impl FilterError {
+  fn new(key: &str, message: impl Display) -> Self {
+    FilterError {
+      key: key.to_string(),
+      message: message.to_string(),
+    }
+  }
+}
+
+fn opt_from_str<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr,
+  T::Err: Display,
+{
+  match Option::<String>::deserialize(d)? {
+    Some(s) => s.parse().map(Some).map_err(de::Error::custom),
+    None => Ok(None),
+  }
+}
+
+fn vec_from_str<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr,
+  T::Err: Display,
+{
+  Vec::<String>::deserialize(d)?
+    .iter()
+    .map(|s| s.parse().map_err(de::Error::custom))
+    .collect()
+}
+
+/// 処理する法令の絞り込みの条件
+#[derive(Debug, Clone, Default, clap::Args, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FilterConfig {
+  /// `Showa,Heisei,Reiwa`のように指定すると、その元号に公布された法令だけを出力する
+  #[clap(long, value_parser, value_delimiter = ',')]
+  pub era: Vec<String>,
+  /// `1990-01-01`のように指定すると、その日以降に公布された法令だけを出力する
+  #[clap(long, value_parser)]
+  #[serde(deserialize_with = "opt_from_str")]
+  pub since: Option<AdDate>,
+  /// `2020-12-31`のように指定すると、その日以前に公布された法令だけを出力する
+  #[clap(long, value_parser)]
+  #[serde(deserialize_with = "opt_from_str")]
+  pub until: Option<AdDate>,
+  /// pathがこのglobパターン（`re:`で始まる場合は正規表現）に合うXMLファイルだけを処理する（複数指定可）
+  #[clap(long, value_parser)]
+  #[serde(deserialize_with = "vec_from_str")]
+  pub include: Vec<PathFilter>,
+  /// pathがこのglobパターン（`re:`で始まる場合は正規表現）に合うXMLファイルは処理しない（複数指定可）
+  #[clap(long, value_parser)]
+  #[serde(deserialize_with = "vec_from_str")]
+  pub exclude: Vec<PathFilter>,
+  /// `3/8`のように指定すると、法令IDのハッシュ値で8分割したうちの3番目に当たる法令だけを処理する
+  #[clap(long, value_parser)]
+  #[serde(deserialize_with = "opt_from_str")]
+  pub shard: Option<Shard>,
+}
+
+impl FilterConfig {
+  /// `era=Heisei&since=1990-01-01`のようなクエリ文字列から読む
+  ///
+  /// 値を複数取るキーは、同じキーを繰り返すかカンマ区切りで書く。
+  /// 知らないキーや正しくない値があった場合は、そのキーを含むエラーを返す
+  pub fn from_query(query: &str) -> Result<Self, FilterError> {
+    let mut filter = FilterConfig::default();
+    for pair in query.trim_start_matches('?').split('&') {
+      if pair.is_empty() {
+        continue;
+      }
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      let key = percent_decode(key).map_err(|e| FilterError::new(key, e))?;
+      let value = percent_decode(value).map_err(|e| FilterError::new(&key, e))?;
+      filter.set(&key, &value)?;
+    }
+    filter.validate()?;
+    Ok(filter)
+  }
+
+  /// キー1つ分の値を設定する
+  fn set(&mut self, key: &str, value: &str) -> Result<(), FilterError> {
+    fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, FilterError>
+    where
+      T::Err: Display,
+    {
+      value.parse().map_err(|e| FilterError::new(key, e))
+    }
+    match key {
+      "era" => self.era.extend(value.split(',').map(str::to_string)),
+      "since" => self.since = Some(parse(key, value)?),
+      "until" => self.until = Some(parse(key, value)?),
+      "include" => self.include.push(parse(key, value)?),
+      "exclude" => self.exclude.push(parse(key, value)?),
+      "shard" => self.shard = Some(parse(key, value)?),
+      _ => return Err(FilterError::new(key, "unknown key")),
+    }
+    Ok(())
+  }
+
+  /// 個々の値だけでは分からない誤りを調べる
+  pub fn validate(&self) -> Result<(), FilterError> {
+    for era in &self.era {
+      if era_from_attr(era).is_none() && era_from_kanji(era).is_none() {
+        return Err(FilterError::new("era", format!("unknown era: {era}")));
+      }
+    }
+    if let (Some(since), Some(until)) = (self.since, self.until) {
+      if until < since {
+        return Err(FilterError::new(
+          "until",
+          format!("{until} is before since ({since})"),
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// 検証してから、一覧を作るときの設定に反映する
+  pub fn apply(&self, options: &mut ListupOptions) -> Result<(), FilterError> {
+    self.validate()?;
+    options.eras = self
+      .era
+      .iter()
+      .filter_map(|era| era_from_attr(era).or_else(|| era_from_kanji(era)))
+      .collect();
+    options.since = self.since;
+    options.until = self.until;
+    options.include = self.include.clone();
+    options.exclude = self.exclude.clone();
+    options.shard = self.shard;
+    Ok(())
+  }
+}
+
+/// `%E5%B9%B3`のようなパーセントエンコーディングと`+`を元に戻す
+fn percent_decode(s: &str) -> Result<String, String> {
+  let mut bytes = Vec::with_capacity(s.len());
+  let mut iter = s.bytes();
+  while let Some(b) = iter.next() {
+    match b {
+      b'%' => {
+        let hex = [iter.next(), iter.next()];
+        let [Some(hi), Some(lo)] = hex else {
+          return Err(format!("broken percent encoding: {s}"));
+        };
+        let byte = std::str::from_utf8(&[hi, lo])
+          .ok()
+          .and_then(|h| u8::from_str_radix(h, 16).ok())
+          .ok_or_else(|| format!("broken percent encoding: {s}"))?;
+        bytes.push(byte);
+      }
+      b'+' => bytes.push(b' '),
+      _ => bytes.push(b),
+    }
+  }
+  String::from_utf8(bytes).map_err(|_| format!("not UTF-8 after decoding: {s}"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::calendar::EraExt;
+
+  #[test]
+  fn reads_query_strings() {
+    let filter = FilterConfig::from_query(
+      "?era=Heisei,%E4%BB%A4%E5%92%8C&since=1990-01-01&include=*%2F415*.xml&include=re:_2020&shard=1%2F2",
+    )
+    .unwrap();
+    assert_eq!(filter.era, ["Heisei", "令和"]);
+    assert_eq!(filter.since, Some(AdDate::new(1990, 1, 1)));
+    assert_eq!(filter.until, None);
+    assert_eq!(filter.include.len(), 2);
+    assert!(filter.include[0].matches("a/415AC0000000057.xml"));
+    assert!(filter.include[1].matches("a/415AC0000000057_20200401.xml"));
+    assert_eq!(filter.shard, Some(Shard { index: 1, count: 2 }));
+    assert!(FilterConfig::from_query("").unwrap().era.is_empty());
+  }
+
+  #[test]
+  fn reports_the_invalid_key() {
+    let key = |query: &str| FilterConfig::from_query(query).unwrap_err().key;
+    assert_eq!(key("era=Heisei&color=red"), "color");
+    assert_eq!(key("since=1990-13-01"), "since");
+    assert_eq!(key("era=Future"), "era");
+    assert_eq!(key("since=2020-01-01&until=1990-01-01"), "until");
+    assert_eq!(key("shard=3%2F2"), "shard");
+    assert_eq!(key("era=%E4%BB"), "era");
+    assert_eq!(key("era=%G0"), "era");
+  }
+
+  #[test]
+  fn reads_edge_cases_of_query_strings() {
+    // 同じ日を両端に指定してもよい
+    let filter = FilterConfig::from_query("since=2020-01-01&until=2020-01-01&&").unwrap();
+    assert_eq!(filter.since, filter.until);
+    let filter = FilterConfig::from_query("exclude=*%2Fa+b%2F*").unwrap();
+    assert!(filter.exclude[0].matches("work/a b/c.xml"));
+
+    // 値の無いキーは空文字列として検証する
+    let err = FilterConfig::from_query("era").unwrap_err();
+    assert_eq!(err.key, "era");
+    assert_eq!(err.to_string(), "invalid filter `era`: unknown era: ");
+    let err = FilterConfig::from_query("%E8%89%B2=red").unwrap_err();
+    assert_eq!(err.key, "色");
+  }
+
+  #[test]
+  fn reads_config_tables() {
+    let filter: FilterConfig =
+      toml::from_str("era = [\"Showa\"]\nuntil = \"1988-12-31\"\nexclude = [\"*/broken/*\"]")
+        .unwrap();
+    assert_eq!(filter.era, ["Showa"]);
+    assert_eq!(filter.until, Some(AdDate::new(1988, 12, 31)));
+    assert!(filter.exclude[0].matches("work/broken/a.xml"));
+    assert!(toml::from_str::<FilterConfig>("until = \"1988-12-32\"").is_err());
+    assert!(toml::from_str::<FilterConfig>("color = \"red\"").is_err());
+  }
+
+  #[test]
+  fn applies_to_listup_options() {
+    let filter = FilterConfig::from_query("era=Showa,平成&until=2000-01-01&shard=2%2F3").unwrap();
+    let mut options = ListupOptions::default();
+    filter.apply(&mut options).unwrap();
+    let eras = options
+      .eras
+      .iter()
+      .map(|era| era.attr())
+      .collect::<Vec<_>>();
+    assert_eq!(eras, ["Showa", "Heisei"]);
+    assert_eq!(options.until, Some(AdDate::new(2000, 1, 1)));
+    assert_eq!(options.shard, Some(Shard { index: 2, count: 3 }));
+
+    let invalid = FilterConfig {
+      era: vec!["Future".to_string()],
+      ..Default::default()
+    };
+    assert!(invalid.apply(&mut options).is_err());
+  }
+}
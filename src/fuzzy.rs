@@ -0,0 +1,272 @@
+//! 入力された法令名から法令を探す
+//!
+//! 利用者が打ち込んだ法令名は、全角と半角の違いや中黒・空白の有無、略称や書き間違いを含むことが多い。
+//! 比べる前に両方の文字列を正規化し、完全一致・部分一致・略称らしい一致・編集距離の順に候補を並べる
+
+use crate::normalize::Normalization;
+use crate::record::LawRecord;
+
+/// 法令名で探すときの設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyOptions {
+  /// 返す候補の数の上限
+  pub limit: usize,
+  /// 編集距離で一致とみなす上限（`None`の場合は正規化した後の入力の文字数の3分の1、最低1）
+  pub max_distance: Option<usize>,
+}
+
+impl Default for FuzzyOptions {
+  fn default() -> Self {
+    FuzzyOptions {
+      limit: 10,
+      max_distance: None,
+    }
+  }
+}
+
+/// 一致の種類（良いものから順に並ぶ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+  /// 正規化した後の文字列が一致する
+  Exact,
+  /// 法令名などが入力を含む
+  Partial,
+  /// 入力の文字が法令名などに順番通りに現れる（"個情法"と"個人情報の保護に関する法律"など）
+  Abbreviation,
+  /// 編集距離が上限以下
+  Similar,
+}
+
+/// 法令名で探した候補
+#[derive(Debug, Clone)]
+pub struct FuzzyMatch<'a> {
+  /// 候補の法令
+  pub law: &'a LawRecord,
+  /// 一致した法令名・略称・法令名の読み（正規化する前のもの）
+  pub matched: &'a str,
+  /// 一致の種類
+  pub kind: MatchKind,
+  /// 正規化した後の入力と`matched`の編集距離
+  pub distance: usize,
+}
+
+/// 比べるために文字列を正規化する
+///
+/// NFKCで全角英数字と半角カナをそろえ、中黒と空白を除く
+pub fn normalize_law_name(s: &str) -> String {
+  Normalization::Nfkc
+    .apply(s)
+    .chars()
+    .filter(|c| !c.is_whitespace() && !matches!(c, '・' | '･'))
+    .collect()
+}
+
+/// 文字単位の編集距離（挿入・削除・置換をそれぞれ1とする）
+pub fn edit_distance(a: &[char], b: &[char]) -> usize {
+  let mut prev = (0..=b.len()).collect::<Vec<_>>();
+  for (i, ca) in a.iter().enumerate() {
+    let mut cur = vec![i + 1; b.len() + 1];
+    for (j, cb) in b.iter().enumerate() {
+      let replace = prev[j] + usize::from(ca != cb);
+      cur[j + 1] = replace.min(prev[j + 1] + 1).min(cur[j] + 1);
+    }
+    prev = cur;
+  }
+  prev[b.len()]
+}
+
+/// `query`の文字が`target`に順番通りに現れるかどうか
+fn is_subsequence(query: &[char], target: &[char]) -> bool {
+  let mut target = target.iter();
+  query.iter().all(|c| target.any(|t| t == c))
+}
+
+/// 正規化した入力と法令名などを比べる
+fn compare(query: &[char], target: &[char], max_distance: usize) -> Option<(MatchKind, usize)> {
+  let distance = edit_distance(query, target);
+  let kind = if query == target {
+    MatchKind::Exact
+  } else if target.windows(query.len()).any(|w| w == query) {
+    MatchKind::Partial
+  } else if is_subsequence(query, target) && target.first() == query.first() {
+    MatchKind::Abbreviation
+  } else if distance <= max_distance {
+    MatchKind::Similar
+  } else {
+    return None;
+  };
+  Some((kind, distance))
+}
+
+/// 入力された法令名に近い法令を、良い候補から順に探す
+///
+/// 法令名・略称・法令名の読みのうち最も良く一致したもので法令を並べる。
+/// 同じ種類の一致の間では編集距離の短い順、さらに法令IDの順にする
+pub fn find_laws_by_name<'a>(
+  laws: &'a [LawRecord],
+  query: &str,
+  options: FuzzyOptions,
+) -> Vec<FuzzyMatch<'a>> {
+  let query = normalize_law_name(query).chars().collect::<Vec<_>>();
+  if query.is_empty() {
+    return Vec::new();
+  }
+  let max_distance = options
+    .max_distance
+    .unwrap_or_else(|| (query.len() / 3).max(1));
+  let mut matches = laws
+    .iter()
+    .filter_map(|law| {
+      std::iter::once(law.name.as_str())
+        .chain(law.abbrev.iter().map(String::as_str))
+        .chain(law.name_kana.as_deref())
+        .filter_map(|candidate| {
+          let target = normalize_law_name(candidate).chars().collect::<Vec<_>>();
+          let (kind, distance) = compare(&query, &target, max_distance)?;
+          Some(FuzzyMatch {
+            law,
+            matched: candidate,
+            kind,
+            distance,
+          })
+        })
+        .min_by_key(|m| (m.kind, m.distance))
+    })
+    .collect::<Vec<_>>();
+  matches.sort_by_cached_key(|m| (m.kind, m.distance, m.law.id.to_string()));
+  matches.truncate(options.limit);
+  matches
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::law;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const PRIVACY_ORDER_ID: &str = "415CO0000000507";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn find(laws: &[LawRecord], query: &str, options: FuzzyOptions) -> Vec<(String, MatchKind)> {
+    find_laws_by_name(laws, query, options)
+      .into_iter()
+      .map(|m| (m.law.id.to_string(), m.kind))
+      .collect()
+  }
+
+  #[test]
+  fn classifies_matches() {
+    let laws = vec![
+      law(PRIVACY_ACT_ID, "個人情報の保護に関する法律"),
+      law(CIVIL_CODE_ID, "民法"),
+    ];
+    let find = |query| find(&laws, query, FuzzyOptions::default());
+
+    assert_eq!(
+      find("民　法"),
+      [(CIVIL_CODE_ID.to_string(), MatchKind::Exact)]
+    );
+    assert_eq!(
+      find("個人情報"),
+      [(PRIVACY_ACT_ID.to_string(), MatchKind::Partial)]
+    );
+    assert_eq!(
+      find("個情法"),
+      [(PRIVACY_ACT_ID.to_string(), MatchKind::Abbreviation)]
+    );
+    assert_eq!(
+      find("個人情報の保護に関する方律"),
+      [(PRIVACY_ACT_ID.to_string(), MatchKind::Similar)]
+    );
+    assert!(find("著作権法").is_empty());
+    assert!(find("　").is_empty());
+    assert_eq!(normalize_law_name("ＡＢＣ・ｄｅｆ 法"), "ABCdef法");
+  }
+
+  #[test]
+  fn limits_similar_matches_by_max_distance() {
+    let laws = vec![law(CIVIL_CODE_ID, "民法")];
+    // 2文字の入力では、指定しない場合の上限は1
+    assert_eq!(
+      find(&laws, "民放", FuzzyOptions::default()),
+      [(CIVIL_CODE_ID.to_string(), MatchKind::Similar)]
+    );
+    let strict = FuzzyOptions {
+      max_distance: Some(0),
+      ..Default::default()
+    };
+    assert!(find(&laws, "民放", strict).is_empty());
+    assert!(find(&laws, "刑放", FuzzyOptions::default()).is_empty());
+    let loose = FuzzyOptions {
+      max_distance: Some(2),
+      ..Default::default()
+    };
+    assert_eq!(
+      find(&laws, "刑放", loose),
+      [(CIVIL_CODE_ID.to_string(), MatchKind::Similar)]
+    );
+  }
+
+  #[test]
+  fn ranks_abbreviations_by_distance() {
+    let mut order = law(PRIVACY_ORDER_ID, "個人情報の保護に関する法律施行令");
+    order.abbrev = vec!["個情法施行令".to_string()];
+    let laws = vec![
+      law(PRIVACY_ACT_ID, "個人情報の保護に関する法律"),
+      law("415AC0000000058", "個人情報保護法"),
+      order,
+    ];
+
+    // 略称に入力を含むものを先にし、略称らしい一致の間では編集距離の短いものを先にする
+    assert_eq!(
+      find(&laws, "個情法", FuzzyOptions::default()),
+      [
+        (PRIVACY_ORDER_ID.to_string(), MatchKind::Partial),
+        ("415AC0000000058".to_string(), MatchKind::Abbreviation),
+        (PRIVACY_ACT_ID.to_string(), MatchKind::Abbreviation),
+      ]
+    );
+    let options = FuzzyOptions {
+      limit: 1,
+      ..Default::default()
+    };
+    assert_eq!(find(&laws, "個情法", options).len(), 1);
+    // 先頭の文字が違う場合は略称とみなさない
+    assert!(find(&laws, "情保法", FuzzyOptions::default()).is_empty());
+  }
+
+  #[test]
+  fn ignores_width_middle_dots_and_spaces_in_every_candidate() {
+    let mut civil = law(CIVIL_CODE_ID, "民法");
+    civil.name_kana = Some("ﾐﾝﾎﾟｳ".to_string());
+    let laws = vec![
+      civil,
+      law("412AC0000000061", "消費者契約法"),
+      law("412AC0000000062", "消費者契約法"),
+    ];
+
+    let matches = find_laws_by_name(&laws, "ミン ポウ", FuzzyOptions::default());
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].kind, MatchKind::Exact);
+    // 一致した候補は正規化する前の文字列で返す
+    assert_eq!(matches[0].matched, "ﾐﾝﾎﾟｳ");
+
+    // 同じ一致の間では法令IDの順にする
+    assert_eq!(
+      find(&laws, "消費者･契約　法", FuzzyOptions::default()),
+      [
+        ("412AC0000000061".to_string(), MatchKind::Exact),
+        ("412AC0000000062".to_string(), MatchKind::Exact),
+      ]
+    );
+  }
+
+  #[test]
+  fn counts_edit_distance_per_character() {
+    let chars = |s: &str| s.chars().collect::<Vec<_>>();
+    assert_eq!(edit_distance(&chars(""), &chars("民法")), 2);
+    assert_eq!(edit_distance(&chars("民法"), &chars("民法")), 0);
+    assert_eq!(edit_distance(&chars("民放"), &chars("民法")), 1);
+    assert_eq!(edit_distance(&chars("刑法典"), &chars("民法")), 2);
+  }
+}
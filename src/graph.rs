@@ -0,0 +1,160 @@
+//! 法令同士の改正と引用の関係をグラフとして書き出す
+
+use crate::record::LawRecord;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// グラフの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+  /// Graphvizで使うDOT形式
+  #[default]
+  Dot,
+  /// Gephiなどで使うGraphML形式
+  GraphMl,
+}
+
+impl FromStr for GraphFormat {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "dot" => Ok(GraphFormat::Dot),
+      "graphml" => Ok(GraphFormat::GraphMl),
+      _ => Err(anyhow!("unknown graph format: {s}")),
+    }
+  }
+}
+
+/// 辺の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+  /// 法令Yから、法令Yによって改正された法令Xへの辺
+  Amendment,
+  /// 法令Xから、法令Xの本文で引用されている法令Yへの辺
+  Citation,
+}
+
+impl EdgeKind {
+  fn as_str(&self) -> &'static str {
+    match self {
+      EdgeKind::Amendment => "amendment",
+      EdgeKind::Citation => "citation",
+    }
+  }
+}
+
+/// 法令同士の改正と引用の関係を表す有向グラフ
+#[derive(Debug, Clone, Default)]
+pub struct LawGraph {
+  /// 法令IDごとの法令名（一覧に無い法令の場合は空文字列）
+  pub nodes: BTreeMap<String, String>,
+  /// 始点と終点の法令IDと辺の種類の組ごとの数
+  pub edges: BTreeMap<(String, String, EdgeKind), usize>,
+}
+
+impl LawGraph {
+  /// 法令の一覧の改正の情報から、改正の関係だけのグラフを作る
+  pub fn new(laws: &[LawRecord]) -> Self {
+    let mut graph = LawGraph::default();
+    for law in laws {
+      graph.nodes.insert(law.id.to_string(), law.name.clone());
+    }
+    for law in laws {
+      let id = law.id.to_string();
+      for patch in &law.patch {
+        let Some(patch_id) = &patch.info.patch_id else {
+          continue;
+        };
+        let patch_id = patch_id.to_string();
+        if patch_id == id {
+          continue;
+        }
+        graph.nodes.entry(patch_id.clone()).or_default();
+        *graph
+          .edges
+          .entry((patch_id, id.clone(), EdgeKind::Amendment))
+          .or_insert(0) += 1;
+      }
+    }
+    graph
+  }
+
+  /// 法令`from`の本文で法令`to`が引用されていることを加える
+  pub fn add_citation(&mut self, from: &str, to: &str) {
+    self.nodes.entry(from.to_string()).or_default();
+    self.nodes.entry(to.to_string()).or_default();
+    *self
+      .edges
+      .entry((from.to_string(), to.to_string(), EdgeKind::Citation))
+      .or_insert(0) += 1;
+  }
+
+  /// 指定した形式の文字列にする
+  pub fn render(&self, format: GraphFormat) -> String {
+    match format {
+      GraphFormat::Dot => self.to_dot(),
+      GraphFormat::GraphMl => self.to_graphml(),
+    }
+  }
+
+  /// DOT形式の文字列にする
+  pub fn to_dot(&self) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut s = String::from("digraph amendments {\n");
+    for (id, name) in &self.nodes {
+      let label = if name.is_empty() { id } else { name };
+      let _ = writeln!(s, "  \"{}\" [label=\"{}\"];", escape(id), escape(label));
+    }
+    for ((from, to, kind), count) in &self.edges {
+      let _ = writeln!(
+        s,
+        "  \"{}\" -> \"{}\" [kind={}, weight={count}];",
+        escape(from),
+        escape(to),
+        kind.as_str()
+      );
+    }
+    s.push_str("}\n");
+    s
+  }
+
+  /// GraphML形式の文字列にする
+  pub fn to_graphml(&self) -> String {
+    let escape = |s: &str| {
+      s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+    };
+    let mut s = String::from(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="name" for="node" attr.name="name" attr.type="string"/>
+  <key id="kind" for="edge" attr.name="kind" attr.type="string"/>
+  <key id="weight" for="edge" attr.name="weight" attr.type="int"/>
+  <graph id="amendments" edgedefault="directed">
+"#,
+    );
+    for (id, name) in &self.nodes {
+      let _ = writeln!(
+        s,
+        "    <node id=\"{}\"><data key=\"name\">{}</data></node>",
+        escape(id),
+        escape(name)
+      );
+    }
+    for ((from, to, kind), count) in &self.edges {
+      let _ = writeln!(
+        s,
+        "    <edge source=\"{}\" target=\"{}\"><data key=\"kind\">{}</data><data key=\"weight\">{count}</data></edge>",
+        escape(from),
+        escape(to),
+        kind.as_str()
+      );
+    }
+    s.push_str("  </graph>\n</graphml>\n");
+    s
+  }
+}
@@ -0,0 +1,337 @@
+//! 書き出した法令の一覧を読み込む
+
+#[cfg(feature = "full")]
+use crate::record::laws_from_json;
+use crate::record::LawRecord;
+use anyhow::{anyhow, Result};
+use jplaw_data_types::law::LawId;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+#[cfg(feature = "full")]
+use tokio::fs::*;
+
+/// JSON形式で書き出した法令の一覧を読み込む
+///
+/// `--legacy-format`で書き出した法令の配列だけのファイルも読める
+#[cfg(feature = "full")]
+pub async fn get_law_from_index(index_file_path: &str) -> Result<Vec<LawRecord>> {
+  let s = read_to_string(index_file_path).await?;
+  laws_from_json(&s)
+}
+
+/// JSON Lines形式で書き出した法令の一覧を読み込む
+#[cfg(feature = "full")]
+pub async fn get_law_from_index_jsonl(index_file_path: &str) -> Result<Vec<LawRecord>> {
+  let s = read_to_string(index_file_path).await?;
+  let mut lst = Vec::new();
+  for line in s.lines() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    lst.push(serde_json::from_str(line)?);
+  }
+  Ok(lst)
+}
+
+/// 拡張子が".jsonl"か".ndjson"の場合はJSON Lines形式として、それ以外の場合はJSON形式として法令の一覧を読み込む
+#[cfg(feature = "full")]
+pub async fn read_law_index(index_file_path: &str) -> Result<Vec<LawRecord>> {
+  if index_file_path.ends_with(".jsonl") || index_file_path.ends_with(".ndjson") {
+    get_law_from_index_jsonl(index_file_path).await
+  } else {
+    get_law_from_index(index_file_path).await
+  }
+}
+
+/// このcrateに同梱している、"民法"や"労基法"のようなよく使われる通称と法令IDの対応
+const COMMON_NAMES: &str = include_str!("../data/common_names.tsv");
+
+/// 1行に通称と法令IDをタブ区切りで書いた文字列を読む
+///
+/// 空行と`#`で始まる行は読み飛ばす
+fn parse_common_names(s: &str) -> Result<Vec<(String, LawId)>> {
+  let mut lst = Vec::new();
+  for line in s.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let (name, id) = line.split_once('\t').ok_or(anyhow!(
+      "common name must be written as NAME<TAB>LAW_ID: {line}"
+    ))?;
+    let id = LawId::from_str(id.trim()).map_err(|_| anyhow!("invalid law id: {id}"))?;
+    lst.push((name.trim().to_string(), id));
+  }
+  Ok(lst)
+}
+
+/// 法令名・略称・通称・法令番号から法令IDを引くための索引
+#[derive(Debug, Clone, Default)]
+pub struct LawIndex {
+  by_name: HashMap<String, LawId>,
+  by_abbrev: HashMap<String, LawId>,
+  by_common_name: HashMap<String, LawId>,
+  by_num: HashMap<String, LawId>,
+}
+
+impl LawIndex {
+  /// 法令の一覧から索引を作る
+  ///
+  /// 同じ法令名などを持つ法令が複数ある場合は、一覧で後にあるものを使う。
+  /// 通称には同梱している対応を使う
+  pub fn new(laws: &[LawRecord]) -> Self {
+    let mut index = LawIndex {
+      by_common_name: parse_common_names(COMMON_NAMES)
+        .expect("bundled common names are invalid")
+        .into_iter()
+        .collect(),
+      ..Default::default()
+    };
+    for law in laws {
+      index.by_name.insert(law.name.clone(), law.id.clone());
+      index.by_num.insert(law.num.clone(), law.id.clone());
+      for abbrev in &law.abbrev {
+        index.by_abbrev.insert(abbrev.clone(), law.id.clone());
+      }
+    }
+    index
+  }
+
+  /// 法令名から法令IDを引く
+  pub fn by_name(&self, name: &str) -> Option<&LawId> {
+    self.by_name.get(name)
+  }
+
+  /// 略称から法令IDを引く
+  pub fn by_abbrev(&self, abbrev: &str) -> Option<&LawId> {
+    self.by_abbrev.get(abbrev)
+  }
+
+  /// "民法"や"労基法"のような通称から法令IDを引く
+  pub fn by_common_name(&self, name: &str) -> Option<&LawId> {
+    self.by_common_name.get(name)
+  }
+
+  /// 1行に通称と法令IDをタブ区切りで書いたファイルを読み、同梱している通称の対応に加える
+  ///
+  /// 同梱している対応と同じ通称がある場合は、ファイルに書かれたものを使う
+  #[cfg(feature = "full")]
+  pub async fn load_common_names(&mut self, path: &str) -> Result<()> {
+    let s = read_to_string(path).await?;
+    self.by_common_name.extend(parse_common_names(&s)?);
+    Ok(())
+  }
+
+  /// 法令番号から法令IDを引く
+  pub fn by_num(&self, num: &str) -> Option<&LawId> {
+    self.by_num.get(num)
+  }
+
+  /// 法令名・略称・通称・法令番号の順に探して法令IDを引く
+  pub fn lookup(&self, s: &str) -> Option<&LawId> {
+    self
+      .by_name(s)
+      .or_else(|| self.by_abbrev(s))
+      .or_else(|| self.by_common_name(s))
+      .or_else(|| self.by_num(s))
+  }
+}
+
+/// 法令の一覧1つ分の層
+#[derive(Debug, Clone)]
+struct IndexLayer {
+  /// 一覧の名前（ファイルへのpathなど）
+  name: String,
+  laws: Vec<LawRecord>,
+  /// 法令IDから`laws`の中の位置を引く
+  by_id: HashMap<String, usize>,
+  index: LawIndex,
+}
+
+/// 法令名などで法令IDを引く関数
+type Finder = for<'a> fn(&'a LawIndex, &str) -> Option<&'a LawId>;
+
+/// 国の法令・条例・過去の法令の一覧のように、更新の間隔が違う複数の法令の一覧を重ねて引くための索引
+///
+/// 先に加えた一覧ほど優先する。同じ法令IDの法令が複数の一覧にある場合は先に加えた一覧のものを使い、
+/// 法令名などで引く場合は、法令名・略称・通称・法令番号のそれぞれについて先に加えた一覧から探す
+#[derive(Debug, Clone, Default)]
+pub struct LawIndexSet {
+  layers: Vec<IndexLayer>,
+}
+
+impl LawIndexSet {
+  /// 空の索引を作る
+  pub fn new() -> Self {
+    LawIndexSet::default()
+  }
+
+  /// 法令の一覧を、今ある一覧より優先度の低い層として加える
+  pub fn push(&mut self, name: impl Into<String>, laws: Vec<LawRecord>) {
+    let by_id = laws
+      .iter()
+      .enumerate()
+      .map(|(i, law)| (law.id.to_string(), i))
+      .collect();
+    let index = LawIndex::new(&laws);
+    self.layers.push(IndexLayer {
+      name: name.into(),
+      laws,
+      by_id,
+      index,
+    });
+  }
+
+  /// 法令の一覧のファイルを、指定した順（先のものほど優先する）に読み込む
+  #[cfg(feature = "full")]
+  pub async fn load(paths: &[String]) -> Result<Self> {
+    let mut set = LawIndexSet::new();
+    for path in paths {
+      set.push(path.clone(), read_law_index(path).await?);
+    }
+    Ok(set)
+  }
+
+  /// 加えた一覧の名前を優先する順に返す
+  pub fn layer_names(&self) -> impl Iterator<Item = &str> {
+    self.layers.iter().map(|layer| layer.name.as_str())
+  }
+
+  /// 法令IDで法令を探し、見つかった一覧の名前とともに返す
+  pub fn get(&self, id: &str) -> Option<(&str, &LawRecord)> {
+    self.layers.iter().find_map(|layer| {
+      let i = *layer.by_id.get(id)?;
+      Some((layer.name.as_str(), &layer.laws[i]))
+    })
+  }
+
+  /// 法令名・略称・通称・法令番号の順に探して法令IDを引き、見つかった一覧の名前とともに返す
+  pub fn lookup(&self, s: &str) -> Option<(&str, &LawId)> {
+    let finders: [Finder; 4] = [
+      LawIndex::by_name,
+      LawIndex::by_abbrev,
+      LawIndex::by_common_name,
+      LawIndex::by_num,
+    ];
+    finders.iter().find_map(|find| {
+      self
+        .layers
+        .iter()
+        .find_map(|layer| Some((layer.name.as_str(), find(&layer.index, s)?)))
+    })
+  }
+
+  /// すべての一覧の法令を、同じ法令IDのものは優先する一覧のものだけにして返す
+  pub fn laws(&self) -> Vec<&LawRecord> {
+    let mut seen = HashSet::new();
+    self
+      .layers
+      .iter()
+      .flat_map(|layer| layer.laws.iter())
+      .filter(|law| seen.insert(law.id.to_string()))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::law;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const PRIVACY_ORDER_ID: &str = "415CO0000000507";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn index_set() -> LawIndexSet {
+    let mut privacy = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    privacy.abbrev = vec!["個情法".to_string()];
+    let mut civil = law(CIVIL_CODE_ID, "民法");
+    civil.num = "明治二十九年法律第八十九号".to_string();
+    let mut old_privacy = law(PRIVACY_ACT_ID, "旧個人情報の保護に関する法律");
+    old_privacy.abbrev = vec!["旧個情法".to_string()];
+    // 法令名が新しい一覧の略称と同じ法令
+    let mut order = law(PRIVACY_ORDER_ID, "個情法");
+    order.num = "平成十五年政令第五百七号".to_string();
+
+    let mut set = LawIndexSet::new();
+    set.push("current", vec![privacy, civil]);
+    set.push("archive", vec![old_privacy, order]);
+    set
+  }
+
+  #[test]
+  fn prefers_earlier_layers_by_law_id() {
+    let set = index_set();
+    assert_eq!(
+      set.layer_names().collect::<Vec<_>>(),
+      ["current", "archive"]
+    );
+
+    let (layer, law) = set.get(PRIVACY_ACT_ID).unwrap();
+    assert_eq!(layer, "current");
+    assert_eq!(law.name, "個人情報の保護に関する法律");
+    let (layer, _) = set.get(PRIVACY_ORDER_ID).unwrap();
+    assert_eq!(layer, "archive");
+    assert!(set.get("132AC0000000048").is_none());
+
+    let laws = set
+      .laws()
+      .into_iter()
+      .map(|law| law.name.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(laws, ["個人情報の保護に関する法律", "民法", "個情法"]);
+  }
+
+  #[test]
+  fn looks_up_each_kind_of_name_across_layers() {
+    let set = index_set();
+    let lookup = |s: &str| {
+      set
+        .lookup(s)
+        .map(|(layer, id)| (layer.to_string(), id.to_string()))
+    };
+    let found = |layer: &str, id: &str| Some((layer.to_string(), id.to_string()));
+
+    assert_eq!(
+      lookup("旧個人情報の保護に関する法律"),
+      found("archive", PRIVACY_ACT_ID)
+    );
+    assert_eq!(lookup("旧個情法"), found("archive", PRIVACY_ACT_ID));
+    // 法令名はすべての一覧から探した後で略称を探す
+    assert_eq!(lookup("個情法"), found("archive", PRIVACY_ORDER_ID));
+    // 同じ法令番号は先の一覧のものを使う
+    assert_eq!(
+      lookup("平成十五年法律第五十七号"),
+      found("current", PRIVACY_ACT_ID)
+    );
+    assert_eq!(
+      lookup("明治二十九年法律第八十九号"),
+      found("current", CIVIL_CODE_ID)
+    );
+    // 同梱している通称はどの一覧にもあるので、先の一覧のものになる
+    assert_eq!(lookup("著作権法"), found("current", "345AC0000000048"));
+    assert!(lookup("存在しない法令").is_none());
+  }
+
+  #[test]
+  fn handles_empty_sets_and_empty_layers() {
+    let empty = LawIndexSet::new();
+    assert_eq!(empty.layer_names().count(), 0);
+    assert!(empty.get(PRIVACY_ACT_ID).is_none());
+    assert!(empty.lookup("民法").is_none());
+    assert!(empty.laws().is_empty());
+
+    // 空の一覧が優先されても、後の一覧から探す
+    let mut set = LawIndexSet::new();
+    set.push("empty", Vec::new());
+    set.push("current", vec![law(CIVIL_CODE_ID, "民法")]);
+    assert_eq!(set.get(CIVIL_CODE_ID).unwrap().0, "current");
+    let (layer, id) = set.lookup("民法").unwrap();
+    assert_eq!(
+      (layer, id.to_string()),
+      ("current", CIVIL_CODE_ID.to_string())
+    );
+    // 同梱している通称は空の一覧からも引ける
+    assert_eq!(set.lookup("刑法").unwrap().0, "empty");
+  }
+}
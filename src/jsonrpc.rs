@@ -0,0 +1,76 @@
+//! 進み具合・警告・結果をJSON-RPCの通知として標準出力に書く
+//!
+//! エディタの拡張機能やGUIからこのソフトウェアを子プロセスとして動かす場合に、
+//! 1行に1つのJSON-RPC 2.0の通知を読めば済むようにする。
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// JSON-RPCの通知を1行で標準出力に書く
+pub fn notify(method: &str, params: Value) {
+  let line = json!({
+    "jsonrpc": "2.0",
+    "method": method,
+    "params": params,
+  });
+  let mut stdout = std::io::stdout().lock();
+  let _ = writeln!(stdout, "{line}");
+  let _ = stdout.flush();
+}
+
+/// ログのフィールドをJSONのオブジェクトに集める
+struct FieldVisitor(Map<String, Value>);
+
+impl Visit for FieldVisitor {
+  fn record_str(&mut self, field: &Field, value: &str) {
+    self.0.insert(field.name().to_string(), json!(value));
+  }
+  fn record_i64(&mut self, field: &Field, value: i64) {
+    self.0.insert(field.name().to_string(), json!(value));
+  }
+  fn record_u64(&mut self, field: &Field, value: u64) {
+    self.0.insert(field.name().to_string(), json!(value));
+  }
+  fn record_bool(&mut self, field: &Field, value: bool) {
+    self.0.insert(field.name().to_string(), json!(value));
+  }
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    self
+      .0
+      .insert(field.name().to_string(), json!(format!("{value:?}")));
+  }
+}
+
+/// ログを`log`という名前の通知として書くlayer
+struct JsonRpcLayer;
+
+impl<S: Subscriber> Layer<S> for JsonRpcLayer {
+  fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    let mut visitor = FieldVisitor(Map::new());
+    event.record(&mut visitor);
+    let metadata = event.metadata();
+    notify(
+      "log",
+      json!({
+        "level": metadata.level().as_str(),
+        "target": metadata.target(),
+        "fields": visitor.0,
+      }),
+    );
+  }
+}
+
+/// ログをすべてJSON-RPCの通知として書くようにする
+///
+/// `jplaw_io::init_logger`の代わりに呼ぶ
+pub fn init_jsonrpc_logger() -> Result<()> {
+  tracing_subscriber::registry()
+    .with(JsonRpcLayer)
+    .try_init()?;
+  Ok(())
+}
@@ -0,0 +1,121 @@
+//! 漢数字で書かれた数を読み取る
+
+fn digit_value(c: char) -> Option<usize> {
+  match c {
+    '〇' | '零' => Some(0),
+    '一' => Some(1),
+    '二' => Some(2),
+    '三' => Some(3),
+    '四' => Some(4),
+    '五' => Some(5),
+    '六' => Some(6),
+    '七' => Some(7),
+    '八' => Some(8),
+    '九' => Some(9),
+    '0'..='9' => Some(c as usize - '0' as usize),
+    '０'..='９' => Some(c as usize - '０' as usize),
+    _ => None,
+  }
+}
+
+/// "五十七"や"二〇一九"、"元"のような漢数字の文字列を数値に変換する
+///
+/// 算用数字（全角を含む）が混ざっていても読み取る。
+/// 数として読めない文字が含まれていた場合や、`usize`に収まらない場合は`None`を返す。
+pub fn parse_kansuji(s: &str) -> Option<usize> {
+  if s.is_empty() {
+    return None;
+  }
+  if s == "元" {
+    return Some(1);
+  }
+  let mut total: usize = 0;
+  let mut section: usize = 0;
+  let mut current: Option<usize> = None;
+  for c in s.chars() {
+    if let Some(d) = digit_value(c) {
+      current = Some(current.unwrap_or(0).checked_mul(10)?.checked_add(d)?);
+      continue;
+    }
+    match c {
+      '十' | '百' | '千' => {
+        let unit = match c {
+          '十' => 10,
+          '百' => 100,
+          _ => 1000,
+        };
+        section = section.checked_add(current.take().unwrap_or(1).checked_mul(unit)?)?;
+      }
+      '万' => {
+        let n = section.checked_add(current.take().unwrap_or(0))?;
+        total = total.checked_add(n.max(1).checked_mul(10000)?)?;
+        section = 0;
+      }
+      _ => return None,
+    }
+  }
+  total
+    .checked_add(section)?
+    .checked_add(current.unwrap_or(0))
+}
+
+/// 数値を"五十七"や"百二十七"のような漢数字の文字列にする
+///
+/// 法令番号などの書き方に合わせ、十・百・千の前の"一"は書かない
+pub fn to_kansuji(n: usize) -> String {
+  const DIGITS: [&str; 10] = ["〇", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+  if n == 0 {
+    return DIGITS[0].to_string();
+  }
+  if n >= 10000 {
+    let rest = n % 10000;
+    let upper = format!("{}万", to_kansuji(n / 10000));
+    return if rest == 0 {
+      upper
+    } else {
+      upper + &to_kansuji(rest)
+    };
+  }
+  let mut s = String::new();
+  for (unit, name) in [(1000, "千"), (100, "百"), (10, "十")] {
+    let d = n / unit % 10;
+    if d > 1 {
+      s.push_str(DIGITS[d]);
+    }
+    if d > 0 {
+      s.push_str(name);
+    }
+  }
+  if n % 10 > 0 {
+    s.push_str(DIGITS[n % 10]);
+  }
+  s
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_kansuji() {
+    assert_eq!(parse_kansuji("五十七"), Some(57));
+    assert_eq!(parse_kansuji("百二十七"), Some(127));
+    assert_eq!(parse_kansuji("二〇一九"), Some(2019));
+    assert_eq!(parse_kansuji("一万二千"), Some(12000));
+    assert_eq!(parse_kansuji("元"), Some(1));
+    assert_eq!(parse_kansuji("第一"), None);
+  }
+
+  #[test]
+  fn rejects_numbers_out_of_range() {
+    assert_eq!(parse_kansuji(&"九".repeat(40)), None);
+    assert_eq!(parse_kansuji(&format!("{}千", "九".repeat(19))), None);
+  }
+
+  #[test]
+  fn round_trips_with_to_kansuji() {
+    for n in [1, 10, 57, 127, 1000, 2019, 10000, 123456] {
+      assert_eq!(parse_kansuji(&to_kansuji(n)), Some(n));
+    }
+  }
+}
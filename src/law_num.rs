@@ -0,0 +1,75 @@
+//! "平成十五年法律第五十七号"のような法令番号を、元号・年・法令の種類・番号に分けて読み取る
+
+use crate::calendar::{era_from_kanji, EraExt};
+use crate::kansuji::{parse_kansuji, to_kansuji};
+use anyhow::{anyhow, Result};
+use japanese_law_xml_schema::law::Era;
+use jplaw_data_types::law::Date;
+use regex::Regex;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// 読み取った法令番号
+#[derive(Debug, Clone)]
+pub struct LawNum {
+  /// 元号
+  pub era: Era,
+  /// 元号での年
+  pub year: usize,
+  /// 法令の種類（"法律"・"政令"・"厚生省令"・"太政官布告"など）
+  pub category: String,
+  /// 番号
+  pub number: usize,
+}
+
+impl FromStr for LawNum {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+      let num = r"[〇一二三四五六七八九十百千万\d０-９]+";
+      Regex::new(&format!(
+        r"^(?P<era>明治|大正|昭和|平成|令和)(?P<year>元|{num})年(?P<category>[^第年]+?)第(?P<number>{num})号$"
+      ))
+      .unwrap()
+    });
+    let err = || anyhow!("cannot parse law number: {s}");
+    let caps = re.captures(s.trim()).ok_or_else(err)?;
+    Ok(LawNum {
+      era: era_from_kanji(&caps["era"]).ok_or_else(err)?,
+      year: parse_kansuji(&caps["year"]).ok_or_else(err)?,
+      category: caps["category"].to_string(),
+      number: parse_kansuji(&caps["number"]).ok_or_else(err)?,
+    })
+  }
+}
+
+/// 漢数字で書いた正規の形（1年は"元年"）にする
+impl std::fmt::Display for LawNum {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let year = if self.year == 1 {
+      "元".to_string()
+    } else {
+      to_kansuji(self.year)
+    };
+    write!(
+      f,
+      "{}{year}年{}第{}号",
+      self.era.kanji(),
+      self.category,
+      to_kansuji(self.number)
+    )
+  }
+}
+
+impl LawNum {
+  /// 公布の年（月日は含まない）
+  pub fn date(&self) -> Date {
+    Date::new(self.era.clone(), self.year, None, None)
+  }
+
+  /// 元号と年が`date`と同じかどうか
+  pub fn matches_date(&self, date: &Date) -> bool {
+    self.era.attr() == date.era.attr() && self.year == date.year
+  }
+}
@@ -0,0 +1,69 @@
+//! 法律のXMLファイルがあるフォルダから、法律の公布年月日やファイル置き場などのデータをリストアップする
+
+#[cfg(feature = "full")]
+pub mod abbrev;
+pub mod article;
+#[cfg(feature = "full")]
+pub mod badge;
+pub mod calendar;
+pub mod citation;
+#[cfg(feature = "full")]
+pub mod config;
+pub mod consistency;
+pub mod diff;
+#[cfg(feature = "embedded-index")]
+pub mod embedded;
+pub mod enforcement;
+pub mod error;
+#[cfg(feature = "full")]
+pub mod filter;
+pub mod fuzzy;
+pub mod graph;
+pub mod index;
+#[cfg(feature = "full")]
+pub mod jsonrpc;
+pub mod kansuji;
+pub mod law_num;
+pub mod lint;
+#[cfg(feature = "full")]
+pub mod listup;
+pub mod merge;
+pub mod metadata;
+pub mod ministry;
+pub mod normalize;
+#[cfg(feature = "full")]
+pub mod output;
+#[cfg(feature = "full")]
+pub mod path_filter;
+#[cfg(feature = "full")]
+pub mod pipeline;
+pub mod record;
+pub mod repair;
+pub mod schema;
+pub mod search;
+#[cfg(feature = "search-index")]
+pub mod search_index;
+pub mod shard;
+#[cfg(feature = "full")]
+pub mod snapshot;
+#[cfg(feature = "full")]
+pub mod spill;
+#[cfg(feature = "full")]
+pub mod state;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "full")]
+pub mod subset;
+#[cfg(feature = "full")]
+pub mod tags;
+#[cfg(feature = "full")]
+pub mod validate;
+pub mod view;
+
+pub use fuzzy::{find_laws_by_name, FuzzyOptions};
+#[cfg(feature = "full")]
+pub use index::{get_law_from_index, get_law_from_index_jsonl};
+pub use index::{LawIndex, LawIndexSet};
+#[cfg(feature = "full")]
+pub use listup::scan_laws;
+pub use metadata::{parse_law_xml, LawMetadata};
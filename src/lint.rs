@@ -0,0 +1,245 @@
+//! 書き出した法令の一覧の中身が整合しているかを調べる
+
+use crate::calendar::{is_valid_date, CalendarSystem};
+use crate::law_num::LawNum;
+use crate::record::LawRecord;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 問題の深刻度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+impl std::fmt::Display for Severity {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Severity::Warning => write!(f, "warning"),
+      Severity::Error => write!(f, "error"),
+    }
+  }
+}
+
+/// 見つかった問題
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+  pub severity: Severity,
+  /// 問題の種類
+  pub rule: &'static str,
+  /// 問題のあった法令の法令ID
+  pub id: String,
+  pub message: String,
+}
+
+impl std::fmt::Display for LintIssue {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{}[{}] {}: {}",
+      self.severity, self.rule, self.id, self.message
+    )
+  }
+}
+
+/// 法令の一覧を調べる
+///
+/// `check_files`が`true`の場合は、改正ごとのXMLファイルが存在するかも調べる
+pub fn lint_laws(laws: &[LawRecord], check_files: bool) -> Vec<LintIssue> {
+  let mut issues = Vec::new();
+  let mut ids = HashSet::new();
+  for law in laws {
+    let id = law.id.to_string();
+    let mut push = |severity, rule, message: String| {
+      issues.push(LintIssue {
+        severity,
+        rule,
+        id: id.clone(),
+        message,
+      })
+    };
+    if !ids.insert(id.clone()) {
+      push(
+        Severity::Error,
+        "duplicate-id",
+        "law id appears more than once".to_string(),
+      );
+    }
+    if law.name.trim().is_empty() {
+      push(
+        Severity::Warning,
+        "empty-name",
+        "law name is empty".to_string(),
+      );
+    }
+    if !is_valid_date(&law.date) {
+      push(
+        Severity::Error,
+        "invalid-date",
+        format!("invalid date: {:?}", law.date),
+      );
+    }
+    if law.calendar == CalendarSystem::Lunisolar {
+      push(
+        Severity::Warning,
+        "lunisolar-date",
+        "promulgation date is in the lunisolar calendar and is not converted".to_string(),
+      );
+    }
+    if let Ok(num) = law.num.parse::<LawNum>() {
+      if !num.matches_date(&law.date) {
+        push(
+          Severity::Warning,
+          "law-num-date",
+          format!(
+            "law number `{}` does not match the era and year {:?}",
+            law.num, law.date
+          ),
+        );
+      }
+    }
+    if law.patch.is_empty() {
+      push(
+        Severity::Error,
+        "empty-patch",
+        "patch list is empty".to_string(),
+      );
+    }
+    if law.patch.windows(2).any(|w| w[0].order(&w[1]).is_gt()) {
+      push(
+        Severity::Warning,
+        "unsorted-patch",
+        "patch list is not sorted by date and patch id".to_string(),
+      );
+    }
+    for patch in &law.patch {
+      if !is_valid_date(&patch.info.patch_date) {
+        push(
+          Severity::Error,
+          "invalid-date",
+          format!("invalid patch date: {:?}", patch.info.patch_date),
+        );
+      }
+      if check_files && !Path::new(&patch.file).is_file() {
+        push(
+          Severity::Error,
+          "missing-file",
+          format!("file not found: {}", patch.file),
+        );
+      }
+    }
+  }
+  issues
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::calendar::era_from_attr;
+  use crate::record::testing::{date, law, patch};
+  use jplaw_data_types::law::Date;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+
+  fn rules(laws: &[LawRecord]) -> Vec<(Severity, &'static str)> {
+    lint_laws(laws, false)
+      .into_iter()
+      .map(|issue| (issue.severity, issue.rule))
+      .collect()
+  }
+
+  #[test]
+  fn accepts_consistent_laws() {
+    assert!(rules(&[law(PRIVACY_ACT_ID, "個人情報の保護に関する法律")]).is_empty());
+  }
+
+  #[test]
+  fn reports_each_rule() {
+    let mut broken = law(PRIVACY_ACT_ID, " ");
+    // 平成は31年までなので、平成32年は実在しない
+    broken.date = Date::new(era_from_attr("Heisei").unwrap(), 32, None, None);
+    broken.calendar = CalendarSystem::Lunisolar;
+    broken.patch.insert(
+      0,
+      patch(
+        PRIVACY_ACT_ID,
+        date("Heisei", 29, 5, 30),
+        Some("427AC0000000065"),
+      ),
+    );
+    let mut empty = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    empty.patch.clear();
+
+    assert_eq!(
+      rules(&[broken, empty]),
+      [
+        (Severity::Warning, "empty-name"),
+        (Severity::Error, "invalid-date"),
+        (Severity::Warning, "lunisolar-date"),
+        (Severity::Warning, "law-num-date"),
+        (Severity::Warning, "unsorted-patch"),
+        (Severity::Error, "duplicate-id"),
+        (Severity::Error, "empty-patch"),
+      ]
+    );
+  }
+
+  #[test]
+  fn checks_patch_dates_and_order_within_the_same_day() {
+    let mut law = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    // 同じ日の改正は改正法令の法令IDの順に並んでいればよい
+    law.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("415AC0000000057"),
+    ));
+    law.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    assert!(rules(std::slice::from_ref(&law)).is_empty());
+
+    law.patch.swap(1, 2);
+    assert_eq!(
+      rules(std::slice::from_ref(&law)),
+      [(Severity::Warning, "unsorted-patch")]
+    );
+
+    law.patch.swap(1, 2);
+    law.patch[2].info.patch_date = date("Heisei", 29, 6, 31);
+    assert_eq!(
+      rules(std::slice::from_ref(&law)),
+      [(Severity::Error, "invalid-date")]
+    );
+  }
+
+  #[test]
+  fn checks_files_only_when_asked() {
+    let laws = [law(PRIVACY_ACT_ID, "個人情報の保護に関する法律")];
+    let issues = lint_laws(&laws, true);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].rule, "missing-file");
+    assert_eq!(
+      issues[0].to_string(),
+      format!(
+        "error[missing-file] {PRIVACY_ACT_ID}: file not found: {}",
+        laws[0].patch[0].file
+      )
+    );
+    assert!(lint_laws(&laws, false).is_empty());
+
+    let dir = std::env::temp_dir().join(format!("listup_law_lint_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join(format!("{PRIVACY_ACT_ID}.xml"));
+    std::fs::write(&file, "<Law/>").unwrap();
+    let mut found = laws[0].clone();
+    found.patch[0].file = file.to_str().unwrap().to_string();
+    let issues = lint_laws(&[found], true);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(issues.is_empty(), "{issues:?}");
+  }
+}
@@ -0,0 +1,993 @@
+//! 法令XMLファイル群から法令の一覧を作る
+
+use crate::abbrev::split_abbrev;
+use crate::article::articles_of;
+use crate::calendar::{
+  calendar_of, date_from_ad, date_to_ad, era_from_attr, to_iso8601, today, AdDate, CalendarSystem,
+  EraExt, ExtraEra,
+};
+use crate::consistency::{check_era, EraInconsistency, LawConflict};
+use crate::enforcement::enforcement_dates;
+use crate::error::{FileError, ListupError};
+use crate::metadata::{law_attr, law_title_attr, replace_law_era, scan_law_header, LawMetadata};
+use crate::ministry::ministries_of_law_num;
+use crate::path_filter::PathFilter;
+use crate::pipeline::{
+  collect_law_list, spawn_parser, spawn_walker, spawn_walkers, ParsedFile, CHANNEL_CAPACITY,
+};
+use crate::record::{sort_laws, LawRecord, PatchRecord, SortKey};
+use crate::repair::{suggest_fixes, FixSuggestion};
+use crate::shard::Shard;
+use crate::snapshot::file_digest;
+use crate::state::{FileStamp, ListupState};
+use crate::status::{is_repealed, LawStatus};
+use anyhow::Result;
+use japanese_law_xml_schema::law::{Era, Law};
+use jplaw_data_types::law::{Date, LawId, LawPatchInfo};
+use jplaw_io::{info_log, wran_log};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tracing::*;
+
+/// "foobarbaz_20230616_000000000000000.xml"のようなXMLファイル名から読み取れる情報
+#[derive(Debug, Clone)]
+pub struct XmlFileName {
+  /// 法令ID
+  pub id: LawId,
+  /// 改正の日付
+  pub patch_date: Date,
+  /// 改正法令の法令ID
+  pub patch_id: Option<LawId>,
+}
+
+/// XMLファイル名から法令IDを表す部分の文字列だけを取り出す
+pub fn law_id_str_of_file_name(file_name: &str) -> Option<&str> {
+  file_name.split_once('_').map(|(id, _)| id)
+}
+
+/// 法令IDを読み、文字列に戻したときに元の文字列と一致するかを確かめる
+fn parse_law_id(id: &str, file_name: &str) -> Result<LawId, ListupError> {
+  let law_id = LawId::from_str(id).map_err(|_| ListupError::InvalidLawId {
+    file: file_name.to_string(),
+    id: id.to_string(),
+  })?;
+  let parsed = law_id.to_string();
+  if id != parsed {
+    error!("{} != {}({:?})", id, law_id, law_id);
+    return Err(ListupError::LawIdMismatch {
+      file: file_name.to_string(),
+      id: id.to_string(),
+      parsed,
+    });
+  }
+  Ok(law_id)
+}
+
+/// XMLファイル名を解析する
+pub fn parse_file_name(file_name: &str) -> Result<XmlFileName, ListupError> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let path_re = RE.get_or_init(|| {
+    Regex::new(
+      r"(?P<id>[\dA-Za-z]+)_(?P<ad_year>[\d]{4})(?P<month>[\d]{2})(?P<day>[\d]{2})_(?P<patch_id>[\dA-Za-z]+).xml",
+    )
+    .unwrap()
+  });
+  let caps = path_re
+    .captures(file_name)
+    .ok_or_else(|| ListupError::InvalidFileName {
+      file: file_name.to_string(),
+    })?;
+  let law_id = parse_law_id(&caps["id"], file_name)?;
+  info_log("law_id", &law_id.to_string());
+  // 改元の年は月日まで見て元号を決める（20190430は平成31年、20190501は令和元年）
+  let patch_date = date_from_ad(&AdDate::new(
+    caps["ad_year"].parse::<usize>().unwrap(),
+    caps["month"].parse::<usize>().unwrap(),
+    caps["day"].parse::<usize>().unwrap(),
+  ))
+  .map_err(|_| ListupError::InvalidFileName {
+    file: file_name.to_string(),
+  })?;
+  // 改正法令の法令IDの部分は"000000000000000"のように法令IDとして読めない場合がある
+  let re_patch_id_str = &caps["patch_id"];
+  let patch_id = match LawId::from_str(re_patch_id_str) {
+    Ok(_) => Some(parse_law_id(re_patch_id_str, file_name)?),
+    Err(_) => None,
+  };
+  Ok(XmlFileName {
+    id: law_id,
+    patch_date,
+    patch_id,
+  })
+}
+
+/// XMLファイル1つから読み取った情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawFileInfo {
+  /// 法令ID
+  pub id: LawId,
+  /// 公布の日付
+  pub date: Date,
+  /// 公布の年月日
+  pub promulgation_date: Option<Date>,
+  /// 法令名
+  pub name: String,
+  /// 法令番号
+  pub num: String,
+  /// 法令名の読み
+  #[serde(default)]
+  pub name_kana: Option<String>,
+  /// 法令の略称
+  #[serde(default)]
+  pub abbrev: Vec<String>,
+  /// 附則から読み取った施行日
+  #[serde(default)]
+  pub enforcement_dates: Vec<Date>,
+  /// 廃止を表す印があるかどうか
+  #[serde(default)]
+  pub repealed: bool,
+  /// このファイルが表す改正の情報
+  pub patch: PatchRecord,
+  /// 法令番号から復元できるメタデータの修正候補
+  pub fixes: Vec<FixSuggestion>,
+  /// 法令ID・法令番号・`Law`タグの属性の元号と年の食い違い
+  #[serde(default)]
+  pub era_inconsistency: Option<EraInconsistency>,
+}
+
+impl LawFileInfo {
+  /// 解析したXMLファイルとそのファイル名から情報を取り出す
+  pub fn new(law: Law, file_name: XmlFileName, file: &str) -> Self {
+    let fixes = suggest_fixes(&law, &file_name.id.to_string(), file);
+    let LawMetadata {
+      date,
+      promulgation_date,
+      name,
+      num,
+      ..
+    } = LawMetadata::new(&law);
+    if law.law_body.law_title.is_none() {
+      wran_log("not found title", &file);
+    }
+    LawFileInfo {
+      id: file_name.id.clone(),
+      date,
+      promulgation_date,
+      name,
+      num,
+      name_kana: None,
+      abbrev: Vec::new(),
+      enforcement_dates: Vec::new(),
+      repealed: false,
+      patch: PatchRecord {
+        info: LawPatchInfo {
+          id: file_name.id,
+          patch_date: file_name.patch_date,
+          patch_id: file_name.patch_id,
+        },
+        file: file.to_string(),
+        articles: Vec::new(),
+        digest: None,
+        source: None,
+        patch_name: None,
+        patch_num: None,
+      },
+      fixes,
+      era_inconsistency: None,
+    }
+    .with_era_checked()
+  }
+
+  /// `scan_law_header`で読み取ったメタデータとファイル名から情報を作る
+  pub fn from_metadata(metadata: LawMetadata, file_name: XmlFileName, file: &str) -> Self {
+    if metadata.name.is_empty() {
+      wran_log("not found title", &file);
+    }
+    LawFileInfo {
+      id: file_name.id.clone(),
+      date: metadata.date,
+      promulgation_date: metadata.promulgation_date,
+      name: metadata.name,
+      num: metadata.num,
+      name_kana: metadata.name_kana,
+      abbrev: Vec::new(),
+      enforcement_dates: Vec::new(),
+      repealed: false,
+      patch: PatchRecord {
+        info: LawPatchInfo {
+          id: file_name.id,
+          patch_date: file_name.patch_date,
+          patch_id: file_name.patch_id,
+        },
+        file: file.to_string(),
+        articles: Vec::new(),
+        digest: None,
+        source: None,
+        patch_name: None,
+        patch_num: None,
+      },
+      fixes: Vec::new(),
+      era_inconsistency: None,
+    }
+    .with_era_checked()
+  }
+
+  /// 一覧に加える順番（`PatchRecord::order`の順、同じ場合はXMLファイルへのpathの順）
+  pub fn order(&self, other: &Self) -> Ordering {
+    patch_order(&self.patch, &other.patch)
+  }
+
+  /// 元号と年を突き合わせ、食い違っている場合は公布の日付を法令番号に合わせて記録する
+  fn with_era_checked(mut self) -> Self {
+    self.era_inconsistency = check_era(
+      &self.id.to_string(),
+      &self.num,
+      &mut self.date,
+      &self.patch.file,
+    );
+    if let Some(promulgation_date) = &mut self.promulgation_date {
+      promulgation_date.era = self.date.era.clone();
+      promulgation_date.year = self.date.year;
+    }
+    self
+  }
+}
+
+/// 法令一覧を作るときの設定
+#[derive(Debug, Clone, Default)]
+pub struct ListupOptions {
+  /// 処理する法令を絞り込む分割単位
+  pub shard: Option<Shard>,
+  /// XMLファイルの解析を並行して行う数（0の場合は1として扱う）
+  pub jobs: usize,
+  /// ディレクトリの読み込みとファイルの情報の取得を並行して行う数（0の場合は1として扱う）
+  pub walk_jobs: usize,
+  /// 読み込めなかったXMLファイルを飛ばして処理を続ける
+  pub skip_errors: bool,
+  /// 公布された元号で法令を絞り込む（空の場合は絞り込まない）
+  pub eras: Vec<Era>,
+  /// XMLファイルの中身が正しいUTF-8でない場合の扱い
+  pub invalid_utf8: Utf8Policy,
+  /// この日以降に公布された法令だけを含める
+  pub since: Option<AdDate>,
+  /// この日以前に公布された法令だけを含める
+  pub until: Option<AdDate>,
+  /// 1つの法令の改正の数がこの数に達したときに警告を出す
+  pub patch_warn_threshold: Option<usize>,
+  /// 1つの法令の改正の数の上限（超えたXMLファイルは一覧に加えず、エラーとして記録する）
+  pub max_patches: Option<usize>,
+  /// 本文全体を解析せず、`Law`タグの属性と`LawNum`タグ・`LawTitle`タグだけを読む
+  ///
+  /// 施行日・廃止の印・メタデータの修正候補は読み取らない
+  pub fast: bool,
+  /// XMLファイルごとに本則の条を読み取り、`PatchRecord::articles`に入れる
+  pub articles: bool,
+  /// XMLファイルごとに大きさとハッシュ値などを求め、`PatchRecord::digest`に入れる
+  pub snapshots: bool,
+  /// 指定した場合は、pathがいずれかに合うXMLファイルだけを処理する
+  pub include: Vec<PathFilter>,
+  /// pathがいずれかに合うXMLファイルは処理しない
+  pub exclude: Vec<PathFilter>,
+  /// 出力するXMLファイルへのpathの形
+  pub path_style: PathStyle,
+  /// 同じ法令IDのXMLファイルの間で法令名か法令番号が食い違っている場合の扱い
+  pub on_conflict: ConflictPolicy,
+  /// 指定した場合は、改正の日付がこの日以前のXMLファイルだけを一覧に加え、効力の状態もこの日の時点で求める
+  pub as_of: Option<AdDate>,
+  /// 元号の表に無い元号
+  ///
+  /// `Era`属性がこれらの元号のXMLファイルは、警告を出して表の最後の元号の年に読み替える
+  pub extra_eras: Vec<ExtraEra>,
+}
+
+/// 出力するXMLファイルへのpathの形
+///
+/// どちらの場合も区切り文字は`/`にそろえる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+  /// 指定された作業ディレクトリへのpathをそのまま先頭に付ける
+  #[default]
+  Relative,
+  /// 作業ディレクトリへのpathが相対pathの場合は、現在のディレクトリからの絶対pathにする
+  Absolute,
+}
+
+impl FromStr for PathStyle {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "relative" => Ok(PathStyle::Relative),
+      "absolute" => Ok(PathStyle::Absolute),
+      _ => Err(anyhow::anyhow!("unknown path style: {s}")),
+    }
+  }
+}
+
+/// XMLファイルの中身が正しいUTF-8でない場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Utf8Policy {
+  /// エラーにする
+  #[default]
+  Error,
+  /// 不正なバイト列をU+FFFDに置き換え、警告を出して読み込む
+  Replace,
+  /// そのファイルを飛ばし、飛ばしたファイルの一覧に加える
+  Skip,
+}
+
+impl FromStr for Utf8Policy {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "error" => Ok(Utf8Policy::Error),
+      "replace" => Ok(Utf8Policy::Replace),
+      "skip" => Ok(Utf8Policy::Skip),
+      _ => Err(anyhow::anyhow!("unknown UTF-8 policy: {s}")),
+    }
+  }
+}
+
+/// 同じ法令IDのXMLファイルの間で法令名か法令番号が食い違っている場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+  /// 食い違っているXMLファイルを一覧に加えず、エラーとして記録する
+  Error,
+  /// `LawFileInfo::order`の順で先のXMLファイルを使い、後の食い違っているXMLファイルは一覧に加えない
+  First,
+  /// すべてのXMLファイルを一覧に加え、法令名などは改正の日付が最も新しいものを使う
+  #[default]
+  LatestPatch,
+}
+
+impl FromStr for ConflictPolicy {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "error" => Ok(ConflictPolicy::Error),
+      "first" => Ok(ConflictPolicy::First),
+      "latest-patch" => Ok(ConflictPolicy::LatestPatch),
+      _ => Err(anyhow::anyhow!("unknown conflict policy: {s}")),
+    }
+  }
+}
+
+impl ListupOptions {
+  /// XMLファイル名から、そのファイルを処理するかどうかを判定する
+  pub fn is_target(&self, file_name: &str) -> bool {
+    if let Some(shard) = &self.shard {
+      if !law_id_str_of_file_name(file_name).is_some_and(|id| shard.contains(id)) {
+        return false;
+      }
+    }
+    true
+  }
+
+  /// XMLファイルへのpathから、そのファイルを処理するかどうかを判定する
+  ///
+  /// `exclude`に合う場合は`include`に合っても処理しない
+  pub fn includes_path(&self, file_path: &str) -> bool {
+    (self.include.is_empty() || self.include.iter().any(|f| f.matches(file_path)))
+      && !self.exclude.iter().any(|f| f.matches(file_path))
+  }
+
+  /// 読み込めなかったXMLファイルを飛ばして処理を続けるかどうか
+  pub fn skips(&self, err: &ListupError) -> bool {
+    self.skip_errors
+      || (self.invalid_utf8 == Utf8Policy::Skip && matches!(err, ListupError::InvalidUtf8 { .. }))
+  }
+
+  /// 解析したXMLファイルの情報から、その法令を一覧に含めるかどうかを判定する
+  pub fn accepts(&self, info: &LawFileInfo) -> bool {
+    if !self.eras.is_empty()
+      && !self
+        .eras
+        .iter()
+        .any(|era| era.attr() == info.date.era.attr())
+    {
+      return false;
+    }
+    if matches!(self.as_of, Some(as_of) if as_of < date_to_ad(&info.patch.info.patch_date)) {
+      return false;
+    }
+    if self.since.is_some() || self.until.is_some() {
+      let date = date_to_ad(info.promulgation_date.as_ref().unwrap_or(&info.date));
+      if matches!(self.since, Some(since) if date < since)
+        || matches!(self.until, Some(until) if until < date)
+      {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+/// 読み込んだXMLファイル群から作った法令の一覧
+#[derive(Default)]
+pub struct LawList {
+  /// 法令IDごとの法令の情報
+  pub laws: HashMap<LawId, LawRecord>,
+  /// 法令番号から復元できるメタデータの修正候補
+  pub fixes: Vec<FixSuggestion>,
+  /// 法令ID・法令番号・`Law`タグの属性の元号と年の食い違い
+  pub era_inconsistencies: Vec<EraInconsistency>,
+  /// 同じ法令IDのXMLファイルの間での法令名・法令番号の食い違い
+  pub conflicts: Vec<LawConflict>,
+  /// 読み込めずに飛ばしたXMLファイル
+  pub errors: Vec<FileError>,
+  /// 法令IDごとの、これまでに加えた最も新しい改正の`LawRecord::patch`での位置
+  newest_patches: HashMap<LawId, usize>,
+  /// 法令IDと版（改正の日付と改正法令の法令ID）ごとの、最初に加えたXMLファイルの法令名
+  version_names: HashMap<(LawId, String), String>,
+}
+
+impl LawList {
+  /// XMLファイル1つ分の情報を一覧に加える
+  ///
+  /// 法令名などは、加えた順番によらず`LawFileInfo::order`の順で最も新しいファイルのものを使う。
+  /// 改正の数が`options.max_patches`を超える場合は加えずにエラーを返す。
+  /// 先に加えたファイルと法令名か法令番号が食い違っている場合は、`options.on_conflict`に従う。
+  /// 食い違いの扱いは加えた順番で変わるので、まとめて加える場合は`extend_ordered`を使う
+  pub fn push(&mut self, info: LawFileInfo, options: &ListupOptions) -> Result<(), ListupError> {
+    if let Some(conflict) = self.find_conflict(&info) {
+      wran_log("law conflict", &format!("{conflict:?}"));
+      self.conflicts.push(conflict.clone());
+      match options.on_conflict {
+        ConflictPolicy::Error => {
+          return Err(ListupError::LawConflict {
+            file: conflict.file,
+            id: conflict.id,
+            field: conflict.field,
+            existing: conflict.existing,
+            conflicting: conflict.conflicting,
+          })
+        }
+        ConflictPolicy::First => return Ok(()),
+        ConflictPolicy::LatestPatch => (),
+      }
+    }
+    self
+      .version_names
+      .entry((info.id.clone(), version_key(&info.patch.info)))
+      .or_insert_with(|| info.name.clone());
+    if let Some(d) = self.laws.get_mut(&info.id) {
+      let count = d.patch.len() + 1;
+      if let Some(limit) = options.max_patches.filter(|max| *max < count) {
+        return Err(ListupError::TooManyPatches {
+          file: info.patch.file,
+          id: info.id.to_string(),
+          limit,
+        });
+      }
+      if options.patch_warn_threshold == Some(count) {
+        warn!("{} has {count} patches", info.id);
+      }
+      self.fixes.extend(info.fixes);
+      self.era_inconsistencies.extend(info.era_inconsistency);
+      // 最も新しい改正の位置を覚えておき、改正の一覧を毎回なめないようにする
+      let newest = !matches!(
+        self.newest_patches.get(&info.id),
+        Some(&i) if patch_order(&info.patch, &d.patch[i]).is_le()
+      );
+      if newest {
+        self.newest_patches.insert(info.id.clone(), d.patch.len());
+        d.name = info.name;
+        d.name_kana = info.name_kana;
+        d.abbrev = info.abbrev;
+        d.ministries = ministries_of_law_num(&info.num);
+        d.num = info.num;
+        d.date = info.date;
+        d.calendar = calendar_of(info.promulgation_date.as_ref().unwrap_or(&d.date));
+        d.promulgation_date = info.promulgation_date;
+        d.status = LawStatus::new(
+          info.repealed,
+          &info.enforcement_dates,
+          options.as_of.unwrap_or_else(today),
+        );
+        d.enforcement_dates = info.enforcement_dates;
+      }
+      d.patch.push(info.patch);
+    } else {
+      self.fixes.extend(info.fixes);
+      self.era_inconsistencies.extend(info.era_inconsistency);
+      self.newest_patches.insert(info.id.clone(), 0);
+      let calendar = calendar_of(info.promulgation_date.as_ref().unwrap_or(&info.date));
+      if calendar == CalendarSystem::Lunisolar {
+        warn!(
+          "{} was promulgated under the lunisolar calendar; its month and day are kept as is",
+          info.id
+        );
+      }
+      self.laws.insert(
+        info.id.clone(),
+        LawRecord {
+          id: info.id,
+          name: info.name,
+          name_kana: info.name_kana,
+          abbrev: info.abbrev,
+          ministries: ministries_of_law_num(&info.num),
+          num: info.num,
+          date: info.date,
+          promulgation_date: info.promulgation_date,
+          calendar,
+          status: LawStatus::new(
+            info.repealed,
+            &info.enforcement_dates,
+            options.as_of.unwrap_or_else(today),
+          ),
+          enforcement_dates: info.enforcement_dates,
+          historical_names: Vec::new(),
+          patch: vec![info.patch],
+        },
+      );
+    }
+    Ok(())
+  }
+
+  /// 先に加えた同じ法令IDのXMLファイルと、法令番号か同じ版の法令名が食い違っているかを調べる
+  fn find_conflict(&self, info: &LawFileInfo) -> Option<LawConflict> {
+    let law = self.laws.get(&info.id)?;
+    let conflict = |field: &str, existing: &str, conflicting: &str| LawConflict {
+      id: info.id.to_string(),
+      file: info.patch.file.clone(),
+      field: field.to_string(),
+      existing: existing.to_string(),
+      conflicting: conflicting.to_string(),
+    };
+    if law.num != info.num {
+      return Some(conflict("num", &law.num, &info.num));
+    }
+    let name = self
+      .version_names
+      .get(&(info.id.clone(), version_key(&info.patch.info)))?;
+    (*name != info.name).then(|| conflict("name", name, &info.name))
+  }
+
+  /// XMLファイル群の情報を`LawFileInfo::order`の順に並べてから一覧に加える
+  ///
+  /// 解析を並行して行った場合でも、読み込んだ順番によらず同じ一覧になる
+  pub fn extend_ordered(&mut self, mut infos: Vec<LawFileInfo>, options: &ListupOptions) {
+    infos.sort_by(LawFileInfo::order);
+    for info in infos {
+      self.push_or_record(info, options);
+    }
+  }
+
+  /// 修正候補・元号の食い違い・法令名などの食い違い・読み込めなかったXMLファイルを、法令IDとファイルの順に並べる
+  pub fn sort_reports(&mut self) {
+    self
+      .fixes
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self
+      .era_inconsistencies
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self
+      .conflicts
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self.errors.sort_by(|a, b| a.file.cmp(&b.file));
+  }
+
+  /// 法令の一覧を`key`の順に並べて取り出す
+  ///
+  /// 改正の一覧を並べた後に、版ごとの法令名から`historical_names`を埋める
+  pub fn into_laws(self, key: SortKey) -> Vec<LawRecord> {
+    let mut laws = self.laws.into_values().collect::<Vec<_>>();
+    sort_laws(&mut laws, key);
+    for law in laws.iter_mut() {
+      law.historical_names = historical_names(law, &self.version_names);
+    }
+    laws
+  }
+
+  /// XMLファイル1つ分の情報を一覧に加え、加えられなかった場合はエラーとして記録する
+  pub fn push_or_record(&mut self, info: LawFileInfo, options: &ListupOptions) {
+    let file = info.patch.file.clone();
+    if let Err(err) = self.push(info, options) {
+      wran_log("skip file", &err.to_string());
+      self.errors.push(FileError::new(&file, &err));
+    }
+  }
+}
+
+/// 改正の一覧の順に版ごとの法令名をたどり、法令名が変わった版の改正の日付と法令名を集める
+///
+/// 法令名が1つしか無い場合は空を返す
+fn historical_names(
+  law: &LawRecord,
+  version_names: &HashMap<(LawId, String), String>,
+) -> Vec<(Date, String)> {
+  let mut names: Vec<(Date, String)> = Vec::new();
+  for patch in &law.patch {
+    let Some(name) = version_names.get(&(law.id.clone(), version_key(&patch.info))) else {
+      continue;
+    };
+    if names.last().map(|(_, last)| last != name).unwrap_or(true) {
+      names.push((patch.info.patch_date.clone(), name.clone()));
+    }
+  }
+  if names.len() < 2 {
+    names.clear();
+  }
+  names
+}
+
+/// `PatchRecord::order`の順、同じ場合はXMLファイルへのpathの順
+fn patch_order(a: &PatchRecord, b: &PatchRecord) -> Ordering {
+  a.order(b).then_with(|| a.file.cmp(&b.file))
+}
+
+/// 改正の日付と改正法令の法令IDで、同じ法令の版を見分ける
+fn version_key(patch: &LawPatchInfo) -> String {
+  format!(
+    "{}_{}",
+    to_iso8601(&patch.patch_date),
+    patch
+      .patch_id
+      .as_ref()
+      .map(|id| id.to_string())
+      .unwrap_or_default()
+  )
+}
+
+/// 作業ディレクトリの中の処理対象のXMLファイル
+#[derive(Debug, Clone)]
+pub struct XmlFile {
+  /// ファイル名
+  pub file_name: String,
+  /// ファイルへのpath
+  pub file_path: String,
+  /// 更新日時と大きさ（`with_stamp`を指定して一覧を作った場合のみ）
+  pub stamp: Option<FileStamp>,
+  /// ファイルを見つけた作業ディレクトリ（作業ディレクトリを複数指定した場合のみ）
+  pub source: Option<String>,
+}
+
+/// e-govで配布されているファイルは"法令データ一式/foobarbaz/foobarbaz.xml"のような形で配布されていて、、
+/// work_dirに"法令データ一式"が入ると想定している
+///
+/// 一覧の順番は決まっていない。
+/// `with_stamp`が`true`の場合は、ファイルの更新日時と大きさも読む
+pub async fn get_xml_file_lst(
+  work_dir: &str,
+  options: &ListupOptions,
+  with_stamp: bool,
+) -> Result<Vec<XmlFile>> {
+  let (mut rx, walker) = spawn_walker(work_dir, options, with_stamp);
+  let mut files = Vec::new();
+  while let Some(file) = rx.recv().await {
+    files.push(file);
+  }
+  walker.await??;
+  Ok(files)
+}
+
+/// 作業ディレクトリの中のXMLファイルをすべて解析して法令の一覧を作る
+///
+/// XMLファイルの解析は`options.jobs`個まで並行して行う。
+///
+/// `state`を渡すと、前回から更新日時と大きさが変わっていないファイルは解析せずに`state`に記録された情報を使い、
+/// 処理後の`state`は今回読み込んだファイルの情報で置き換えられる
+pub async fn get_law_info_lst(
+  work_dir: &str,
+  options: &ListupOptions,
+  state: Option<&mut ListupState>,
+) -> Result<LawList> {
+  get_law_info_lst_from_dirs(&[work_dir.to_string()], options, state).await
+}
+
+/// 複数の作業ディレクトリの中のXMLファイルをすべて解析して1つの法令の一覧を作る
+///
+/// 作業ディレクトリが2つ以上の場合は、改正ごとにXMLファイルを見つけた作業ディレクトリを`PatchRecord::source`に記録する
+pub async fn get_law_info_lst_from_dirs(
+  work_dirs: &[String],
+  options: &ListupOptions,
+  mut state: Option<&mut ListupState>,
+) -> Result<LawList> {
+  let (files, walker) = spawn_walkers(work_dirs, options, state.is_some());
+  // 解析の設定が違う記録の解析結果は使えないので、記録し直す
+  let old_state = state
+    .as_mut()
+    .map(|state| Arc::new(std::mem::take(&mut **state)))
+    .filter(|state| state.matches(options));
+  let (parsed, parser) = spawn_parser(files, options, old_state);
+  let (mut law_list, new_state) = collect_law_list(parsed, options).await;
+  law_list.errors.extend(walker.await??);
+  law_list.errors.extend(parser.await??);
+  law_list.sort_reports();
+  if let Some(state) = state {
+    *state = new_state;
+  }
+  Ok(law_list)
+}
+
+/// `--work`に指定されたpathのglobパターンを展開し、作業ディレクトリの一覧にする
+///
+/// パターンに合うディレクトリが無い場合はエラーにする
+pub fn expand_work_dirs(patterns: &[String]) -> Result<Vec<String>> {
+  let mut work_dirs = Vec::new();
+  for pattern in patterns {
+    let mut found = false;
+    for path in glob::glob(pattern)? {
+      let path = path?;
+      if path.is_dir() {
+        let dir = path
+          .to_str()
+          .ok_or_else(|| anyhow::anyhow!("non UTF-8 work dir: {}", path.display()))?;
+        if !work_dirs.iter().any(|d| d == dir) {
+          work_dirs.push(dir.to_string());
+        }
+        found = true;
+      }
+    }
+    if !found {
+      return Err(anyhow::anyhow!("no work dir matches: {pattern}"));
+    }
+  }
+  Ok(work_dirs)
+}
+
+/// 作業ディレクトリの中のXMLファイルを解析し、解析できた順に1ファイル分ずつ流す
+///
+/// 法令の一覧がすべてできるのを待たずに、見つかった順に自前のデータベースなどに書き込む場合に使う。
+/// 1つの法令の改正ごとの版は別々に流れてくるので、法令ごとにまとめる場合は`LawList::push`を使う。
+/// `options`の条件に合わないファイルは流さない。
+/// 解析に失敗した場合は、`options.skip_errors`が`false`ならそのエラーを最後に流して終わる
+pub fn scan_laws(
+  work_dir: &str,
+  options: &ListupOptions,
+) -> impl Stream<Item = Result<LawFileInfo>> {
+  let (tx, rx) = tokio::sync::mpsc::channel(CHANNEL_CAPACITY);
+  let (files, walker) = spawn_walker(work_dir, options, false);
+  let (mut parsed, parser) = spawn_parser(files, options, None);
+  let options = options.clone();
+  tokio::spawn(async move {
+    while let Some(ParsedFile { info, .. }) = parsed.recv().await {
+      if options.accepts(&info) && tx.send(Ok(info)).await.is_err() {
+        return;
+      }
+    }
+    let res = async {
+      walker.await??;
+      parser.await??;
+      Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    if let Err(err) = res {
+      let _ = tx.send(Err(err)).await;
+    }
+  });
+  ReceiverStream::new(rx)
+}
+
+/// XMLファイル1つを解析する
+pub fn read_law_file(
+  file_name: &str,
+  file_path: &str,
+  options: &ListupOptions,
+) -> Result<LawFileInfo, ListupError> {
+  let file_name = parse_file_name(file_name)?;
+  let read = || {
+    std::fs::read(file_path).map_err(|source| ListupError::Io {
+      file: file_path.to_string(),
+      source,
+    })
+  };
+  // 一時的なエラーの場合はファイルを開き直して一度だけ読み直す
+  let buf = match read() {
+    Err(err) if err.is_transient() => {
+      wran_log("retry reading file", &err.to_string());
+      read()?
+    }
+    res => res?,
+  };
+  parse_law_bytes(buf, file_name, file_path, options)
+}
+
+/// 解析の前に、XMLファイルが空でないことと、最後が`</Law>`で終わっていることを確かめる
+///
+/// 中断されたダウンロードなどで途中までしか無いファイルを、XMLの解析エラーと区別して記録するため
+fn probe_complete(buf: &[u8], file_path: &str) -> Result<(), ListupError> {
+  let Some(last) = buf.iter().rposition(|b| !b.is_ascii_whitespace()) else {
+    return Err(ListupError::EmptyFile {
+      file: file_path.to_string(),
+    });
+  };
+  if !buf[..=last].ends_with(b"</Law>") {
+    return Err(ListupError::TruncatedFile {
+      file: file_path.to_string(),
+    });
+  }
+  Ok(())
+}
+
+/// XMLファイルの中身が正しいUTF-8でない場合の扱いを`options.invalid_utf8`に従って決めてから解析する
+fn parse_law_bytes(
+  buf: Vec<u8>,
+  file_name: XmlFileName,
+  file_path: &str,
+  options: &ListupOptions,
+) -> Result<LawFileInfo, ListupError> {
+  probe_complete(&buf, file_path)?;
+  let digest = options
+    .snapshots
+    .then(|| file_digest(&buf, &file_name.patch_date));
+  let buf = match std::str::from_utf8(&buf) {
+    Ok(_) => buf,
+    Err(e) => match options.invalid_utf8 {
+      Utf8Policy::Replace => {
+        wran_log("invalid UTF-8", &file_path);
+        String::from_utf8_lossy(&buf).into_owned().into_bytes()
+      }
+      Utf8Policy::Error | Utf8Policy::Skip => {
+        return Err(ListupError::InvalidUtf8 {
+          file: file_path.to_string(),
+          valid_up_to: e.valid_up_to(),
+        })
+      }
+    },
+  };
+  let buf = fallback_unknown_era(buf, file_path, options)?;
+  if options.fast {
+    let metadata = scan_law_header(&buf).map_err(|e| ListupError::Xml {
+      file: file_path.to_string(),
+      message: e.to_string(),
+    })?;
+    let mut info = LawFileInfo::from_metadata(metadata, file_name, file_path);
+    let xml = String::from_utf8_lossy(&buf);
+    info.abbrev = law_title_attr(&xml, "Abbrev")
+      .map(|attr| split_abbrev(&attr))
+      .unwrap_or_default();
+    if options.articles {
+      info.patch.articles = articles_of(&xml);
+    }
+    info.patch.digest = digest;
+    return Ok(info);
+  }
+  let law = japanese_law_xml_schema::parse_xml(&buf).map_err(|e| ListupError::Xml {
+    file: file_path.to_string(),
+    message: e.to_string(),
+  })?;
+  let mut info = LawFileInfo::new(law, file_name, file_path);
+  let xml = String::from_utf8_lossy(&buf);
+  info.enforcement_dates =
+    enforcement_dates(&xml, info.promulgation_date.as_ref().unwrap_or(&info.date));
+  info.repealed = is_repealed(&xml);
+  info.name_kana = law_title_attr(&xml, "Kana");
+  info.abbrev = law_title_attr(&xml, "Abbrev")
+    .map(|attr| split_abbrev(&attr))
+    .unwrap_or_default();
+  if options.articles {
+    info.patch.articles = articles_of(&xml);
+  }
+  info.patch.digest = digest;
+  Ok(info)
+}
+
+/// `Law`タグの`Era`属性が元号の表に無い場合に、`ListupOptions::extra_eras`を使って読み替える
+///
+/// `Era`属性や`Year`属性が無い場合はそのまま返し、XMLの解析のエラーにする
+fn fallback_unknown_era(
+  buf: Vec<u8>,
+  file_path: &str,
+  options: &ListupOptions,
+) -> Result<Vec<u8>, ListupError> {
+  let xml = String::from_utf8_lossy(&buf);
+  let (Some(era), Some(year)) = (law_attr(&xml, "Era"), law_attr(&xml, "Year")) else {
+    return Ok(buf);
+  };
+  if era_from_attr(&era).is_some() {
+    return Ok(buf);
+  }
+  let read_as = year.parse().ok().and_then(|year| {
+    options
+      .extra_eras
+      .iter()
+      .find(|extra| extra.attr == era)?
+      .to_known_era(year)
+  });
+  let Some((known_era, known_year)) = read_as else {
+    return Err(ListupError::UnknownEra {
+      file: file_path.to_string(),
+      era,
+    });
+  };
+  wran_log(
+    &format!(
+      "unknown era {era} {year} is read as {} {known_year}",
+      known_era.attr()
+    ),
+    &file_path,
+  );
+  Ok(replace_law_era(&xml, &known_era, known_year).into_bytes())
+}
+
+/// e-govで配布されているZIPファイルを展開せずに読み込む
+///
+/// ZIPファイルの中の".xml"で終わるファイルをすべて対象にする
+pub fn get_law_info_lst_from_zip(zip_path: &str, options: &ListupOptions) -> Result<LawList> {
+  let mut law_list = LawList::default();
+  let mut infos = Vec::new();
+  let mut archive = zip::ZipArchive::new(std::fs::File::open(zip_path)?)?;
+  for i in 0..archive.len() {
+    let mut entry = archive.by_index(i)?;
+    if !entry.is_file() || !entry.name().ends_with(".xml") {
+      continue;
+    }
+    let entry_name = entry.name().to_string();
+    let file_name_string = entry_name.rsplit('/').next().unwrap_or(&entry_name);
+    let file_path = format!("{zip_path}/{entry_name}");
+    info_log("xml path", &file_path);
+    if !options.is_target(file_name_string) || !options.includes_path(&file_path) {
+      continue;
+    }
+    match read_law_reader(&mut entry, file_name_string, &file_path, options) {
+      Ok(info) if options.accepts(&info) => infos.push(info),
+      Ok(_) => (),
+      Err(err) if options.skips(&err) => {
+        wran_log("skip file", &err.to_string());
+        law_list.errors.push(FileError::new(&file_path, &err));
+      }
+      Err(err) => return Err(err.into()),
+    }
+  }
+  law_list.extend_ordered(infos, options);
+  law_list.sort_reports();
+  Ok(law_list)
+}
+
+/// 一覧に書かれたXMLファイルへのpathから、XMLファイルの中身を読み込む
+///
+/// `get_law_info_lst_from_zip`で作った"ZIPファイルへのpath/ZIPファイルの中のpath"の形のpathの場合は、
+/// ZIPファイルの中から読む。一度開いたZIPファイルは使い回す
+#[derive(Default)]
+pub struct XmlFileReader {
+  archives: HashMap<String, zip::ZipArchive<std::fs::File>>,
+}
+
+impl XmlFileReader {
+  /// XMLファイル1つの中身を読み込む
+  pub fn read(&mut self, file: &str) -> Result<Vec<u8>> {
+    let zip_entry = file
+      .match_indices(".zip/")
+      .map(|(i, _)| (&file[..i + 4], &file[i + 5..]))
+      .find(|(zip_path, _)| std::path::Path::new(zip_path).is_file());
+    let Some((zip_path, entry_name)) = zip_entry else {
+      return Ok(std::fs::read(file)?);
+    };
+    let archive = match self.archives.entry(zip_path.to_string()) {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => entry.insert(zip::ZipArchive::new(std::fs::File::open(zip_path)?)?),
+    };
+    let mut entry = archive
+      .by_name(entry_name)
+      .map_err(|e| anyhow::anyhow!("{file}: {e}"))?;
+    let mut buf = Vec::new();
+    entry.read_to_end(&mut buf)?;
+    Ok(buf)
+  }
+}
+
+/// ZIPファイルの中のファイルのような、pathで開けないXMLファイル1つを解析する
+fn read_law_reader<R: Read>(
+  reader: &mut R,
+  file_name: &str,
+  file_path: &str,
+  options: &ListupOptions,
+) -> Result<LawFileInfo, ListupError> {
+  let file_name = parse_file_name(file_name)?;
+  let mut buf = Vec::new();
+  reader
+    .read_to_end(&mut buf)
+    .map_err(|source| ListupError::Io {
+      file: file_path.to_string(),
+      source,
+    })?;
+  parse_law_bytes(buf, file_name, file_path, options)
+}
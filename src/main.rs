@@ -1,148 +1,1142 @@
 #![recursion_limit = "256"]
 use anyhow::{anyhow, Result};
-use clap::Parser;
-use jplaw_data_types::{
-  self,
-  law::{Date, LawId, LawPatchInfo},
-  listup::LawInfo,
+use clap::{CommandFactory, Parser, Subcommand};
+use jplaw_io::init_logger;
+#[cfg(feature = "search-index")]
+use listup_law::search_index::build_law_index;
+use listup_law::{
+  abbrev::{apply_abbrev, read_abbrev_csv},
+  badge::Badge,
+  calendar::{to_iso8601, AdDate, ExtraEra},
+  citation::cited_laws,
+  config::expand_config_args,
+  consistency::{find_dangling_patches, DanglingPatch, EraInconsistency, LawConflict},
+  diff::diff_law_lists,
+  filter::FilterConfig,
+  graph::{GraphFormat, LawGraph},
+  index::read_law_index,
+  index::{LawIndex, LawIndexSet},
+  jsonrpc::{init_jsonrpc_logger, notify},
+  lint::{lint_laws, Severity},
+  listup::{
+    expand_work_dirs, get_law_info_lst, get_law_info_lst_from_dirs, get_law_info_lst_from_zip,
+    read_law_file, ConflictPolicy, ListupOptions, PathStyle, Utf8Policy, XmlFileReader,
+  },
+  merge::merge_law_lists,
+  normalize::{normalize_laws, Normalization},
+  output::{write_law_lst, write_outputs, OutputFormat, OutputSpec, RecordGranularity, SplitKey},
+  record::{resolve_patch_laws, sort_laws, LawRecord, OutputHeader, OutputWarnings, SortKey},
+  schema::output_schema,
+  search::{search_laws, SearchQuery},
+  spill::{spill_law_info_lst, LawWriter},
+  state::ListupState,
+  stats::{churn_by_ministry_year, law_churn, LawStats},
+  subset::{copy_law_files, read_id_list, subset_laws},
+  tags::unknown_tag_report,
+  validate::{
+    apply_law_list_kana, apply_law_list_status, read_law_list_csvs, validate_laws, LawListEntry,
+  },
 };
-use jplaw_io::{
-  end_log, flush_file_value_lst, gen_file_value_lst, info_log, init_logger, start_log, wran_log,
-  write_value_lst,
-};
-use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
 use std::collections::HashMap;
-use std::path::Path;
-use std::str::FromStr;
+use std::path::{Path, PathBuf};
 use tokio::fs::*;
-use tokio_stream::StreamExt;
 use tracing::*;
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+  #[clap(subcommand)]
+  command: Option<Command>,
+  /// オプションを書いたTOMLファイルへのpath（コマンドラインで指定したオプションを優先する）
+  #[clap(long, value_parser)]
+  config: Option<String>,
   /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
-  #[clap(short, long, value_parser)]
-  work: String,
-  /// 解析結果を出力するJSONファイルへのpath
-  #[clap(short, long, value_parser)]
-  output: String,
-}
-
-/// e-govで配布されているファイルは"法令データ一式/foobarbaz/foobarbaz.xml"のような形で配布されていて、、
-/// work_dirに"法令データ一式"が入ると想定している
-async fn get_law_info_lst(work_dir: &str) -> Result<HashMap<LawId, LawInfo>> {
-  let mut info_lst: HashMap<LawId, LawInfo> = HashMap::new();
-  let mut work_dir_info = read_dir(work_dir).await?;
-  let path_re = Regex::new(
-    r"(?P<id>[\dA-Za-z]+)_(?P<ad_year>[\d]{4})(?P<month>[\d]{2})(?P<day>[\d]{2})_(?P<patch_id>[\dA-Za-z]+).xml",
-  )?;
-  while let Some(dir_entry) = work_dir_info.next_entry().await? {
-    if dir_entry.file_type().await?.is_dir() {
-      let new_path = Path::new(work_dir).join(dir_entry.file_name());
-      let mut new_dir = read_dir(&new_path).await?;
-      while let Some(new_entry) = new_dir.next_entry().await? {
-        if new_entry.file_type().await?.is_file() {
-          let dir_string = dir_entry.file_name().to_str().unwrap().to_string();
-          let file_name_osstr = new_entry.file_name();
-          let file_name_string = file_name_osstr.to_str().unwrap().to_string();
-          let file_path = format!("{work_dir}/{dir_string}/{file_name_string}");
-          info_log("xml path", &file_path);
-          let law = japanese_law_xml_schema::parse_xml_file(&file_path)?;
-          let date = Date::new(law.era, law.year, None, None);
-          let caps = path_re
-            .captures(&file_name_string)
-            .ok_or(anyhow!("cannot parse file path"))?;
-          let re_law_id_str = &caps["id"];
-          let law_id = LawId::from_str(re_law_id_str).unwrap();
-          info_log("law_id", &law_id.to_string());
-          if re_law_id_str != format!("{law_id}") {
-            error!("{} != {}({:?})", re_law_id_str, law_id, law_id);
-            panic!()
-          }
-          if let Some(d) = info_lst.get(&law_id) {
-            let patch_date = Date::gen_from_ad(
-              caps["ad_year"].parse::<usize>().unwrap(),
-              caps["month"].parse::<usize>().unwrap(),
-              caps["day"].parse::<usize>().unwrap(),
-            );
-            let re_patch_id_str = &caps["patch_id"];
-            let patch_id = LawId::from_str(re_patch_id_str).ok();
-            if let Some(id) = &patch_id {
-              let s = format!("{id}");
-              if re_patch_id_str != s {
-                error!("{} != {}({:?})", re_patch_id_str, id, id);
-                panic!()
-              }
-            }
-            let mut patch = d.clone().patch;
-            patch.push(LawPatchInfo {
-              id: law_id.clone(),
-              patch_date,
-              patch_id,
-            });
-            info_lst.insert(law_id, LawInfo { patch, ..d.clone() });
-          } else {
-            let num = law.law_num;
-            let name = if let Some(title) = law.law_body.law_title {
-              title.text.to_string()
-            } else {
-              wran_log("not found title", &file_name_string);
-              String::new()
-            };
-            let patch_date = Date::gen_from_ad(
-              caps["ad_year"].parse::<usize>().unwrap(),
-              caps["month"].parse::<usize>().unwrap(),
-              caps["day"].parse::<usize>().unwrap(),
-            );
-            let patch_id = LawId::from_str(&caps["patch_id"]).ok();
-            info_lst.insert(
-              law_id.clone(),
-              LawInfo {
-                date,
-                name,
-                num,
-                id: law_id.clone(),
-                patch: vec![LawPatchInfo {
-                  id: law_id,
-                  patch_date,
-                  patch_id,
-                }],
-              },
-            );
-          }
-        }
+  ///
+  /// 複数指定でき、`data/*/法令データ一式`のようなglobパターンも使える
+  #[clap(short, long, value_parser, required_unless_present = "work_zip")]
+  work: Vec<String>,
+  /// 法令XMLファイル群をまとめたZIPファイルへのpath（展開せずに読み込む）
+  #[clap(long, value_parser, conflicts_with = "work")]
+  work_zip: Option<String>,
+  /// 解析結果を出力するファイルへのpath
+  ///
+  /// 複数指定でき、`heisei.csv,format=csv,era=Heisei`のように出力先ごとに形式や絞り込みの条件を書ける
+  #[clap(short, long, value_parser, required = true)]
+  output: Vec<OutputSpec>,
+  /// 出力形式（`json`・`jsonl`・`csv`）
+  #[clap(long, value_parser, default_value = "json")]
+  format: OutputFormat,
+  /// JSON形式の出力に作成日時などの情報を付けず、法令の情報の配列だけを書く（出力の形式の版1の形）
+  #[clap(long)]
+  legacy_format: bool,
+  /// 法令名などの文字列のUnicode正規化形式（`nfc`・`nfkc`）
+  #[clap(long, value_parser, default_value = "nfc")]
+  normalize: Normalization,
+  /// 出力を元号・年・法令の種類ごとのファイルに分ける（`era`・`year`・`law-type`）。
+  /// `output.json`は`output_reiwa.json`のように、拡張子の前に単位を表す名前を付けたファイルに書き出す
+  #[clap(long, value_parser)]
+  split_by: Option<SplitKey>,
+  /// 法令の並べ方（`id`・`date`・`name`）
+  #[clap(long, value_parser, default_value = "id")]
+  sort_by: SortKey,
+  /// 出力する1行の単位（`law`は法令ごと、`snapshot`は改正ごとのXMLファイルごと、`article`は本則の条ごと）
+  #[clap(long, value_parser, default_value = "law")]
+  record_granularity: RecordGranularity,
+  /// 1列目に法令ID、2列目に略称を書いたCSVファイルへのpath
+  #[clap(long, value_parser)]
+  abbrev_csv: Option<String>,
+  /// e-Govで配布されている法令の一覧（all_law_list.csv）へのpath。
+  /// 改正法令が読み込んだ法令の中に無い場合に、その法令名と法令番号をここから探す
+  ///
+  /// 法令の種類ごとの一覧のように複数指定でき、同じ法令IDが複数の一覧にある場合は先に指定したものを使う
+  #[clap(long, value_parser)]
+  law_list_csv: Vec<String>,
+  /// 法令番号から復元できるメタデータの修正候補を出力するJSONファイルへのpath
+  #[clap(long, value_parser)]
+  suggest_fixes: Option<String>,
+  /// 法令ID・法令番号・`Law`タグの属性の元号と年の食い違いを出力するJSONファイルへのpath
+  #[clap(long, value_parser)]
+  era_report: Option<String>,
+  /// `Law`タグの`Era`属性と`Year`属性を法令番号・法令IDと突き合わせ、食い違いを1件ずつ警告し、
+  /// 食い違いがあった場合はすべて書き出した後にエラーで終了する
+  #[clap(long)]
+  strict: bool,
+  /// 法令の数や作成日時をまとめたJSONファイルへのpath
+  ///
+  /// `--law-list-csv`を指定した場合は、その一覧に載っている法令のうち手元にある割合も書く
+  #[clap(long, value_parser)]
+  badge: Option<String>,
+  /// 法令の数を表示するshields.ioのendpoint badge用のJSONファイルへのpath
+  #[clap(long, value_parser)]
+  badge_shields: Option<String>,
+  #[clap(flatten)]
+  filter: FilterConfig,
+  /// 出力するXMLファイルへのpathの形（`relative`は`--work`の指定のまま、`absolute`は絶対path）
+  #[clap(long, value_parser, default_value = "relative")]
+  path_style: PathStyle,
+  /// XMLファイルの解析を並行して行う数
+  #[clap(short, long, value_parser, default_value_t = 1)]
+  jobs: usize,
+  /// ディレクトリの読み込みとファイルの情報の取得を並行して行う数
+  #[clap(long, value_parser, default_value_t = 1)]
+  walk_jobs: usize,
+  /// 読み込めなかったXMLファイルを飛ばして処理を続ける
+  #[clap(long, conflicts_with = "fail_fast")]
+  skip_errors: bool,
+  /// 読み込めないXMLファイルがあった時点で処理を止める（デフォルト）
+  #[clap(long)]
+  fail_fast: bool,
+  /// XMLファイルの中身が正しいUTF-8でない場合の扱い（`error`・`replace`・`skip`）
+  #[clap(long, value_parser, default_value = "error")]
+  invalid_utf8: Utf8Policy,
+  /// 元号の表に無い元号を`Reiwa2:2040`のように`Era`属性での表記と元年の西暦年で指定する（複数指定可）
+  ///
+  /// その元号のXMLファイルは警告を出して令和の年に読み替える。指定しない元号のXMLファイルは読み込めないものとして扱う
+  #[clap(long = "extra-era", value_parser)]
+  extra_eras: Vec<ExtraEra>,
+  /// `2020-04-01`のように指定すると、法令ごとに改正の日付がその日以前で最も新しいXMLファイルだけを出力する
+  ///
+  /// 法令名などもそのXMLファイルのものを使い、その日に公布されていない法令は出力しない
+  #[clap(long, value_parser)]
+  as_of: Option<AdDate>,
+  /// 改正の一覧（`patch`）を、法令ごとに最も新しいXMLファイルだけにして出力する
+  #[clap(long)]
+  latest_only: bool,
+  /// 同じ法令IDのXMLファイルの間で法令名か法令番号が食い違っている場合の扱い
+  ///
+  /// `error`は食い違ったXMLファイルをエラーとし（`--skip-errors`が無い場合は終了する）、
+  /// `first`は改正の日付・改正法令の法令ID・ファイルへのpathの順で先のものを使い、`latest-patch`は最も後のものの法令名などを使う
+  #[clap(long, value_parser, default_value = "latest-patch")]
+  on_conflict: ConflictPolicy,
+  /// 同じ法令IDのXMLファイルの間での法令名・法令番号の食い違いを出力するJSONファイルへのpath
+  #[clap(long, value_parser)]
+  conflict_report: Option<String>,
+  /// `--skip-errors`で飛ばしたXMLファイルとエラーの内容を出力するJSONファイルへのpath
+  #[clap(long, value_parser)]
+  error_report: Option<String>,
+  /// 1つの法令の改正の数がこの数に達したときに警告を出す
+  #[clap(long, value_parser, default_value_t = 1000)]
+  patch_warn_threshold: usize,
+  /// 1つの法令の改正の数の上限（超えたXMLファイルは一覧に加えず、エラーの一覧に記録する）
+  #[clap(long, value_parser)]
+  max_patches: Option<usize>,
+  /// 本文全体を解析せず、`Law`タグの属性と法令番号・法令名だけを読んで速く一覧を作る
+  ///
+  /// 施行日・廃止の状態・メタデータの修正候補は出力されない
+  #[clap(long)]
+  fast: bool,
+  /// ログ・進み具合・結果をJSON-RPC 2.0の通知として1行ずつ標準出力に書く
+  #[clap(long)]
+  jsonrpc: bool,
+  /// 開発者向けに、XMLファイルに現れるが一覧の作成に使っていない要素の出現回数を出力するJSONファイルへのpath
+  #[clap(long, value_parser)]
+  debug_unknown_tags: Option<String>,
+  /// 前回の実行から変更の無いXMLファイルの解析を省く
+  #[clap(long, requires = "state", conflicts_with = "work_zip")]
+  incremental: bool,
+  /// `--incremental`で使う、解析済みのXMLファイルの情報を記録するJSONファイルへのpath
+  #[clap(long, value_parser, requires = "incremental")]
+  state: Option<String>,
+  /// 指定すると、この数のXMLファイルの情報がたまるごとに一時ファイルに書き出し、メモリの使用量を抑える
+  ///
+  /// 出力形式は`json`・`jsonl`、並べ方は`id`、出力の単位は`law`のみ対応する
+  #[clap(
+    long,
+    value_parser,
+    conflicts_with_all = ["work_zip", "incremental", "badge", "badge_shields", "debug_unknown_tags"]
+  )]
+  memory_cap: Option<usize>,
+  /// `--memory-cap`で使う一時ファイルを置くディレクトリへのpath（デフォルトはOSの一時ディレクトリの中）
+  #[clap(long, value_parser, requires = "memory_cap")]
+  spill_dir: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// XMLファイル1つから読み取った情報をJSONで表示する
+  Inspect {
+    /// 法令XMLファイルへのpath
+    #[clap(value_parser)]
+    file: String,
+  },
+  /// 書き出した法令の一覧の中身が整合しているかを調べる
+  Lint {
+    /// 法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    input: String,
+    /// 改正ごとのXMLファイルが存在するかも調べる
+    #[clap(long)]
+    check_files: bool,
+  },
+  /// 法令の一覧から指定した法令だけを取り出す
+  Subset {
+    /// 法令の一覧のファイルへのpath
+    #[clap(long, value_parser)]
+    input: String,
+    /// 取り出す法令の法令IDを1行に1つ書いたファイルへのpath
+    #[clap(long, value_parser)]
+    ids: String,
+    /// 取り出した法令の一覧を出力するファイルへのpath
+    #[clap(short, long, value_parser)]
+    output: String,
+    /// 出力形式（`json`・`jsonl`・`csv`）
+    #[clap(long, value_parser, default_value = "json")]
+    format: OutputFormat,
+    /// 取り出した法令のXMLファイルをコピーするフォルダへのpath
+    #[clap(long, value_parser)]
+    copy_files: Option<String>,
+    /// JSON形式の出力に作成日時などの情報を付けず、法令の情報の配列だけを書く
+    #[clap(long)]
+    legacy_format: bool,
+  },
+  /// 別々に作った法令の一覧を法令IDごとに1つにまとめる
+  Merge {
+    /// まとめる法令の一覧のファイルへのpath（法令名などが同じ新しさの場合は先に指定したものを使う）
+    #[clap(value_parser, required = true)]
+    inputs: Vec<String>,
+    /// まとめた法令の一覧を出力するファイルへのpath
+    #[clap(short, long, value_parser)]
+    output: String,
+    /// 出力形式（`json`・`jsonl`・`csv`）
+    #[clap(long, value_parser, default_value = "json")]
+    format: OutputFormat,
+    /// JSON形式の出力に作成日時などの情報を付けず、法令の情報の配列だけを書く
+    #[clap(long)]
+    legacy_format: bool,
+  },
+  /// 書き出した法令の一覧を元号・年代・種類ごとに集計する
+  Stats {
+    /// 法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    input: String,
+    /// 表ではなくJSONで表示する
+    #[clap(long)]
+    json: bool,
+    /// 府省など・年ごとの改正の数を書き出すCSVファイルへのpath
+    #[clap(long, value_parser)]
+    churn_csv: Option<String>,
+    /// 法令ごとの改正の間の日数の平均を書き出すCSVファイルへのpath
+    #[clap(long, value_parser)]
+    interval_csv: Option<String>,
+  },
+  /// 書き出した法令の一覧から条件に合う法令を探して表示する
+  Search {
+    /// 法令の一覧のファイルへのpath
+    ///
+    /// 複数指定でき、同じ法令IDの法令が複数の一覧にある場合は先に指定したものを使う
+    #[clap(short, long = "input", value_parser, required = true)]
+    inputs: Vec<String>,
+    /// 法令名・法令名の読み・略称のいずれかにこの文字列を含む法令を探す
+    #[clap(long, value_parser)]
+    name: Option<String>,
+    /// 法令番号から読み取った法令の種類（`法律`・`政令`・`省令`など）
+    #[clap(long = "type", value_parser)]
+    law_type: Option<String>,
+    /// 公布された西暦の年がこの年以降の法令を探す
+    #[clap(long, value_parser)]
+    since: Option<usize>,
+    /// 公布された西暦の年がこの年以前の法令を探す
+    #[clap(long, value_parser)]
+    until: Option<usize>,
+    /// 表ではなくJSONで表示する
+    #[clap(long)]
+    json: bool,
+  },
+  /// 法令とそれを改正した法令の関係を有向グラフとして書き出す
+  Graph {
+    /// 法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    input: String,
+    /// グラフを出力するファイルへのpath
+    #[clap(short, long, value_parser)]
+    output: String,
+    /// グラフの形式（`dot`・`graphml`）
+    #[clap(long, value_parser, default_value = "dot")]
+    format: GraphFormat,
+    /// 各法令の最新のXMLファイルの本文を読み、引用している法令への辺も加える
+    #[clap(long)]
+    citations: bool,
+  },
+  /// 2回の実行で書き出した法令の一覧を比べる
+  Diff {
+    /// 古い法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    old: String,
+    /// 新しい法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    new: String,
+    /// 差分を出力するJSONファイルへのpath
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+  },
+  /// 法令名・法令名の読み・略称・法令番号を検索するためのtantivyのインデックスを作る
+  #[cfg(feature = "search-index")]
+  Index {
+    /// 法令の一覧のファイルへのpath
+    #[clap(value_parser)]
+    input: String,
+    /// インデックスを作るディレクトリへのpath（既にインデックスがある場合はエラーにする）
+    #[clap(short, long, value_parser)]
+    output: String,
+    /// インデックスを書くときに使うメモリの量（バイト）
+    #[clap(long, value_parser, default_value_t = 50_000_000)]
+    memory_budget: usize,
+  },
+  /// JSON形式の出力の形を表すJSON Schemaを書き出す
+  Schema {
+    /// JSON Schemaを出力するファイルへのpath（指定しない場合は標準出力に書く）
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+    /// `--legacy-format`で書き出す、法令の情報の配列だけの形のものを書き出す
+    #[clap(long)]
+    legacy_format: bool,
+  },
+  /// 手元のXMLファイル群とe-Govで配布されている法令の一覧（all_law_list.csv）を突き合わせる
+  Validate {
+    /// 法令XMLファイル群が置かれている作業ディレクトリへのpath
+    #[clap(short, long, value_parser)]
+    work: String,
+    /// all_law_list.csvへのpath（複数指定でき、同じ法令IDが複数の一覧にある場合は先に指定したものを使う）
+    #[clap(long, value_parser, required = true)]
+    csv: Vec<String>,
+    /// 突き合わせの結果を出力するJSONファイルへのpath
+    #[clap(short, long, value_parser)]
+    output: Option<String>,
+    /// 一覧にあるが手元にXMLファイルが無い法令の法令IDを、1行に1つずつ書き出すファイルへのpath
+    #[clap(long, value_parser)]
+    missing_ids: Option<String>,
+    /// XMLファイルの解析を並行して行う数
+    #[clap(short, long, value_parser, default_value_t = 1)]
+    jobs: usize,
+  },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+  let args = Args::parse_from(expand_config_args(
+    std::env::args().collect(),
+    &Args::command(),
+  )?);
+
+  if args.jsonrpc {
+    init_jsonrpc_logger()?;
+  } else {
+    init_logger().await?;
+  }
+
+  match &args.command {
+    Some(Command::Inspect { file }) => inspect(file),
+    Some(Command::Lint { input, check_files }) => lint(input, *check_files).await,
+    Some(Command::Subset {
+      input,
+      ids,
+      output,
+      format,
+      copy_files,
+      legacy_format,
+    }) => {
+      subset(
+        input,
+        ids,
+        output,
+        *format,
+        copy_files.as_deref(),
+        *legacy_format,
+      )
+      .await
+    }
+    Some(Command::Merge {
+      inputs,
+      output,
+      format,
+      legacy_format,
+    }) => merge(inputs, output, *format, *legacy_format).await,
+    Some(Command::Stats {
+      input,
+      json,
+      churn_csv,
+      interval_csv,
+    }) => stats(input, *json, churn_csv.as_deref(), interval_csv.as_deref()).await,
+    Some(Command::Search {
+      inputs,
+      name,
+      law_type,
+      since,
+      until,
+      json,
+    }) => {
+      let query = SearchQuery {
+        name: name.clone(),
+        law_type: law_type.clone(),
+        since: *since,
+        until: *until,
+      };
+      search(inputs, &query, *json).await
+    }
+    Some(Command::Graph {
+      input,
+      output,
+      format,
+      citations,
+    }) => graph(input, output, *format, *citations).await,
+    Some(Command::Diff { old, new, output }) => diff(old, new, output.as_deref()).await,
+    #[cfg(feature = "search-index")]
+    Some(Command::Index {
+      input,
+      output,
+      memory_budget,
+    }) => search_index(input, output, *memory_budget).await,
+    Some(Command::Schema {
+      output,
+      legacy_format,
+    }) => schema(output.as_deref(), *legacy_format).await,
+    Some(Command::Validate {
+      work,
+      csv,
+      output,
+      missing_ids,
+      jobs,
+    }) => validate(work, csv, output.as_deref(), missing_ids.as_deref(), *jobs).await,
+    None if args.jsonrpc => {
+      let res = listup(&args).await;
+      match &res {
+        Ok(summary) => notify("result", serde_json::to_value(summary)?),
+        Err(err) => notify("error", json!({ "message": format!("{err:#}") })),
       }
+      res.map(|_| ())
     }
+    None => listup(&args).await.map(|_| ()),
   }
-  Ok(info_lst)
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-  let args = Args::parse();
+fn inspect(file: &str) -> Result<()> {
+  let file_name = Path::new(file)
+    .file_name()
+    .and_then(|s| s.to_str())
+    .ok_or(anyhow!("cannot get file name: {file}"))?;
+  let info = read_law_file(file_name, file, &ListupOptions::default())?;
+  println!("{}", serde_json::to_string_pretty(&info)?);
+  Ok(())
+}
+
+async fn lint(input: &str, check_files: bool) -> Result<()> {
+  let laws = read_law_index(input).await?;
+  let issues = lint_laws(&laws, check_files);
+  for issue in &issues {
+    println!("{issue}");
+  }
+  let errors = issues
+    .iter()
+    .filter(|issue| issue.severity == Severity::Error)
+    .count();
+  let warnings = issues.len() - errors;
+  println!(
+    "{} laws checked: {errors} errors, {warnings} warnings",
+    laws.len()
+  );
+  if errors > 0 {
+    return Err(anyhow!("lint found {errors} errors"));
+  }
+  Ok(())
+}
+
+async fn subset(
+  input: &str,
+  ids: &str,
+  output: &str,
+  format: OutputFormat,
+  copy_files: Option<&str>,
+  legacy_format: bool,
+) -> Result<()> {
+  let laws = read_law_index(input).await?;
+  let ids = read_id_list(ids).await?;
+  let mut laws = subset_laws(laws, &ids);
+  if laws.len() < ids.len() {
+    warn!("{} law ids were not found", ids.len() - laws.len());
+  }
+  if let Some(dir) = copy_files {
+    info!("[START] copy xml files");
+    copy_law_files(&mut laws, dir).await?;
+    info!("[END] copy xml files");
+  }
+  let header = (!legacy_format).then(|| OutputHeader::new(vec![input.to_string()]));
+  write_law_lst(output, format, laws, header.as_ref()).await
+}
+
+#[cfg(feature = "search-index")]
+async fn search_index(input: &str, output: &str, memory_budget: usize) -> Result<()> {
+  let laws = read_law_index(input).await?;
+  info!("[START] build search index");
+  build_law_index(&laws, Path::new(output), memory_budget)?;
+  info!("[END] build search index");
+  Ok(())
+}
+
+async fn merge(
+  inputs: &[String],
+  output: &str,
+  format: OutputFormat,
+  legacy_format: bool,
+) -> Result<()> {
+  let mut lists = Vec::new();
+  for input in inputs {
+    lists.push(read_law_index(input).await?);
+  }
+  let laws = merge_law_lists(lists);
+  info!("{} laws after merging {} lists", laws.len(), inputs.len());
+  // 一覧ごとには見つからなかった改正法令が、他の一覧にあることがあるので調べ直す
+  let known_ids = laws.iter().map(|law| law.id.to_string()).collect();
+  let dangling_patches = find_dangling_patches(laws.iter().flat_map(|law| &law.patch), &known_ids);
+  warn_dangling_patches(&dangling_patches);
+  let header = (!legacy_format).then(|| OutputHeader {
+    warnings: OutputWarnings { dangling_patches },
+    ..OutputHeader::new(inputs.to_vec())
+  });
+  write_law_lst(output, format, laws, header.as_ref()).await
+}
+
+async fn stats(
+  input: &str,
+  json: bool,
+  churn_csv: Option<&str>,
+  interval_csv: Option<&str>,
+) -> Result<()> {
+  let laws = read_law_index(input).await?;
+  if let Some(path) = churn_csv {
+    write_csv_rows(path, churn_by_ministry_year(&laws)).await?;
+  }
+  if let Some(path) = interval_csv {
+    write_csv_rows(path, law_churn(&laws)).await?;
+  }
+  let stats = LawStats::new(&laws);
+  if json {
+    println!("{}", serde_json::to_string_pretty(&stats)?);
+  } else {
+    print!("{stats}");
+  }
+  Ok(())
+}
+
+/// CSVの書き出しはブロッキングするので、tokioのworker threadを塞がないように別のthreadで行う
+async fn write_csv_rows<T: Serialize + Send + 'static>(path: &str, rows: Vec<T>) -> Result<()> {
+  let path = path.to_string();
+  tokio::task::spawn_blocking(move || -> Result<()> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    for row in &rows {
+      wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+  })
+  .await?
+}
+
+async fn search(inputs: &[String], query: &SearchQuery, json: bool) -> Result<()> {
+  let set = LawIndexSet::load(inputs).await?;
+  let hits = search_laws(set.laws(), query);
+  if json {
+    println!("{}", serde_json::to_string_pretty(&hits)?);
+  } else {
+    for hit in &hits {
+      println!("{hit}");
+    }
+    println!("{} laws found", hits.len());
+  }
+  Ok(())
+}
+
+async fn graph(input: &str, output: &str, format: GraphFormat, citations: bool) -> Result<()> {
+  let laws = read_law_index(input).await?;
+  let (laws, edges) = if citations {
+    // XMLファイルの読み込みはブロッキングするので、別のthreadでまとめて行う
+    tokio::task::spawn_blocking(move || -> Result<_> {
+      let edges = read_citations(&laws)?;
+      Ok((laws, edges))
+    })
+    .await??
+  } else {
+    (laws, Vec::new())
+  };
+  let mut graph = LawGraph::new(&laws);
+  for (from, to) in &edges {
+    graph.add_citation(from, to);
+  }
+  info!("{} nodes, {} edges", graph.nodes.len(), graph.edges.len());
+  write(output, graph.render(format)).await?;
+  Ok(())
+}
+
+/// 法令ごとに最も新しい版のXMLファイルを読み、本文で引用している法令への辺（引用する法令ID・引用される法令ID）を集める
+fn read_citations(laws: &[LawRecord]) -> Result<Vec<(String, String)>> {
+  let index = LawIndex::new(laws);
+  // `--work-zip`で作った一覧の場合は、ZIPファイルの中のXMLファイルを読む
+  let mut reader = XmlFileReader::default();
+  let mut edges = Vec::new();
+  for law in laws {
+    let Some(newest) = law.patch.iter().max_by(|a, b| a.order(b)) else {
+      continue;
+    };
+    let buf = reader.read(&newest.file)?;
+    for cited in cited_laws(&index, &String::from_utf8_lossy(&buf), &law.id) {
+      edges.push((law.id.to_string(), cited.to_string()));
+    }
+  }
+  Ok(edges)
+}
+
+async fn schema(output: Option<&str>, legacy_format: bool) -> Result<()> {
+  let schema = serde_json::to_string_pretty(&output_schema(legacy_format))?;
+  match output {
+    Some(path) => write(path, schema).await?,
+    None => println!("{schema}"),
+  }
+  Ok(())
+}
+
+async fn diff(old: &str, new: &str, output: Option<&str>) -> Result<()> {
+  let old_laws = read_law_index(old).await?;
+  let new_laws = read_law_index(new).await?;
+  let diff = diff_law_lists(&old_laws, &new_laws);
+  for id in &diff.added {
+    println!("added: {id}");
+  }
+  for id in &diff.removed {
+    println!("removed: {id}");
+  }
+  for (law, change) in diff.renamed() {
+    println!("renamed: {} `{}` -> `{}`", law.id, change.old, change.new);
+  }
+  for law in &diff.changed {
+    for patch in &law.added_patches {
+      println!("new patch: {} {}", law.id, to_iso8601(&patch.patch_date));
+    }
+  }
+  println!(
+    "{} added, {} removed, {} changed",
+    diff.added.len(),
+    diff.removed.len(),
+    diff.changed.len()
+  );
+  if let Some(path) = output {
+    write(path, serde_json::to_string_pretty(&diff)?).await?;
+  }
+  Ok(())
+}
+
+async fn validate(
+  work: &str,
+  csvs: &[String],
+  output: Option<&str>,
+  missing_ids: Option<&str>,
+  jobs: usize,
+) -> Result<()> {
+  let (entries, conflicts) = read_law_list_csvs(csvs)?;
+  warn_law_list_conflicts(&conflicts);
+  let options = ListupOptions {
+    jobs,
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst(work, &options, None).await?;
+  let laws = law_list.into_laws(SortKey::Id);
+  let report = validate_laws(&laws, &entries);
+  for entry in &report.missing_local {
+    println!("missing local file: {} {}", entry.id, entry.name);
+  }
+  for entry in &report.unknown_num {
+    println!("law number not in csv: {} {}", entry.id, entry.num);
+  }
+  for mismatch in &report.name_mismatches {
+    println!(
+      "name mismatch: {} csv=`{}` local=`{}`",
+      mismatch.id, mismatch.csv_name, mismatch.local_name
+    );
+  }
+  println!(
+    "{} missing, {} unknown law numbers, {} name mismatches",
+    report.missing_local.len(),
+    report.unknown_num.len(),
+    report.name_mismatches.len()
+  );
+  if let Some(path) = output {
+    write(path, serde_json::to_string_pretty(&report)?).await?;
+  }
+  if let Some(path) = missing_ids {
+    let ids = report
+      .missing_local
+      .iter()
+      .map(|entry| format!("{}\n", entry.id))
+      .collect::<String>();
+    write(path, ids).await?;
+  }
+  Ok(())
+}
+
+/// 法令の一覧を作った結果の要約
+#[derive(Debug, Serialize)]
+struct ListupSummary {
+  /// 書き出した法令の数
+  laws: usize,
+  /// 読み込めずに飛ばしたXMLファイルの数
+  skipped: usize,
+  /// 法令ID・法令番号・`Law`タグの属性の元号と年が食い違っていたXMLファイルの数
+  era_inconsistencies: usize,
+  /// 存在しない法令を指している改正法令の法令IDの数
+  dangling_patches: usize,
+  /// 書き出したファイルへのpath
+  outputs: Vec<String>,
+}
+
+async fn listup(args: &Args) -> Result<ListupSummary> {
+  if args.output.is_empty() {
+    return Err(anyhow!("--output is required"));
+  }
 
-  init_logger().await?;
+  let mut options = ListupOptions {
+    jobs: args.jobs,
+    walk_jobs: args.walk_jobs,
+    skip_errors: args.skip_errors && !args.fail_fast,
+    invalid_utf8: args.invalid_utf8,
+    patch_warn_threshold: Some(args.patch_warn_threshold),
+    max_patches: args.max_patches,
+    fast: args.fast,
+    path_style: args.path_style,
+    on_conflict: args.on_conflict,
+    as_of: args.as_of,
+    extra_eras: args.extra_eras.clone(),
+    articles: args.output.iter().any(|spec| {
+      spec.granularity.unwrap_or(args.record_granularity) == RecordGranularity::Article
+    }),
+    snapshots: args.output.iter().any(|spec| {
+      spec.granularity.unwrap_or(args.record_granularity) == RecordGranularity::Snapshot
+    }),
+    ..Default::default()
+  };
+  args.filter.apply(&mut options)?;
+
+  if let Some(cap) = args.memory_cap {
+    return listup_spilled(args, &options, cap).await;
+  }
+
+  let mut state = match &args.state {
+    Some(path) => Some(ListupState::load(path, &options).await?),
+    None => None,
+  };
 
   info!("[START] get law list");
-  let law_info_lst = get_law_info_lst(&args.work).await?;
+  let (law_list, input) = match &args.work_zip {
+    Some(zip_path) => (
+      get_law_info_lst_from_zip(zip_path, &options)?,
+      vec![zip_path.clone()],
+    ),
+    None if args.work.is_empty() => return Err(anyhow!("either --work or --work-zip is required")),
+    None => {
+      let work_dirs = expand_work_dirs(&args.work)?;
+      (
+        get_law_info_lst_from_dirs(&work_dirs, &options, state.as_mut()).await?,
+        work_dirs,
+      )
+    }
+  };
   info!("[END] get law list");
 
-  info!("[START] write json file");
-  let mut output_file = gen_file_value_lst(&args.output).await?;
+  if let (Some(state), Some(path)) = (&state, &args.state) {
+    info!("[START] write state file");
+    state.save(path).await?;
+    info!("[END] write state file");
+  }
+
+  if let Some(path) = &args.error_report {
+    info!("[START] write error report");
+    write(path, serde_json::to_string_pretty(&law_list.errors)?).await?;
+    info!("[END] write error report");
+  }
+  let skipped = law_list.errors.len();
+  if skipped > 0 {
+    warn!("{skipped} files were skipped");
+  }
+
+  report_conflicts(args, &options, &law_list.conflicts).await?;
 
-  let mut law_info_lst_stream = tokio_stream::iter(law_info_lst);
+  if let Some(path) = &args.suggest_fixes {
+    info!("[START] write fix suggestions");
+    write(path, serde_json::to_string_pretty(&law_list.fixes)?).await?;
+    info!("[END] write fix suggestions");
+  }
+
+  if let Some(path) = &args.era_report {
+    info!("[START] write era report");
+    write(
+      path,
+      serde_json::to_string_pretty(&law_list.era_inconsistencies)?,
+    )
+    .await?;
+    info!("[END] write era report");
+  }
+
+  let era_inconsistencies = law_list.era_inconsistencies.clone();
+  let mut laws = law_list.into_laws(args.sort_by);
+  let law_list_entries = read_law_list(&args.law_list_csv, &options)?;
+  let patch_laws = patch_laws_of(&law_list_entries);
+  let known_ids = laws
+    .iter()
+    .map(|law| law.id.to_string())
+    .chain(patch_laws.keys().cloned())
+    .collect();
+  let dangling_patches = find_dangling_patches(laws.iter().flat_map(|law| &law.patch), &known_ids);
+  warn_dangling_patches(&dangling_patches);
+  let abbrevs = match &args.abbrev_csv {
+    Some(path) => Some(read_abbrev_csv(path)?),
+    None => None,
+  };
+  postprocess_laws(
+    args,
+    &mut laws,
+    abbrevs.as_ref(),
+    &patch_laws,
+    &law_list_entries,
+  );
+  // 正規化で法令名の順番が変わることがあるので並べ直す
+  if args.sort_by == SortKey::Name {
+    sort_laws(&mut laws, args.sort_by);
+  }
 
-  while let Some((id, data)) = law_info_lst_stream.next().await {
-    start_log("write law info", &id);
-    let mut lst = data.clone().patch;
-    lst.sort_by(|a, b| a.patch_date.cmp(&b.patch_date));
-    info_log("patch list", &lst);
-    write_value_lst(&mut output_file, data).await?;
-    end_log("write law info", &id);
+  if let Some(path) = &args.debug_unknown_tags {
+    info!("[START] write unknown tag report");
+    let report = unknown_tag_report(&laws)?;
+    write(path, serde_json::to_string_pretty(&report)?).await?;
+    info!("[END] write unknown tag report");
+  }
+
+  if args.badge.is_some() || args.badge_shields.is_some() {
+    let mut badge = Badge::new(&laws);
+    if !args.law_list_csv.is_empty() {
+      badge = badge.with_coverage(&laws, &patch_laws.keys().cloned().collect());
+    }
+    if let Some(path) = &args.badge {
+      write(path, serde_json::to_string_pretty(&badge)?).await?;
+    }
+    if let Some(path) = &args.badge_shields {
+      write(path, serde_json::to_string_pretty(&badge.shields())?).await?;
+    }
   }
-  flush_file_value_lst(&mut output_file).await?;
+
+  let specs = match args.split_by {
+    Some(key) => args
+      .output
+      .iter()
+      .flat_map(|spec| spec.split(key, &laws))
+      .collect(),
+    None => args.output.clone(),
+  };
+
+  let summary = ListupSummary {
+    laws: laws.len(),
+    skipped,
+    era_inconsistencies: era_inconsistencies.len(),
+    dangling_patches: dangling_patches.len(),
+    outputs: specs.iter().map(|spec| spec.path.clone()).collect(),
+  };
+
+  let header = (!args.legacy_format).then(|| OutputHeader {
+    warnings: OutputWarnings { dangling_patches },
+    ..OutputHeader::new(input)
+  });
+  info!("[START] write json file");
+  write_outputs(
+    &specs,
+    args.format,
+    args.record_granularity,
+    laws,
+    header.as_ref(),
+  )
+  .await?;
   info!("[END] write json file");
 
+  check_strict(args, &era_inconsistencies)?;
+  Ok(summary)
+}
+
+/// 一覧を作った後の法令に、改正の絞り込み（`--as-of`・`--latest-only`）・略称・改正法令の解決・
+/// 法令の一覧の未施行の列による効力の状態の補正・正規化を順に施す
+///
+/// `--memory-cap`の場合も一時ファイルごとにこれを通し、メモリ上で作った場合と同じ出力にする
+fn postprocess_laws(
+  args: &Args,
+  laws: &mut [LawRecord],
+  abbrevs: Option<&HashMap<String, Vec<String>>>,
+  patch_laws: &HashMap<String, (String, String)>,
+  law_list_entries: &[LawListEntry],
+) {
+  if args.as_of.is_some() || args.latest_only {
+    for law in laws.iter_mut() {
+      law.retain_latest_patch();
+    }
+  }
+  if let Some(abbrevs) = abbrevs {
+    apply_abbrev(laws, abbrevs);
+  }
+  resolve_patch_laws(laws, patch_laws);
+  apply_law_list_kana(laws, law_list_entries);
+  // 法令の一覧の未施行の列はダウンロードした日の時点のものなので、`--as-of`で別の日を指定した場合は使わない
+  if args.as_of.is_none() {
+    apply_law_list_status(laws, law_list_entries);
+  }
+  normalize_laws(laws, args.normalize);
+}
+
+/// 同じ法令IDのXMLファイルの間の食い違いを書き出し、`--on-conflict error`で`--skip-errors`が無い場合はエラーにする
+async fn report_conflicts(
+  args: &Args,
+  options: &ListupOptions,
+  conflicts: &[LawConflict],
+) -> Result<()> {
+  if let Some(path) = &args.conflict_report {
+    write(path, serde_json::to_string_pretty(conflicts)?).await?;
+  }
+  if conflicts.is_empty() {
+    return Ok(());
+  }
+  warn!(
+    "{} files conflict with another file of the same law id",
+    conflicts.len()
+  );
+  if options.on_conflict == ConflictPolicy::Error && !options.skip_errors {
+    let first = &conflicts[0];
+    return Err(anyhow!(
+      "{} in {} has {} `{}`, but `{}` was read before",
+      first.id,
+      first.file,
+      first.field,
+      first.conflicting,
+      first.existing
+    ));
+  }
   Ok(())
 }
+
+/// `--law-list-csv`に指定した法令の一覧をすべて読み、法令IDごとに1つにまとめる
+///
+/// 一覧の間で法令名か法令番号が食い違っている場合は警告し、`--on-conflict error`で`--skip-errors`が無い場合はエラーにする。
+/// XMLファイルの間の食い違い（`report_conflicts`）と同じく、`options`の設定に従う
+fn read_law_list(paths: &[String], options: &ListupOptions) -> Result<Vec<LawListEntry>> {
+  let (entries, conflicts) = read_law_list_csvs(paths)?;
+  warn_law_list_conflicts(&conflicts);
+  if options.on_conflict == ConflictPolicy::Error && !options.skip_errors {
+    if let Some(first) = conflicts.first() {
+      return Err(anyhow!(
+        "{} in {} has {} `{}`, but `{}` was read before",
+        first.id,
+        first.file,
+        first.field,
+        first.conflicting,
+        first.existing
+      ));
+    }
+  }
+  Ok(entries)
+}
+
+/// 法令の一覧から、改正法令を探すための法令IDから法令名と法令番号への対応を作る
+fn patch_laws_of(entries: &[LawListEntry]) -> HashMap<String, (String, String)> {
+  entries
+    .iter()
+    .map(|entry| (entry.id.clone(), (entry.name.clone(), entry.num.clone())))
+    .collect()
+}
+
+/// 法令の一覧のCSVの間の法令名・法令番号の食い違いを警告する
+fn warn_law_list_conflicts(conflicts: &[LawConflict]) {
+  if conflicts.is_empty() {
+    return;
+  }
+  for conflict in conflicts {
+    info!(
+      "{} in {} has {} `{}`, but `{}` was read before",
+      conflict.id, conflict.file, conflict.field, conflict.conflicting, conflict.existing
+    );
+  }
+  warn!(
+    "{} laws have different names or numbers between law list files",
+    conflicts.len()
+  );
+}
+
+/// 存在しない法令を指している改正法令の法令IDを警告する
+fn warn_dangling_patches(dangling_patches: &[DanglingPatch]) {
+  if dangling_patches.is_empty() {
+    return;
+  }
+  for dangling in dangling_patches {
+    info!("{dangling}");
+  }
+  warn!(
+    "{} patches refer to amending laws that are not found; the download may be truncated",
+    dangling_patches.len()
+  );
+}
+
+/// `--strict`の場合、元号と年の食い違いを1件ずつ警告し、1件でもあればエラーにする
+fn check_strict(args: &Args, era_inconsistencies: &[EraInconsistency]) -> Result<()> {
+  if !args.strict || era_inconsistencies.is_empty() {
+    return Ok(());
+  }
+  for inconsistency in era_inconsistencies {
+    warn!("{inconsistency}");
+  }
+  Err(anyhow!(
+    "--strict: {} files have inconsistent era and year between the Law tag, LawNum and law id",
+    era_inconsistencies.len()
+  ))
+}
+
+async fn listup_spilled(args: &Args, options: &ListupOptions, cap: usize) -> Result<ListupSummary> {
+  if args.work.is_empty() {
+    return Err(anyhow!("--memory-cap requires --work"));
+  }
+  let work_dirs = expand_work_dirs(&args.work)?;
+  if args.sort_by != SortKey::Id {
+    return Err(anyhow!("--memory-cap only supports --sort-by id"));
+  }
+  if args.split_by.is_some() {
+    return Err(anyhow!("--split-by is not supported with --memory-cap"));
+  }
+  let dir = match &args.spill_dir {
+    Some(dir) => PathBuf::from(dir),
+    None => std::env::temp_dir().join(format!("listup_law-{}", std::process::id())),
+  };
+  let abbrevs = match &args.abbrev_csv {
+    Some(path) => Some(read_abbrev_csv(path)?),
+    None => None,
+  };
+  let law_list_entries = read_law_list(&args.law_list_csv, options)?;
+  let patch_laws = patch_laws_of(&law_list_entries);
+
+  info!("[START] get law list");
+  let mut spilled = spill_law_info_lst(&work_dirs, options, &dir, cap).await?;
+  // 食い違いは一時ファイルを読むときに分かるので、出力を書き出す前に一度すべて読んでおく
+  spilled.check(options).await?;
+  info!("[END] get law list");
+
+  if let Some(path) = &args.error_report {
+    write(path, serde_json::to_string_pretty(&spilled.errors)?).await?;
+  }
+  let skipped = spilled.errors.len();
+  if skipped > 0 {
+    warn!("{skipped} files were skipped");
+  }
+  report_conflicts(args, options, &spilled.conflicts).await?;
+  if let Some(path) = &args.suggest_fixes {
+    write(path, serde_json::to_string_pretty(&spilled.fixes)?).await?;
+  }
+  if let Some(path) = &args.era_report {
+    write(
+      path,
+      serde_json::to_string_pretty(&spilled.era_inconsistencies)?,
+    )
+    .await?;
+  }
+
+  let dangling_patches = spilled.dangling_patches(&patch_laws.keys().cloned().collect());
+  warn_dangling_patches(&dangling_patches);
+  let dangling_count = dangling_patches.len();
+  let header = (!args.legacy_format).then(|| OutputHeader {
+    warnings: OutputWarnings { dangling_patches },
+    ..OutputHeader::new(work_dirs.clone())
+  });
+  let mut writers = Vec::new();
+  for spec in &args.output {
+    if spec.granularity.unwrap_or(args.record_granularity) != RecordGranularity::Law {
+      return Err(anyhow!(
+        "--memory-cap only supports law granularity: {}",
+        spec.path
+      ));
+    }
+    let writer = LawWriter::create(
+      &spec.path,
+      spec.format.unwrap_or(args.format),
+      header.as_ref(),
+    )
+    .await?;
+    writers.push((spec, writer));
+  }
+
+  info!("[START] write json file");
+  let mut laws = 0;
+  while let Some(mut lst) = spilled.next_laws(options).await? {
+    // 一時ファイルごとに処理するので、他の一時ファイルに入っている改正法令は`--law-list-csv`からしか探せない
+    postprocess_laws(
+      args,
+      &mut lst,
+      abbrevs.as_ref(),
+      &patch_laws,
+      &law_list_entries,
+    );
+    laws += lst.len();
+    for law in lst {
+      for (spec, writer) in writers.iter_mut() {
+        if spec.accepts(&law) {
+          writer.write(law.clone()).await?;
+        }
+      }
+    }
+  }
+  for (_, writer) in writers {
+    writer.finish().await?;
+  }
+  info!("[END] write json file");
+  if args.spill_dir.is_none() {
+    remove_dir_all(&dir).await?;
+  }
+
+  check_strict(args, &spilled.era_inconsistencies)?;
+  Ok(ListupSummary {
+    laws,
+    skipped,
+    era_inconsistencies: spilled.era_inconsistencies.len(),
+    dangling_patches: dangling_count,
+    outputs: args.output.iter().map(|spec| spec.path.clone()).collect(),
+  })
+}
@@ -0,0 +1,202 @@
+//! 別々に作った法令の一覧を1つにまとめる
+//!
+//! 法令IDの範囲（`--shard`など）ごとに別の機械で作った一覧を合わせるのに使う
+
+use crate::calendar::cmp_dates;
+use crate::record::{sort_laws, LawRecord, PatchRecord, SortKey};
+use jplaw_data_types::law::Date;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// 法令の一覧を法令IDごとにまとめる
+///
+/// 同じ法令IDの法令が複数の一覧にある場合は、改正の一覧を合わせて同じ版（改正の日付と改正法令の法令ID）の
+/// 重複を除き、法令名などは最も新しい改正を含む方のものを使う。最も新しい改正が同じ場合は先の一覧のものを使う。
+/// 結果は法令IDの順に並べる
+pub fn merge_law_lists(lists: Vec<Vec<LawRecord>>) -> Vec<LawRecord> {
+  let mut merged: HashMap<String, LawRecord> = HashMap::new();
+  for law in lists.into_iter().flatten() {
+    match merged.get_mut(&law.id.to_string()) {
+      Some(existing) => merge_law(existing, law),
+      None => {
+        merged.insert(law.id.to_string(), law);
+      }
+    }
+  }
+  let mut laws = merged.into_values().collect::<Vec<_>>();
+  sort_laws(&mut laws, SortKey::Id);
+  laws
+}
+
+/// 同じ法令IDの法令の情報を`existing`にまとめる
+fn merge_law(existing: &mut LawRecord, mut law: LawRecord) {
+  let newer = match (latest_patch(existing), latest_patch(&law)) {
+    (Some(a), Some(b)) => b.order(a) == Ordering::Greater,
+    (None, Some(_)) => true,
+    _ => false,
+  };
+  let mut patch = std::mem::take(&mut existing.patch);
+  patch.append(&mut law.patch);
+  let mut historical_names = std::mem::take(&mut existing.historical_names);
+  historical_names.append(&mut law.historical_names);
+  if newer {
+    *existing = law;
+  }
+  patch.sort_by(PatchRecord::order);
+  patch.dedup_by(|a, b| a.order(b) == Ordering::Equal);
+  existing.patch = patch;
+  existing.historical_names = merge_historical_names(historical_names);
+}
+
+/// 改正の一覧のうち、`PatchRecord::order`の順で最も新しいもの
+fn latest_patch(law: &LawRecord) -> Option<&PatchRecord> {
+  law.patch.iter().max_by(|a, b| a.order(b))
+}
+
+/// 2つの一覧の法令名の移り変わりを改正の日付の順に並べ、続けて同じ法令名のものを1つにする
+///
+/// 法令名が1つしか残らない場合は空にする
+fn merge_historical_names(mut names: Vec<(Date, String)>) -> Vec<(Date, String)> {
+  names.sort_by(|(a, _), (b, _)| cmp_dates(a, b));
+  names.dedup_by(|(_, a), (_, b)| a == b);
+  if names.len() < 2 {
+    names.clear();
+  }
+  names
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::calendar::to_iso8601;
+  use crate::record::testing::{date, law, patch};
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn patch_dates(law: &LawRecord) -> Vec<String> {
+    law
+      .patch
+      .iter()
+      .map(|patch| to_iso8601(&patch.info.patch_date))
+      .collect()
+  }
+
+  #[test]
+  fn merges_versions_and_uses_the_list_with_the_latest_patch() {
+    let old = law(PRIVACY_ACT_ID, "古い法令名");
+    let mut new = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    new.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    let merged = merge_law_lists(vec![vec![law(CIVIL_CODE_ID, "民法"), old], vec![new]]);
+
+    let ids = merged
+      .iter()
+      .map(|law| law.id.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(ids, [CIVIL_CODE_ID, PRIVACY_ACT_ID]);
+    assert_eq!(merged[1].name, "個人情報の保護に関する法律");
+    // 同じ版は1つにまとめる
+    assert_eq!(patch_dates(&merged[1]), ["2003-05-30", "2017-05-30"]);
+  }
+
+  #[test]
+  fn keeps_the_newer_law_whichever_list_it_comes_from() {
+    let mut newer = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    newer.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    let merged = merge_law_lists(vec![
+      vec![newer],
+      vec![law(PRIVACY_ACT_ID, "古い法令名")],
+      vec![],
+    ]);
+    assert_eq!(merged[0].name, "個人情報の保護に関する法律");
+    assert_eq!(patch_dates(&merged[0]), ["2003-05-30", "2017-05-30"]);
+    assert!(merge_law_lists(vec![]).is_empty());
+  }
+
+  #[test]
+  fn keeps_versions_of_the_same_day_from_different_amending_laws() {
+    let mut a = law(PRIVACY_ACT_ID, "改正法令の法令IDが小さい版");
+    a.patch = vec![patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("415AC0000000058"),
+    )];
+    let mut b = law(PRIVACY_ACT_ID, "改正法令の法令IDが大きい版");
+    b.patch = vec![patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    )];
+    let merged = merge_law_lists(vec![vec![b], vec![a]]);
+
+    // 同じ日付の版は改正法令の法令IDの順に並べ、大きい方を最も新しい版とする
+    assert_eq!(merged[0].name, "改正法令の法令IDが大きい版");
+    let patch_ids = merged[0]
+      .patch
+      .iter()
+      .map(|patch| patch.info.patch_id.as_ref().unwrap().to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(patch_ids, ["415AC0000000058", "427AC0000000065"]);
+  }
+
+  #[test]
+  fn prefers_the_earlier_list_on_a_tie() {
+    let merged = merge_law_lists(vec![
+      vec![law(PRIVACY_ACT_ID, "先の一覧")],
+      vec![law(PRIVACY_ACT_ID, "後の一覧")],
+    ]);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].name, "先の一覧");
+
+    // 改正の一覧が空の法令より、改正のある法令を使う
+    let mut empty = law(PRIVACY_ACT_ID, "先の一覧");
+    empty.patch.clear();
+    let merged = merge_law_lists(vec![vec![empty], vec![law(PRIVACY_ACT_ID, "後の一覧")]]);
+    assert_eq!(merged[0].name, "後の一覧");
+    assert_eq!(patch_dates(&merged[0]), ["2003-05-30"]);
+  }
+
+  #[test]
+  fn merges_historical_names_in_date_order() {
+    let mut a = law(PRIVACY_ACT_ID, "新しい法令名");
+    a.historical_names = vec![
+      (date("Heisei", 29, 5, 30), "新しい法令名".to_string()),
+      (date("Heisei", 15, 5, 30), "古い法令名".to_string()),
+    ];
+    let mut b = law(PRIVACY_ACT_ID, "新しい法令名");
+    b.historical_names = vec![
+      (date("Heisei", 15, 5, 30), "古い法令名".to_string()),
+      (date("Reiwa", 2, 6, 12), "新しい法令名".to_string()),
+    ];
+    let merged = merge_law_lists(vec![vec![a], vec![b]]);
+
+    let names = merged[0]
+      .historical_names
+      .iter()
+      .map(|(date, name)| (to_iso8601(date), name.as_str()))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      names,
+      [
+        ("2003-05-30".to_string(), "古い法令名"),
+        ("2017-05-30".to_string(), "新しい法令名"),
+      ]
+    );
+
+    // まとめた結果、法令名が1つしか残らない場合は空にする
+    let mut a = law(PRIVACY_ACT_ID, "法令名");
+    a.historical_names = vec![(date("Heisei", 15, 5, 30), "法令名".to_string())];
+    let mut b = law(PRIVACY_ACT_ID, "法令名");
+    b.historical_names = vec![(date("Heisei", 29, 5, 30), "法令名".to_string())];
+    let merged = merge_law_lists(vec![vec![a], vec![b]]);
+    assert!(merged[0].historical_names.is_empty());
+  }
+}
@@ -0,0 +1,304 @@
+//! ディレクトリの走査などを伴わずに、法令XML1つからメタデータを読み取る
+
+use crate::calendar::{era_from_attr, EraExt};
+use anyhow::{anyhow, Result};
+use japanese_law_xml_schema::law::{Era, Law};
+use jplaw_data_types::law::Date;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 法令名などのメタデータを読み取るのに使う要素
+pub const READ_TAGS: &[&str] = &["Law", "LawNum", "LawBody", "LawTitle", "Rt"];
+
+/// 法令XMLの`Law`タグとその中身から読み取れるメタデータ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawMetadata {
+  /// 公布の日付（元号と年まで）
+  pub date: Date,
+  /// 公布の年月日
+  ///
+  /// `Law`タグに`PromulgateMonth`属性がある場合のみ
+  pub promulgation_date: Option<Date>,
+  /// 法令名（`LawTitle`タグが無い場合は空文字列）
+  pub name: String,
+  /// 法令番号
+  pub num: String,
+  /// 法令名の読み（`LawTitle`タグの`Kana`属性）
+  pub name_kana: Option<String>,
+}
+
+impl LawMetadata {
+  /// 解析済みの`Law`からメタデータを取り出す
+  pub fn new(law: &Law) -> Self {
+    let promulgation_date = law.promulgate_month.map(|_| {
+      Date::new(
+        law.era.clone(),
+        law.year,
+        law.promulgate_month,
+        law.promulgate_day,
+      )
+    });
+    let date = Date::new(law.era.clone(), law.year, None, None);
+    let name = law
+      .law_body
+      .law_title
+      .as_ref()
+      .map(|title| title.text.to_string())
+      .unwrap_or_default();
+    LawMetadata {
+      date,
+      promulgation_date,
+      name,
+      num: law.law_num.clone(),
+      name_kana: None,
+    }
+  }
+
+  /// 解析済みの`Law`と、そのXMLの文字列からメタデータを取り出す
+  ///
+  /// `Law`の型に含まれない`LawTitle`タグの属性は、XMLの文字列から読み取る
+  pub fn with_xml(law: &Law, xml: &str) -> Self {
+    LawMetadata {
+      name_kana: law_title_attr(xml, "Kana"),
+      ..LawMetadata::new(law)
+    }
+  }
+}
+
+/// XMLの文字列から、最初の`LawTitle`タグの属性の値を取り出す
+pub fn law_title_attr(xml: &str, attr: &str) -> Option<String> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(r"<LawTitle(?P<attrs>\s[^>]*)?>").unwrap());
+  attr_value(re.captures(xml)?.name("attrs")?.as_str(), attr)
+}
+
+/// XMLの文字列から、最初の`Law`タグの属性の値を取り出す
+pub fn law_attr(xml: &str, attr: &str) -> Option<String> {
+  attr_value(law_tag_attrs(xml)?.as_str(), attr)
+}
+
+fn law_tag_attrs(xml: &str) -> Option<regex::Match<'_>> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(r"<Law(?P<attrs>\s[^>]*)>").unwrap());
+  re.captures(xml)?.name("attrs")
+}
+
+fn attr_value(attrs: &str, attr: &str) -> Option<String> {
+  attrs
+    .split('"')
+    .collect::<Vec<_>>()
+    .chunks(2)
+    .find_map(|kv| match kv {
+      [key, value] if key.trim().trim_end_matches('=').trim() == attr => Some(value.to_string()),
+      _ => None,
+    })
+}
+
+/// 最初の`Law`タグの`Era`属性と`Year`属性を書き換えたXMLの文字列を返す
+///
+/// `Law`タグが無い場合はそのまま返す
+pub fn replace_law_era(xml: &str, era: &Era, year: usize) -> String {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(r#"\b(?P<key>Era|Year)="[^"]*""#).unwrap());
+  let Some(attrs) = law_tag_attrs(xml) else {
+    return xml.to_string();
+  };
+  let replaced = re.replace_all(attrs.as_str(), |caps: &regex::Captures| {
+    match &caps["key"] {
+      "Era" => format!(r#"Era="{}""#, era.attr()),
+      _ => format!(r#"Year="{year}""#),
+    }
+  });
+  format!(
+    "{}{}{}",
+    &xml[..attrs.start()],
+    replaced,
+    &xml[attrs.end()..]
+  )
+}
+
+/// 法令XMLのバイト列を解析し、メタデータを取り出す
+///
+/// ファイル名に由来する法令IDや改正の情報は含まない。
+/// それらも必要な場合は`listup::read_law_file`を使う
+pub fn parse_law_xml(bytes: &[u8]) -> Result<LawMetadata> {
+  let law = japanese_law_xml_schema::parse_xml(bytes)?;
+  Ok(LawMetadata::with_xml(&law, &String::from_utf8_lossy(bytes)))
+}
+
+/// `LawNum`タグと`LawTitle`タグのどちらの中身を読んでいるか
+#[derive(Clone, Copy)]
+enum HeaderField {
+  Num,
+  Title,
+}
+
+/// `LawTitle`タグの`Kana`属性
+fn kana_attr(e: &BytesStart) -> Result<Option<String>> {
+  for attr in e.attributes() {
+    let attr = attr?;
+    if attr.key.as_ref() == b"Kana" {
+      return Ok(Some(attr.unescape_value()?.into_owned()));
+    }
+  }
+  Ok(None)
+}
+
+/// 法令XMLのバイト列の先頭から`Law`タグの属性と`LawNum`タグ・`LawTitle`タグだけを読み取る
+///
+/// 本文全体を解析する`parse_law_xml`より速いが、XMLの全体が正しいかどうかは確かめない。
+/// `LawNum`タグと`LawTitle`タグを読み終えた時点で読むのをやめる
+pub fn scan_law_header(bytes: &[u8]) -> Result<LawMetadata> {
+  let mut reader = Reader::from_reader(bytes);
+  let (mut era, mut year, mut month, mut day) = (None, None, None, None);
+  let mut num: Option<String> = None;
+  let mut name: Option<String> = None;
+  let mut name_kana = None;
+  let mut current = None;
+  // ルビの読みは法令名に含めない
+  let mut in_rt = false;
+  loop {
+    match reader.read_event()? {
+      Event::Start(e) => match e.name().as_ref() {
+        b"Law" => {
+          for attr in e.attributes() {
+            let attr = attr?;
+            let value = attr.unescape_value()?;
+            match attr.key.as_ref() {
+              b"Era" => era = era_from_attr(&value),
+              b"Year" => year = value.parse().ok(),
+              b"PromulgateMonth" => month = value.parse().ok(),
+              b"PromulgateDay" => day = value.parse().ok(),
+              _ => (),
+            }
+          }
+        }
+        b"LawNum" => {
+          num.get_or_insert_with(String::new);
+          current = Some(HeaderField::Num);
+        }
+        b"LawTitle" => {
+          name_kana = kana_attr(&e)?.or(name_kana);
+          name.get_or_insert_with(String::new);
+          current = Some(HeaderField::Title);
+        }
+        b"Rt" => in_rt = true,
+        _ if current.is_none() && name.is_some() => break,
+        _ => (),
+      },
+      // `<LawNum/>`や`<LawTitle/>`のように中身の無いタグも、読み終えたものとして扱う
+      Event::Empty(e) => {
+        match e.name().as_ref() {
+          b"LawNum" => {
+            num.get_or_insert_with(String::new);
+          }
+          b"LawTitle" => {
+            name_kana = kana_attr(&e)?.or(name_kana);
+            name.get_or_insert_with(String::new);
+          }
+          _ if current.is_none() && name.is_some() => break,
+          _ => continue,
+        }
+        if num.is_some() && name.is_some() {
+          break;
+        }
+      }
+      Event::Text(t) if !in_rt => {
+        let text = t.unescape()?;
+        match current {
+          Some(HeaderField::Num) => num.get_or_insert_with(String::new).push_str(&text),
+          Some(HeaderField::Title) => name.get_or_insert_with(String::new).push_str(&text),
+          None => (),
+        }
+      }
+      Event::End(e) => match e.name().as_ref() {
+        b"LawNum" | b"LawTitle" => {
+          current = None;
+          if num.is_some() && name.is_some() {
+            break;
+          }
+        }
+        b"Rt" => in_rt = false,
+        _ => (),
+      },
+      Event::Eof => break,
+      _ => (),
+    }
+  }
+  let era = era.ok_or(anyhow!("Law tag has no valid Era attribute"))?;
+  let year = year.ok_or(anyhow!("Law tag has no valid Year attribute"))?;
+  let promulgation_date = month.map(|_| Date::new(era.clone(), year, month, day));
+  Ok(LawMetadata {
+    date: Date::new(era, year, None, None),
+    promulgation_date,
+    name: name.unwrap_or_default().trim().to_string(),
+    num: num
+      .ok_or(anyhow!("LawNum tag is not found"))?
+      .trim()
+      .to_string(),
+    name_kana,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// `LawNum`タグと`LawTitle`タグの後に、閉じタグの食い違った本文を置いた法令XML
+  ///
+  /// 本文まで読んだ場合はエラーになるので、ヘッダーだけで読み終えたことが分かる
+  fn xml(num: &str, title: &str) -> String {
+    format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<Law Era="Heisei" Year="15" Num="57" LawType="Act" Lang="ja" PromulgateMonth="05" PromulgateDay="30">
+  {num}
+  <LawBody>
+    {title}
+    <MainProvision><Article></Paragraph></MainProvision>
+  </LawBody>
+</Law>
+"#
+    )
+  }
+
+  #[test]
+  fn scans_only_the_header() {
+    let metadata = scan_law_header(
+      xml(
+        "<LawNum>平成十五年法律第五十七号</LawNum>",
+        r#"<LawTitle Kana="こじんじょうほうのほごにかんするほうりつ">個人情報の保護に関する法律</LawTitle>"#,
+      )
+      .as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(metadata.num, "平成十五年法律第五十七号");
+    assert_eq!(metadata.name, "個人情報の保護に関する法律");
+    assert_eq!(
+      metadata.name_kana.as_deref(),
+      Some("こじんじょうほうのほごにかんするほうりつ")
+    );
+    assert_eq!(metadata.date.year, 15);
+    assert_eq!(metadata.promulgation_date.unwrap().day, Some(30));
+  }
+
+  #[test]
+  fn stops_after_self_closing_header_tags() {
+    let metadata = scan_law_header(
+      xml(
+        "<LawNum>平成十五年法律第五十七号</LawNum>",
+        r#"<LawTitle Kana="ほう"/>"#,
+      )
+      .as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(metadata.name, "");
+    assert_eq!(metadata.name_kana.as_deref(), Some("ほう"));
+
+    let metadata = scan_law_header(xml("<LawNum/>", "<LawTitle>法</LawTitle>").as_bytes()).unwrap();
+    assert_eq!(metadata.num, "");
+    assert_eq!(metadata.name, "法");
+  }
+}
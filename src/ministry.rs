@@ -0,0 +1,32 @@
+//! 法令番号の文字列から、その命令を出した府省などを読み取る
+//!
+//! "平成十二年厚生省令第百二十七号"のような省令・府令・規則の法令番号の場合のみ読み取れる。
+//! 法律・政令・勅令などの場合は空になる。
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 府省などの名前ではない、法令の種類を表す接頭辞
+const NOT_MINISTRY: [&str; 4] = ["政", "勅", "閣", "太政官"];
+
+/// 法令番号から、その命令を出した府省などの名前を返す
+///
+/// "財務省・厚生労働省令第一号"のような共同命令の場合は、すべての府省などを返す
+pub fn ministries_of_law_num(law_num: &str) -> Vec<String> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| {
+    Regex::new(r"^(?:明治|大正|昭和|平成|令和)[^年]+年(?P<issuer>[^第年]+?)(?:令|規則)").unwrap()
+  });
+  let Some(caps) = re.captures(law_num.trim()) else {
+    return Vec::new();
+  };
+  let issuer = &caps["issuer"];
+  if NOT_MINISTRY.contains(&issuer) {
+    return Vec::new();
+  }
+  issuer
+    .split('・')
+    .filter(|s| !s.is_empty())
+    .map(str::to_string)
+    .collect()
+}
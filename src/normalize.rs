@@ -0,0 +1,157 @@
+//! 出力する文字列のUnicode正規化
+//!
+//! 元のXMLファイルでは、同じ法令名でも正規化形式が混ざっていることがあり、
+//! 見た目が同じ法令名の重複や、他のデータとの結合の失敗の原因になる
+
+use crate::record::LawRecord;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+/// 正規化形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+  /// 正規分解の後に正規合成する
+  #[default]
+  Nfc,
+  /// 互換分解の後に正規合成する（全角英数字は半角になる）
+  Nfkc,
+}
+
+impl FromStr for Normalization {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "nfc" => Ok(Normalization::Nfc),
+      "nfkc" => Ok(Normalization::Nfkc),
+      _ => Err(anyhow!("unknown normalization form: {s}")),
+    }
+  }
+}
+
+impl Normalization {
+  /// 文字列を正規化する
+  pub fn apply(&self, s: &str) -> String {
+    match self {
+      Normalization::Nfc => s.nfc().collect(),
+      Normalization::Nfkc => s.nfkc().collect(),
+    }
+  }
+
+  fn apply_in_place(&self, s: &mut String) {
+    *s = self.apply(s);
+  }
+}
+
+/// 法令の情報に含まれる、XMLファイルなどから読み取った文字列をすべて正規化する
+///
+/// 法令IDやファイルへのpathは正規化しない
+pub fn normalize_laws(laws: &mut [LawRecord], form: Normalization) {
+  for law in laws {
+    form.apply_in_place(&mut law.name);
+    form.apply_in_place(&mut law.num);
+    if let Some(kana) = &mut law.name_kana {
+      form.apply_in_place(kana);
+    }
+    law.abbrev.iter_mut().for_each(|s| form.apply_in_place(s));
+    law
+      .ministries
+      .iter_mut()
+      .for_each(|s| form.apply_in_place(s));
+    for (_, name) in law.historical_names.iter_mut() {
+      form.apply_in_place(name);
+    }
+    for name in law
+      .patch
+      .iter_mut()
+      .filter_map(|patch| patch.patch_name.as_mut())
+    {
+      form.apply_in_place(name);
+    }
+    for article in law.patch.iter_mut().flat_map(|patch| &mut patch.articles) {
+      form.apply_in_place(&mut article.title);
+      if let Some(caption) = &mut article.caption {
+        form.apply_in_place(caption);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::article::ArticleEntry;
+  use crate::record::testing::{date, law};
+
+  #[test]
+  fn normalizes_names_but_not_ids() {
+    assert_eq!(Normalization::Nfc.apply("か\u{3099}ＡＢＣ"), "がＡＢＣ");
+    assert_eq!(Normalization::Nfkc.apply("か\u{3099}ＡＢＣｶﾞ"), "がABCガ");
+    assert_eq!(
+      "nfkc".parse::<Normalization>().unwrap(),
+      Normalization::Nfkc
+    );
+    assert!("nfd".parse::<Normalization>().is_err());
+
+    let mut privacy = law("415AC0000000057", "ＡＢＣ法");
+    privacy.name_kana = Some("ｴｰﾋﾞｰｼｰﾎｳ".to_string());
+    privacy.abbrev = vec!["ＡＢＣ".to_string()];
+    privacy.historical_names = vec![(date("Heisei", 15, 5, 30), "旧ＡＢＣ法".to_string())];
+    privacy.patch[0].patch_name = Some("ＡＢＣ法の一部を改正する法律".to_string());
+    let file = privacy.patch[0].file.clone();
+    let mut laws = vec![privacy];
+    normalize_laws(&mut laws, Normalization::Nfkc);
+
+    let privacy = &laws[0];
+    assert_eq!(privacy.name, "ABC法");
+    assert_eq!(privacy.name_kana.as_deref(), Some("エービーシーホウ"));
+    assert_eq!(privacy.abbrev, ["ABC"]);
+    assert_eq!(privacy.historical_names[0].1, "旧ABC法");
+    assert_eq!(
+      privacy.patch[0].patch_name.as_deref(),
+      Some("ABC法の一部を改正する法律")
+    );
+    assert_eq!(privacy.id.to_string(), "415AC0000000057");
+    assert_eq!(privacy.patch[0].file, file);
+  }
+
+  #[test]
+  fn merges_names_written_in_different_forms_with_the_default() {
+    // 濁点を合成済みの文字で書いたものと、結合文字で書いたもの
+    let composed = law("415AC0000000057", "がん対策基本法");
+    let decomposed = law("415AC0000000057", "か\u{3099}ん対策基本法");
+    assert_ne!(composed.name, decomposed.name);
+    let mut laws = vec![composed, decomposed];
+    normalize_laws(&mut laws, Normalization::default());
+    assert_eq!(laws[0].name, laws[1].name);
+    assert_eq!(laws[0].name, "がん対策基本法");
+
+    // NFCでは全角英数字や半角カナはそのままにする
+    assert_eq!(Normalization::Nfc.apply("ＡＢＣｶﾞ"), "ＡＢＣｶﾞ");
+    assert_eq!(Normalization::Nfc.apply(""), "");
+    assert!("NFC".parse::<Normalization>().is_err());
+  }
+
+  #[test]
+  fn normalizes_ministries_and_articles() {
+    let mut rule = law("415AC0000000057", "規則");
+    rule.num = "平成十五年法律第５７号".to_string();
+    rule.ministries = vec!["ﾃｽﾄ省".to_string()];
+    rule.patch[0].articles = vec![ArticleEntry {
+      num: "1".to_string(),
+      title: "第１条".to_string(),
+      caption: Some("（ﾃｽﾄ）".to_string()),
+    }];
+    let mut laws = vec![rule];
+    normalize_laws(&mut laws, Normalization::Nfkc);
+
+    let rule = &laws[0];
+    assert_eq!(rule.num, "平成十五年法律第57号");
+    assert_eq!(rule.ministries, ["テスト省"]);
+    assert_eq!(rule.patch[0].articles[0].title, "第1条");
+    assert_eq!(
+      rule.patch[0].articles[0].caption.as_deref(),
+      Some("(テスト)")
+    );
+  }
+}
@@ -0,0 +1,671 @@
+//! 法令の一覧をファイルに書き出す
+
+use crate::article::{article_records, ArticleRecord};
+use crate::calendar::{date_to_ad, era_from_attr, era_from_kanji, to_iso8601, AdDate, EraExt};
+use crate::record::{LawRecord, OutputHeader};
+use crate::snapshot::{snapshot_records, SnapshotRecord};
+use crate::stats::law_type_of_num;
+use anyhow::{anyhow, Result};
+use japanese_law_xml_schema::law::Era;
+use jplaw_io::{
+  end_log, flush_file_value_lst, gen_file_value_lst, info_log, start_log, write_value_lst,
+};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::str::FromStr;
+use tokio::{fs::File, io::AsyncWriteExt, io::BufWriter};
+
+/// 出力するファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+  /// 法令の情報の配列を持つJSONファイル
+  #[default]
+  Json,
+  /// 1行に1つの法令の情報を書くJSON Linesファイル
+  Jsonl,
+  /// 1行に1つの法令の情報を書くCSVファイル
+  ///
+  /// 改正の一覧は"foo.patches.csv"のような別のCSVファイルに書く
+  Csv,
+}
+
+impl FromStr for OutputFormat {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "json" => Ok(OutputFormat::Json),
+      "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+      "csv" => Ok(OutputFormat::Csv),
+      _ => Err(anyhow!("unknown output format: {s}")),
+    }
+  }
+}
+
+/// 出力する1行（1要素）の単位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordGranularity {
+  /// 法令ごとに1つとし、改正の一覧を入れ子にする
+  #[default]
+  Law,
+  /// 改正ごとのXMLファイルごとに1つとする
+  Snapshot,
+  /// XMLファイルの本則の条ごとに1つとする
+  Article,
+}
+
+impl FromStr for RecordGranularity {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "law" => Ok(RecordGranularity::Law),
+      "snapshot" => Ok(RecordGranularity::Snapshot),
+      "article" => Ok(RecordGranularity::Article),
+      _ => Err(anyhow!("unknown record granularity: {s}")),
+    }
+  }
+}
+
+/// 出力を複数のファイルに分けるときの単位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitKey {
+  /// 公布された元号ごと（`output_reiwa.json`など）
+  Era,
+  /// 公布された西暦の年ごと（`output_2003.json`など）
+  Year,
+  /// 法令番号から読み取った法令の種類ごと（`output_法律.json`など）
+  LawType,
+}
+
+impl FromStr for SplitKey {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "era" => Ok(SplitKey::Era),
+      "year" => Ok(SplitKey::Year),
+      "law-type" => Ok(SplitKey::LawType),
+      _ => Err(anyhow!("unknown split key: {s}")),
+    }
+  }
+}
+
+impl SplitKey {
+  /// 法令を入れるファイルを表す名前
+  ///
+  /// 法令の種類が法令番号から読み取れない場合は`other`とする
+  pub fn slice_of(&self, law: &LawRecord) -> String {
+    match self {
+      SplitKey::Era => law.date.era.attr().to_lowercase(),
+      SplitKey::Year => date_to_ad(law.promulgation_date.as_ref().unwrap_or(&law.date))
+        .year
+        .to_string(),
+      SplitKey::LawType => law_type_of_num(&law.num).unwrap_or("other").to_string(),
+    }
+  }
+}
+
+/// `output.json`を`output_reiwa.json`のように、ファイル名の拡張子の前に`_`と`slice`を加えたpathにする
+pub fn sliced_path(path: &str, slice: &str) -> String {
+  let name_start = path
+    .rfind(|c| c == '/' || c == '\\')
+    .map(|i| i + 1)
+    .unwrap_or(0);
+  match path[name_start..].rfind('.') {
+    Some(i) if i > 0 => {
+      let (stem, ext) = path.split_at(name_start + i);
+      format!("{stem}_{slice}{ext}")
+    }
+    _ => format!("{path}_{slice}"),
+  }
+}
+
+fn log_law(data: &LawRecord) {
+  let lst = data
+    .patch
+    .iter()
+    .map(|p| p.info.clone())
+    .collect::<Vec<_>>();
+  info_log("patch list", &lst);
+}
+
+/// JSON形式の法令の一覧を、法令を1つずつ受け取って書き出す
+pub enum JsonLawWriter {
+  /// 法令の情報の配列だけを書く（`--legacy-format`）
+  Legacy(File),
+  /// 書き出したときの情報と`laws`に法令の情報の配列を持つオブジェクトを書く
+  Envelope {
+    file: BufWriter<File>,
+    /// まだ法令を1つも書いていないかどうか
+    first: bool,
+  },
+}
+
+impl JsonLawWriter {
+  /// `header`が`None`の場合は法令の情報の配列だけを書く
+  pub async fn create(path: &str, header: Option<&OutputHeader>) -> Result<Self> {
+    let Some(header) = header else {
+      return Ok(JsonLawWriter::Legacy(gen_file_value_lst(path).await?));
+    };
+    let mut file = BufWriter::new(File::create(path).await?);
+    // ヘッダーのオブジェクトの閉じ括弧の代わりに`laws`を続ける
+    let header = serde_json::to_string(header)?;
+    let header = header.strip_suffix('}').unwrap_or(&header);
+    file
+      .write_all(format!("{header},\"laws\":[\n").as_bytes())
+      .await?;
+    Ok(JsonLawWriter::Envelope { file, first: true })
+  }
+
+  /// 法令1つ分を書き出す
+  pub async fn write(&mut self, law: LawRecord) -> Result<()> {
+    match self {
+      JsonLawWriter::Legacy(file) => write_value_lst(file, law).await?,
+      JsonLawWriter::Envelope { file, first } => {
+        if !*first {
+          file.write_all(b",\n").await?;
+        }
+        *first = false;
+        file
+          .write_all(serde_json::to_string(&law)?.as_bytes())
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// 書き出しを終える
+  pub async fn finish(self) -> Result<()> {
+    match self {
+      JsonLawWriter::Legacy(mut file) => flush_file_value_lst(&mut file).await?,
+      JsonLawWriter::Envelope { mut file, .. } => {
+        file.write_all(b"\n]}\n").await?;
+        file.flush().await?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// 法令の一覧を指定した形式でファイルに書き出す
+///
+/// JSON形式の場合は`header`を法令の一覧と同じオブジェクトに書き、`None`の場合は法令の情報の配列だけを書く。
+/// 改正の一覧は必ず改正の日付の順に並べ替えてから書く
+pub async fn write_law_lst(
+  path: &str,
+  format: OutputFormat,
+  mut laws: Vec<LawRecord>,
+  header: Option<&OutputHeader>,
+) -> Result<()> {
+  for law in laws.iter_mut() {
+    law.sort_patches();
+  }
+  match format {
+    OutputFormat::Json => {
+      let mut output_file = JsonLawWriter::create(path, header).await?;
+      for data in laws {
+        let id = data.id.clone();
+        start_log("write law info", &id);
+        log_law(&data);
+        output_file.write(data).await?;
+        end_log("write law info", &id);
+      }
+      output_file.finish().await?;
+    }
+    OutputFormat::Jsonl => {
+      let mut output_file = BufWriter::new(File::create(path).await?);
+      for data in laws {
+        let id = data.id.clone();
+        start_log("write law info", &id);
+        log_law(&data);
+        let mut line = serde_json::to_string(&data)?;
+        line.push('\n');
+        output_file.write_all(line.as_bytes()).await?;
+        end_log("write law info", &id);
+      }
+      output_file.flush().await?;
+    }
+    OutputFormat::Csv => write_csv(path, &laws)?,
+  }
+  Ok(())
+}
+
+/// CSVファイルの改正の一覧を書くファイルへのpath
+pub fn patches_csv_path(path: &str) -> String {
+  let stem = path.strip_suffix(".csv").unwrap_or(path);
+  format!("{stem}.patches.csv")
+}
+
+fn write_csv(path: &str, laws: &[LawRecord]) -> Result<()> {
+  let mut law_wtr = csv::Writer::from_path(path)?;
+  law_wtr.write_record([
+    "id",
+    "num",
+    "name",
+    "era",
+    "year",
+    "month",
+    "day",
+    "file",
+    "latest_patch_date",
+    "ministry",
+  ])?;
+  let mut patch_wtr = csv::Writer::from_path(patches_csv_path(path))?;
+  patch_wtr.write_record([
+    "id",
+    "patch_date",
+    "patch_id",
+    "file",
+    "patch_name",
+    "patch_num",
+  ])?;
+  for law in laws {
+    let date = law.promulgation_date.as_ref().unwrap_or(&law.date);
+    let latest = law.patch.iter().max_by(|a, b| a.order(b));
+    law_wtr.write_record([
+      law.id.to_string(),
+      law.num.clone(),
+      law.name.clone(),
+      date.era.attr().to_string(),
+      date.year.to_string(),
+      date.month.map(|m| m.to_string()).unwrap_or_default(),
+      date.day.map(|d| d.to_string()).unwrap_or_default(),
+      latest.map(|p| p.file.clone()).unwrap_or_default(),
+      latest
+        .map(|p| to_iso8601(&p.info.patch_date))
+        .unwrap_or_default(),
+      law.ministries.join("・"),
+    ])?;
+    for patch in &law.patch {
+      patch_wtr.write_record([
+        law.id.to_string(),
+        to_iso8601(&patch.info.patch_date),
+        patch
+          .info
+          .patch_id
+          .as_ref()
+          .map(|id| id.to_string())
+          .unwrap_or_default(),
+        patch.file.clone(),
+        patch.patch_name.clone().unwrap_or_default(),
+        patch.patch_num.clone().unwrap_or_default(),
+      ])?;
+    }
+  }
+  law_wtr.flush()?;
+  patch_wtr.flush()?;
+  Ok(())
+}
+
+/// XMLファイルごとの一覧を指定した形式でファイルに書き出す
+///
+/// CSVの場合も1つのファイルにすべての項目を書く
+pub async fn write_snapshot_lst(
+  path: &str,
+  format: OutputFormat,
+  snapshots: &[SnapshotRecord],
+) -> Result<()> {
+  write_flat_lst(path, format, snapshots).await
+}
+
+/// 条ごとの一覧を指定した形式でファイルに書き出す
+///
+/// CSVの場合も1つのファイルにすべての項目を書く
+pub async fn write_article_lst(
+  path: &str,
+  format: OutputFormat,
+  articles: &[ArticleRecord],
+) -> Result<()> {
+  write_flat_lst(path, format, articles).await
+}
+
+/// 入れ子の無い項目の一覧を書き出す
+async fn write_flat_lst<T: Serialize>(path: &str, format: OutputFormat, lst: &[T]) -> Result<()> {
+  match format {
+    OutputFormat::Json => {
+      tokio::fs::write(path, serde_json::to_string_pretty(lst)?).await?;
+    }
+    OutputFormat::Jsonl => {
+      let mut output_file = BufWriter::new(File::create(path).await?);
+      for item in lst {
+        let mut line = serde_json::to_string(item)?;
+        line.push('\n');
+        output_file.write_all(line.as_bytes()).await?;
+      }
+      output_file.flush().await?;
+    }
+    OutputFormat::Csv => {
+      let mut wtr = csv::Writer::from_path(path)?;
+      for item in lst {
+        wtr.serialize(item)?;
+      }
+      wtr.flush()?;
+    }
+  }
+  Ok(())
+}
+
+/// `--output`に指定する出力先1つ
+///
+/// `heisei.csv,format=csv,era=Heisei|Reiwa,since=1990-01-01`のように、
+/// pathの後ろにカンマ区切りで形式や絞り込みの条件を書ける。
+/// 書かなかった形式などは、全体に指定したものを使う
+#[derive(Debug, Clone)]
+pub struct OutputSpec {
+  /// 出力するファイルへのpath
+  pub path: String,
+  /// 出力形式
+  pub format: Option<OutputFormat>,
+  /// 出力する1行の単位
+  pub granularity: Option<RecordGranularity>,
+  /// 公布された元号で絞り込む（空の場合は絞り込まない）
+  pub eras: Vec<Era>,
+  /// この日以降に公布された法令だけを含める
+  pub since: Option<AdDate>,
+  /// この日以前に公布された法令だけを含める
+  pub until: Option<AdDate>,
+  /// `--split-by`で分けた出力先の場合、その単位と法令を入れるファイルを表す名前
+  pub split: Option<(SplitKey, String)>,
+}
+
+impl FromStr for OutputSpec {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    let mut parts = s.split(',');
+    let path = parts.next().unwrap_or_default().to_string();
+    if path.is_empty() {
+      return Err(anyhow!("output path is empty: {s}"));
+    }
+    let mut spec = OutputSpec {
+      path,
+      format: None,
+      granularity: None,
+      eras: Vec::new(),
+      since: None,
+      until: None,
+      split: None,
+    };
+    for part in parts {
+      let (key, value) = part.split_once('=').ok_or(anyhow!(
+        "output option must be written as key=value: {part}"
+      ))?;
+      match key {
+        "format" => spec.format = Some(value.parse()?),
+        "granularity" => spec.granularity = Some(value.parse()?),
+        "era" => {
+          for era in value.split('|') {
+            spec.eras.push(
+              era_from_attr(era)
+                .or_else(|| era_from_kanji(era))
+                .ok_or(anyhow!("unknown era: {era}"))?,
+            );
+          }
+        }
+        "since" => spec.since = Some(value.parse()?),
+        "until" => spec.until = Some(value.parse()?),
+        _ => return Err(anyhow!("unknown output option: {key}")),
+      }
+    }
+    Ok(spec)
+  }
+}
+
+impl OutputSpec {
+  /// この出力先に法令を含めるかどうか
+  pub fn accepts(&self, law: &LawRecord) -> bool {
+    if !self.eras.is_empty()
+      && !self
+        .eras
+        .iter()
+        .any(|era| era.attr() == law.date.era.attr())
+    {
+      return false;
+    }
+    if let Some((key, slice)) = &self.split {
+      if key.slice_of(law) != *slice {
+        return false;
+      }
+    }
+    let date = date_to_ad(law.promulgation_date.as_ref().unwrap_or(&law.date));
+    !(matches!(self.since, Some(since) if date < since)
+      || matches!(self.until, Some(until) if until < date))
+  }
+
+  /// `key`で分けた出力先にする
+  ///
+  /// この出力先に含める法令のうち、実際に現れた単位ごとに1つの出力先を作る
+  pub fn split(&self, key: SplitKey, laws: &[LawRecord]) -> Vec<OutputSpec> {
+    let slices = laws
+      .iter()
+      .filter(|law| self.accepts(law))
+      .map(|law| key.slice_of(law))
+      .collect::<BTreeSet<_>>();
+    slices
+      .into_iter()
+      .map(|slice| OutputSpec {
+        path: sliced_path(&self.path, &slice),
+        split: Some((key, slice)),
+        ..self.clone()
+      })
+      .collect()
+  }
+}
+
+/// 法令の一覧を複数の出力先に書き出す
+///
+/// XMLファイルの読み直しが必要な改正ごとの一覧は、必要な場合に一度だけ作る。
+/// `header`は法令ごとのJSON形式の出力にだけ書く
+pub async fn write_outputs(
+  specs: &[OutputSpec],
+  format: OutputFormat,
+  granularity: RecordGranularity,
+  laws: Vec<LawRecord>,
+  header: Option<&OutputHeader>,
+) -> Result<()> {
+  let mut snapshots: Option<Vec<SnapshotRecord>> = None;
+  for spec in specs {
+    let format = spec.format.unwrap_or(format);
+    start_log("write output", &spec.path);
+    match spec.granularity.unwrap_or(granularity) {
+      RecordGranularity::Law => {
+        let lst = laws
+          .iter()
+          .filter(|law| spec.accepts(law))
+          .cloned()
+          .collect();
+        write_law_lst(&spec.path, format, lst, header).await?;
+      }
+      RecordGranularity::Snapshot => {
+        if snapshots.is_none() {
+          snapshots = Some(snapshot_records(&laws)?);
+        }
+        let ids = laws
+          .iter()
+          .filter(|law| spec.accepts(law))
+          .map(|law| law.id.to_string())
+          .collect::<HashSet<_>>();
+        let lst = snapshots
+          .iter()
+          .flatten()
+          .filter(|snapshot| ids.contains(&snapshot.id))
+          .cloned()
+          .collect::<Vec<_>>();
+        write_snapshot_lst(&spec.path, format, &lst).await?;
+      }
+      RecordGranularity::Article => {
+        let lst = article_records(
+          &laws
+            .iter()
+            .filter(|law| spec.accepts(law))
+            .cloned()
+            .collect::<Vec<_>>(),
+        );
+        write_article_lst(&spec.path, format, &lst).await?;
+      }
+    }
+    end_log("write output", &spec.path);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::{date, law};
+  use jplaw_data_types::law::Date;
+
+  fn laws() -> Vec<LawRecord> {
+    let mut civil = law("129AC0000000089", "民法");
+    civil.num = "明治二十九年法律第八十九号".to_string();
+    civil.date = Date::new(era_from_attr("Meiji").unwrap(), 29, None, None);
+    civil.promulgation_date = Some(date("Meiji", 29, 4, 27));
+    let mut order = law("415CO0000000507", "個人情報の保護に関する法律施行令");
+    order.num = "平成十五年政令第五百七号".to_string();
+    vec![
+      civil,
+      law("415AC0000000057", "個人情報の保護に関する法律"),
+      order,
+    ]
+  }
+
+  #[test]
+  fn parses_output_specs() {
+    let spec = "heisei.csv,format=csv,granularity=snapshot,era=Heisei|令和,since=1990-01-01,until=2020-12-31"
+      .parse::<OutputSpec>()
+      .unwrap();
+    assert_eq!(spec.path, "heisei.csv");
+    assert_eq!(spec.format, Some(OutputFormat::Csv));
+    assert_eq!(spec.granularity, Some(RecordGranularity::Snapshot));
+    let eras = spec.eras.iter().map(|era| era.attr()).collect::<Vec<_>>();
+    assert_eq!(eras, ["Heisei", "Reiwa"]);
+    assert_eq!(spec.since, Some(AdDate::new(1990, 1, 1)));
+    assert_eq!(spec.until, Some(AdDate::new(2020, 12, 31)));
+
+    let spec = "all.json".parse::<OutputSpec>().unwrap();
+    assert_eq!(spec.format, None);
+    assert!(spec.eras.is_empty());
+    for s in [
+      "",
+      ",format=csv",
+      "all.json,format",
+      "all.json,format=xml",
+      "all.json,era=Future",
+      "all.json,since=2020-02-30",
+      "all.json,shard=1/2",
+    ] {
+      assert!(s.parse::<OutputSpec>().is_err(), "{s}");
+    }
+  }
+
+  #[test]
+  fn filters_laws_by_era_and_date() {
+    let laws = laws();
+    let accepted = |spec: &str| {
+      let spec = spec.parse::<OutputSpec>().unwrap();
+      laws
+        .iter()
+        .filter(|law| spec.accepts(law))
+        .map(|law| law.name.as_str())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(accepted("all.json").len(), 3);
+    assert_eq!(accepted("meiji.json,era=明治"), ["民法"]);
+    // 公布の年月日で比べ、両端を含む
+    assert_eq!(
+      accepted("a.json,since=2003-05-30,until=2003-05-30").len(),
+      2
+    );
+    assert!(accepted("a.json,since=2003-05-31,era=Heisei").is_empty());
+  }
+
+  #[test]
+  fn splits_outputs_by_key() {
+    let laws = laws();
+    let split = |spec: &str, key: SplitKey| {
+      spec
+        .parse::<OutputSpec>()
+        .unwrap()
+        .split(key, &laws)
+        .into_iter()
+        .map(|spec| {
+          let count = laws.iter().filter(|law| spec.accepts(law)).count();
+          (spec.path, count)
+        })
+        .collect::<Vec<_>>()
+    };
+    let expected = |paths: &[(&str, usize)]| {
+      paths
+        .iter()
+        .map(|(path, count)| (path.to_string(), *count))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      split("out/all.json", SplitKey::Era),
+      expected(&[("out/all_heisei.json", 2), ("out/all_meiji.json", 1)])
+    );
+    assert_eq!(
+      split("out/all.json", SplitKey::Year),
+      expected(&[("out/all_1896.json", 1), ("out/all_2003.json", 2)])
+    );
+    assert_eq!(
+      split("out/all.json", SplitKey::LawType),
+      expected(&[("out/all_政令.json", 1), ("out/all_法律.json", 2)])
+    );
+    // 出力先の条件で除いた法令の単位は作らない
+    assert_eq!(
+      split("out/all.json,era=Heisei", SplitKey::Era),
+      expected(&[("out/all_heisei.json", 2)])
+    );
+
+    let mut unknown = law("415AC0000000057", "番号の無い法令");
+    unknown.num = "番号なし".to_string();
+    assert_eq!(SplitKey::LawType.slice_of(&unknown), "other");
+    assert_eq!("law-type".parse::<SplitKey>().unwrap(), SplitKey::LawType);
+    assert!("month".parse::<SplitKey>().is_err());
+  }
+
+  #[test]
+  fn splits_around_an_era_change() {
+    // 2019年4月30日までは平成、5月1日からは令和
+    let mut heisei = law("431AC0000000001", "平成最後の法令");
+    heisei.num = "平成三十一年法律第一号".to_string();
+    heisei.date = Date::new(era_from_attr("Heisei").unwrap(), 31, None, None);
+    heisei.promulgation_date = Some(date("Heisei", 31, 4, 30));
+    let mut reiwa = law("501AC0000000001", "令和最初の法令");
+    reiwa.num = "令和元年法律第一号".to_string();
+    reiwa.date = Date::new(era_from_attr("Reiwa").unwrap(), 1, None, None);
+    // 公布の月日が分からない場合は年だけで分ける
+    reiwa.promulgation_date = None;
+    let laws = vec![heisei, reiwa];
+    let paths = |key: SplitKey| {
+      "output.json"
+        .parse::<OutputSpec>()
+        .unwrap()
+        .split(key, &laws)
+        .into_iter()
+        .map(|spec| spec.path)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      paths(SplitKey::Era),
+      ["output_heisei.json", "output_reiwa.json"]
+    );
+    assert_eq!(paths(SplitKey::Year), ["output_2019.json"]);
+    assert!("output.json"
+      .parse::<OutputSpec>()
+      .unwrap()
+      .split(SplitKey::Era, &[])
+      .is_empty());
+  }
+
+  #[test]
+  fn inserts_slice_before_extension() {
+    assert_eq!(sliced_path("out/all.json", "heisei"), "out/all_heisei.json");
+    assert_eq!(sliced_path("out.v2/all", "2003"), "out.v2/all_2003");
+    assert_eq!(
+      sliced_path(r"out\all.tar.json", "2003"),
+      r"out\all.tar_2003.json"
+    );
+    assert_eq!(sliced_path(".laws", "2003"), ".laws_2003");
+  }
+}
@@ -0,0 +1,39 @@
+//! 作業ディレクトリを走査するときに、XMLファイルへのpathで処理するかどうかを決める
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::str::FromStr;
+
+/// XMLファイルへのpathに対する条件
+///
+/// `re:`で始まる場合は正規表現として、それ以外はglobパターンとしてpath全体に対して照合する。
+/// globパターンの`*`は`/`にも合う
+#[derive(Debug, Clone)]
+pub enum PathFilter {
+  Glob(glob::Pattern),
+  Regex(Regex),
+}
+
+impl FromStr for PathFilter {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    match s.strip_prefix("re:") {
+      Some(re) => Ok(PathFilter::Regex(
+        Regex::new(re).map_err(|e| anyhow!("invalid path regex {re}: {e}"))?,
+      )),
+      None => Ok(PathFilter::Glob(
+        glob::Pattern::new(s).map_err(|e| anyhow!("invalid path glob {s}: {e}"))?,
+      )),
+    }
+  }
+}
+
+impl PathFilter {
+  /// pathが条件に合うかどうか
+  pub fn matches(&self, path: &str) -> bool {
+    match self {
+      PathFilter::Glob(pattern) => pattern.matches(path),
+      PathFilter::Regex(re) => re.is_match(path),
+    }
+  }
+}
@@ -0,0 +1,282 @@
+//! 法令の一覧を作る処理を、容量に上限のあるチャネルでつないだ段に分けて行う
+//!
+//! ```text
+//! spawn_walker（spawn_walkers） → spawn_parser → (spawn_stage …) → collect_law_list
+//! ```
+//!
+//! 各段は別々のtaskで動き、後ろの段の処理が遅い場合はチャネルが埋まって前の段が待つため、
+//! 読み込んだ結果がメモリ上に溜まり続けることはない。
+//! ライブラリの利用者は`spawn_stage`で独自の段を間に挟める。
+
+use crate::error::{FileError, ListupError};
+use crate::listup::{read_law_file, LawFileInfo, LawList, ListupOptions, PathStyle, XmlFile};
+use crate::state::{FileStamp, ListupState};
+use anyhow::Result;
+use jplaw_io::{info_log, wran_log};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::*;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// 各段をつなぐチャネルの容量
+pub const CHANNEL_CAPACITY: usize = 256;
+
+/// 解析を終えたXMLファイル
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+  pub file: XmlFile,
+  pub info: LawFileInfo,
+}
+
+/// pathを、区切り文字を`/`にそろえた文字列にする
+///
+/// UTF-8として読めない場合は`None`を返す
+pub fn normalize_path(path: &Path) -> Option<String> {
+  let s = path.to_str()?;
+  if cfg!(windows) {
+    Some(s.replace('\\', "/"))
+  } else {
+    Some(s.to_string())
+  }
+}
+
+/// 作業ディレクトリの下のディレクトリ1つの中のXMLファイルを送る
+///
+/// ファイル名やpathがUTF-8として読めないファイルは、e-govのファイルではないので`options.skip_errors`によらず
+/// 警告を出し、エラーとして記録して飛ばす。送り先が閉じていた場合は`false`を返す
+async fn walk_dir(
+  dir_path: &Path,
+  source: Option<&str>,
+  options: &ListupOptions,
+  with_stamp: bool,
+  tx: &Sender<XmlFile>,
+) -> Result<(bool, Vec<FileError>)> {
+  let mut errors = Vec::new();
+  let mut new_dir = read_dir(dir_path).await?;
+  while let Some(new_entry) = new_dir.next_entry().await? {
+    if new_entry.file_type().await?.is_file() {
+      let path = new_entry.path();
+      let (Some(file_name_string), Some(file_path)) = (
+        new_entry.file_name().to_str().map(str::to_string),
+        normalize_path(&path),
+      ) else {
+        let file = path.to_string_lossy().to_string();
+        let err = ListupError::NonUtf8FileName { file: file.clone() };
+        wran_log("skip file", &err.to_string());
+        errors.push(FileError::new(&file, &err));
+        continue;
+      };
+      info_log("xml path", &file_path);
+      if !options.is_target(&file_name_string) || !options.includes_path(&file_path) {
+        continue;
+      }
+      let stamp = if with_stamp {
+        Some(FileStamp::new(&new_entry.metadata().await?)?)
+      } else {
+        None
+      };
+      let file = XmlFile {
+        file_name: file_name_string,
+        file_path,
+        stamp,
+        source: source.map(str::to_string),
+      };
+      if tx.send(file).await.is_err() {
+        return Ok((false, errors));
+      }
+    }
+  }
+  Ok((true, errors))
+}
+
+/// 作業ディレクトリの中の処理対象のXMLファイルを見つけた順に送る段
+///
+/// e-govで配布されているファイルは"法令データ一式/foobarbaz/foobarbaz.xml"のような形で配布されていて、、
+/// work_dirに"法令データ一式"が入ると想定している。
+/// 下のディレクトリの読み込みは`options.walk_jobs`個まで並行して行う。
+/// `with_stamp`が`true`の場合は、ファイルの更新日時と大きさも読む。
+///
+/// ファイル名がUTF-8として読めないなどの理由で飛ばしたファイルの一覧を返す
+pub fn spawn_walker(
+  work_dir: &str,
+  options: &ListupOptions,
+  with_stamp: bool,
+) -> (Receiver<XmlFile>, JoinHandle<Result<Vec<FileError>>>) {
+  spawn_walkers(&[work_dir.to_string()], options, with_stamp)
+}
+
+/// 複数の作業ディレクトリの中の処理対象のXMLファイルを見つけた順に送る段
+///
+/// 作業ディレクトリが2つ以上の場合は、どの作業ディレクトリで見つけたかを`XmlFile::source`に入れる
+pub fn spawn_walkers(
+  work_dirs: &[String],
+  options: &ListupOptions,
+  with_stamp: bool,
+) -> (Receiver<XmlFile>, JoinHandle<Result<Vec<FileError>>>) {
+  let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+  let work_dirs = work_dirs.to_vec();
+  let options = options.clone();
+  let handle = tokio::spawn(async move {
+    let with_source = work_dirs.len() > 1;
+    let mut dirs = Vec::new();
+    for work_dir in &work_dirs {
+      let base = match options.path_style {
+        PathStyle::Relative => PathBuf::from(work_dir),
+        PathStyle::Absolute => std::env::current_dir()?.join(work_dir),
+      };
+      let mut work_dir_info = read_dir(&base).await?;
+      while let Some(dir_entry) = work_dir_info.next_entry().await? {
+        if dir_entry.file_type().await?.is_dir() {
+          dirs.push((work_dir.clone(), dir_entry.path()));
+        }
+      }
+    }
+    let walk_jobs = options.walk_jobs.max(1);
+    let mut dirs = dirs.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut errors = Vec::new();
+    loop {
+      while tasks.len() < walk_jobs {
+        let Some((work_dir, dir_path)) = dirs.next() else {
+          break;
+        };
+        let options = options.clone();
+        let tx = tx.clone();
+        tasks.spawn(async move {
+          let source = with_source.then_some(work_dir.as_str());
+          walk_dir(&dir_path, source, &options, with_stamp, &tx).await
+        });
+      }
+      let Some(res) = tasks.join_next().await else {
+        break;
+      };
+      let (open, dir_errors) = res??;
+      errors.extend(dir_errors);
+      if !open {
+        break;
+      }
+    }
+    Ok::<_, anyhow::Error>(errors)
+  });
+  (rx, handle)
+}
+
+/// XMLファイルを解析する段
+///
+/// 解析は`options.jobs`個まで並行して行い、終わった順に送る。
+/// `state`に変更の無いファイルの解析結果が記録されている場合は、解析せずにそれを使う。
+///
+/// `options.skip_errors`が`true`の場合などは、解析に失敗したファイルを飛ばして処理を続け、
+/// 飛ばしたファイルの一覧を返す。それ以外の場合は最初のエラーで止まる
+pub fn spawn_parser(
+  mut rx: Receiver<XmlFile>,
+  options: &ListupOptions,
+  state: Option<Arc<ListupState>>,
+) -> (Receiver<ParsedFile>, JoinHandle<Result<Vec<FileError>>>) {
+  let (tx, out) = mpsc::channel(CHANNEL_CAPACITY);
+  let options = Arc::new(options.clone());
+  let jobs = options.jobs.max(1);
+  let handle = tokio::spawn(async move {
+    let mut tasks = JoinSet::new();
+    let mut input_closed = false;
+    let mut errors = Vec::new();
+    loop {
+      tokio::select! {
+        file = rx.recv(), if !input_closed && tasks.len() < jobs => {
+          let Some(file) = file else {
+            input_closed = true;
+            continue;
+          };
+          // 記録には条とハッシュ値を残していないので、それらを読み取る場合は解析し直す
+          let cached = match (&state, &file.stamp) {
+            _ if options.articles || options.snapshots => None,
+            (Some(state), Some(stamp)) => state.get(&file.file_path, stamp).cloned(),
+            _ => None,
+          };
+          if let Some(mut info) = cached {
+            info.patch.source = file.source.clone();
+            if tx.send(ParsedFile { file, info }).await.is_err() {
+              return Ok(errors);
+            }
+          } else {
+            let options = options.clone();
+            tasks.spawn_blocking(move || {
+              let info = read_law_file(&file.file_name, &file.file_path, &options);
+              (file, info)
+            });
+          }
+        }
+        Some(res) = tasks.join_next(), if !tasks.is_empty() => {
+          let (file, info) = res?;
+          let info = match info {
+            Ok(mut info) => {
+              info.patch.source = file.source.clone();
+              info
+            }
+            Err(err) if options.skips(&err) => {
+              wran_log("skip file", &err.to_string());
+              errors.push(FileError::new(&file.file_path, &err));
+              continue;
+            }
+            Err(err) => return Err(err.into()),
+          };
+          if tx.send(ParsedFile { file, info }).await.is_err() {
+            return Ok(errors);
+          }
+        }
+        else => break,
+      }
+    }
+    Ok::<_, anyhow::Error>(errors)
+  });
+  (out, handle)
+}
+
+/// 解析結果に手を加える段
+///
+/// `f`が`None`を返したファイルは後ろの段に送らない
+pub fn spawn_stage<F>(
+  mut rx: Receiver<ParsedFile>,
+  mut f: F,
+) -> (Receiver<ParsedFile>, JoinHandle<Result<()>>)
+where
+  F: FnMut(ParsedFile) -> Option<ParsedFile> + Send + 'static,
+{
+  let (tx, out) = mpsc::channel(CHANNEL_CAPACITY);
+  let handle = tokio::spawn(async move {
+    while let Some(parsed) = rx.recv().await {
+      if let Some(parsed) = f(parsed) {
+        if tx.send(parsed).await.is_err() {
+          break;
+        }
+      }
+    }
+    Ok(())
+  });
+  (out, handle)
+}
+
+/// 解析結果を集めて法令の一覧にする段
+///
+/// `options`の条件に合わない法令は一覧に加えない。
+/// 解析が終わった順番は`options.jobs`などで変わるので、すべて集めてから`LawList::extend_ordered`で加える。
+/// 更新日時と大きさを読んだファイルについては、条件に合うかどうかによらず次回の実行のための記録を作る
+pub async fn collect_law_list(
+  mut rx: Receiver<ParsedFile>,
+  options: &ListupOptions,
+) -> (LawList, ListupState) {
+  let mut law_list = LawList::default();
+  let mut state = ListupState::new(options);
+  let mut infos = Vec::new();
+  while let Some(ParsedFile { file, info }) = rx.recv().await {
+    if let Some(stamp) = file.stamp {
+      state.insert(file.file_path, stamp, info.clone());
+    }
+    if options.accepts(&info) {
+      infos.push(info);
+    }
+  }
+  law_list.extend_ordered(infos, options);
+  (law_list, state)
+}
@@ -0,0 +1,345 @@
+//! 出力する法令の情報
+
+use crate::article::ArticleEntry;
+use crate::calendar::{cmp_dates, date_sort_key, CalendarSystem};
+use crate::consistency::DanglingPatch;
+use crate::status::LawStatus;
+use jplaw_data_types::{
+  law::{Date, LawId, LawPatchInfo},
+  listup::LawInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// 出力の形式の版
+///
+/// 出力の項目の名前や意味を変えたときに上げる
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// 法令の一覧を書き出したときの情報
+///
+/// JSON形式の出力では、法令の一覧（`laws`）と同じオブジェクトに並べて書く
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputHeader {
+  /// 出力の形式の版（`SCHEMA_VERSION`）
+  pub schema_version: u32,
+  /// 一覧を作成した日時（RFC 3339形式）
+  pub generated_at: String,
+  /// 一覧を作成したこのソフトウェアの版
+  pub tool_version: String,
+  /// 読み込んだ作業ディレクトリやZIPファイル、法令の一覧のファイルへのpath
+  pub input: Vec<String>,
+  /// 法令の一覧を作るときに見つかった、元データの欠けを疑わせるもの
+  #[serde(default, skip_serializing_if = "OutputWarnings::is_empty")]
+  pub warnings: OutputWarnings,
+}
+
+/// 法令の一覧を作るときに見つかった、元データの欠けを疑わせるもの
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputWarnings {
+  /// 存在しない法令を指している改正法令の法令ID
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub dangling_patches: Vec<DanglingPatch>,
+}
+
+impl OutputWarnings {
+  pub fn is_empty(&self) -> bool {
+    self.dangling_patches.is_empty()
+  }
+}
+
+#[cfg(feature = "full")]
+impl OutputHeader {
+  /// 今の日時とこのソフトウェアの版で作る
+  pub fn new(input: Vec<String>) -> Self {
+    OutputHeader {
+      schema_version: SCHEMA_VERSION,
+      generated_at: chrono::Utc::now().to_rfc3339(),
+      tool_version: env!("CARGO_PKG_VERSION").to_string(),
+      input,
+      warnings: OutputWarnings::default(),
+    }
+  }
+}
+
+/// 書き出したときの情報とともに法令の一覧を持つ、JSON形式の出力
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawListEnvelope {
+  #[serde(flatten)]
+  pub header: OutputHeader,
+  /// 法令の一覧
+  pub laws: Vec<LawRecord>,
+}
+
+/// JSON形式の法令の一覧を読み込む
+///
+/// 書き出したときの情報を持つ形式と、`--legacy-format`で書き出した法令の配列だけの形式の両方を読める。
+/// 書き出したときの情報がある場合は、`schema_version`がこの版より新しいとエラーにする
+pub fn laws_from_json(s: &str) -> anyhow::Result<Vec<LawRecord>> {
+  if s.trim_start().starts_with('[') {
+    return Ok(serde_json::from_str(s)?);
+  }
+  let envelope: LawListEnvelope = serde_json::from_str(s)?;
+  check_schema_version(envelope.header.schema_version)?;
+  Ok(envelope.laws)
+}
+
+/// 読み込んだ出力の形式の版が、このソフトウェアで読めるものかを調べる
+pub fn check_schema_version(version: u32) -> anyhow::Result<()> {
+  if version > SCHEMA_VERSION {
+    return Err(anyhow::anyhow!(
+      "unsupported schema version: {version} (this version of listup_law reads up to {SCHEMA_VERSION})"
+    ));
+  }
+  Ok(())
+}
+
+/// 改正ごとの情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchRecord {
+  #[serde(flatten)]
+  pub info: LawPatchInfo,
+  /// この改正を反映したXMLファイルへのpath
+  #[serde(default)]
+  pub file: String,
+  /// XMLファイルを見つけた作業ディレクトリ（作業ディレクトリを複数指定した場合のみ）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub source: Option<String>,
+  /// 改正法令の法令名（`resolve_patch_laws`で改正法令の法令IDから解決できた場合のみ）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub patch_name: Option<String>,
+  /// 改正法令の法令番号（`resolve_patch_laws`で改正法令の法令IDから解決できた場合のみ）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub patch_num: Option<String>,
+  /// このXMLファイルの本則の条（`ListupOptions::articles`を指定した場合のみ読み取る）
+  ///
+  /// 法令ごとの出力には含めない
+  #[serde(skip)]
+  pub articles: Vec<ArticleEntry>,
+  /// XMLファイルの大きさとハッシュ値など（`ListupOptions::snapshots`を指定した場合のみ読み取る）
+  ///
+  /// 法令ごとの出力には含めない
+  #[serde(skip)]
+  pub digest: Option<FileDigest>,
+}
+
+/// XMLファイルを解析するときに求めた、大きさとハッシュ値などの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDigest {
+  /// XMLファイルの大きさ（バイト数）
+  pub size: u64,
+  /// XMLファイルの中身のSHA-256ハッシュ値（16進数）
+  pub sha256: String,
+  /// この版の日付に、最後の附則の改正が施行済みだったかどうか
+  pub in_force_at_snapshot: Option<bool>,
+}
+
+/// 出力する法令の情報
+///
+/// `LawInfo`と同じ項目に、このcrateで追加した項目を加えたもの
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawRecord {
+  /// 法令ID
+  pub id: LawId,
+  /// 法令名
+  pub name: String,
+  /// 法令名の読み（`LawTitle`タグの`Kana`属性）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub name_kana: Option<String>,
+  /// 法令の略称（`LawTitle`タグの`Abbrev`属性と`--abbrev-csv`で指定したもの）
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub abbrev: Vec<String>,
+  /// 法令番号
+  pub num: String,
+  /// 法令番号から読み取った、その命令を出した府省など（省令・府令・規則の場合のみ）
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub ministries: Vec<String>,
+  /// 公布の日付
+  pub date: Date,
+  /// 公布の年月日
+  ///
+  /// `Law`タグに`PromulgateMonth`属性がある場合のみ。
+  /// `date`は年までしか持たないため、月日まで必要な場合はこちらを使う
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub promulgation_date: Option<Date>,
+  /// 公布の年月日の月日が従う暦（太陰太陽暦の場合のみ出力する）
+  ///
+  /// 明治5年までに公布された法令の月日は太陰太陽暦のもので、グレゴリオ暦に換算していない
+  #[serde(default, skip_serializing_if = "CalendarSystem::is_gregorian")]
+  pub calendar: CalendarSystem,
+  /// 附則から読み取った施行日（古い順）
+  ///
+  /// 最新のXMLファイルに含まれるすべての附則から読み取る
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub enforcement_dates: Vec<Date>,
+  /// 一覧を作った日の時点での効力の状態
+  #[serde(default)]
+  pub status: LawStatus,
+  /// 題名改正で変わった法令名の移り変わり（古い順）
+  ///
+  /// 法令名ごとに、その法令名が使われた最初の版の改正の日付と組にする。
+  /// 一度も題名改正されていない場合は空にする
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub historical_names: Vec<(Date, String)>,
+  /// 改正の一覧
+  pub patch: Vec<PatchRecord>,
+}
+
+impl PatchRecord {
+  /// 改正の一覧の並び順（改正の日付、同じ日付の場合は改正法令の法令IDの順）
+  pub fn order(&self, other: &Self) -> Ordering {
+    cmp_dates(&self.info.patch_date, &other.info.patch_date).then_with(|| {
+      let id = |p: &PatchRecord| p.info.patch_id.as_ref().map(|id| id.to_string());
+      id(self).cmp(&id(other))
+    })
+  }
+}
+
+impl LawRecord {
+  /// 改正の一覧を`PatchRecord::order`の順に並べ替える
+  pub fn sort_patches(&mut self) {
+    self.patch.sort_by(PatchRecord::order);
+  }
+
+  /// 改正の一覧を、`PatchRecord::order`の順で最も新しいものだけにする
+  pub fn retain_latest_patch(&mut self) {
+    self.sort_patches();
+    let len = self.patch.len();
+    self.patch.drain(..len.saturating_sub(1));
+  }
+}
+
+/// 改正法令の法令IDから、その法令名と法令番号を`PatchRecord`に書き込む
+///
+/// `laws`に含まれる法令を先に探し、含まれない場合は`extra`（法令IDから法令名と法令番号への対応、
+/// all_law_list.csvから読んだものなど）から探す。どちらにも無い場合は`None`のままにする
+pub fn resolve_patch_laws(laws: &mut [LawRecord], extra: &HashMap<String, (String, String)>) {
+  let known = laws
+    .iter()
+    .map(|law| (law.id.to_string(), (law.name.clone(), law.num.clone())))
+    .collect::<HashMap<_, _>>();
+  for law in laws.iter_mut() {
+    for patch in law.patch.iter_mut() {
+      let Some(patch_id) = &patch.info.patch_id else {
+        continue;
+      };
+      let patch_id = patch_id.to_string();
+      if let Some((name, num)) = known.get(&patch_id).or_else(|| extra.get(&patch_id)) {
+        patch.patch_name = Some(name.clone());
+        patch.patch_num = Some(num.clone());
+      }
+    }
+  }
+}
+
+/// 法令の一覧の並べ方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+  /// 法令IDの順
+  #[default]
+  Id,
+  /// 公布の日付の順
+  Date,
+  /// 法令名の順
+  Name,
+}
+
+impl FromStr for SortKey {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> anyhow::Result<Self> {
+    match s {
+      "id" => Ok(SortKey::Id),
+      "date" => Ok(SortKey::Date),
+      "name" => Ok(SortKey::Name),
+      _ => Err(anyhow::anyhow!("unknown sort key: {s}")),
+    }
+  }
+}
+
+/// 法令の一覧を`key`の順に並べ、それぞれの改正の一覧も並べ替える
+///
+/// 法令IDの順以外の場合も、同じ値の法令は法令IDの順に並べるので、実行ごとに順番が変わらない
+pub fn sort_laws(laws: &mut [LawRecord], key: SortKey) {
+  for law in laws.iter_mut() {
+    law.sort_patches();
+  }
+  let id = |law: &LawRecord| law.id.to_string();
+  match key {
+    SortKey::Id => laws.sort_by_cached_key(id),
+    SortKey::Date => laws.sort_by_cached_key(|law| {
+      (
+        date_sort_key(law.promulgation_date.as_ref().unwrap_or(&law.date)),
+        id(law),
+      )
+    }),
+    SortKey::Name => laws.sort_by_cached_key(|law| (law.name.clone(), id(law))),
+  }
+}
+
+impl From<LawRecord> for LawInfo {
+  fn from(record: LawRecord) -> Self {
+    LawInfo {
+      date: record.date,
+      name: record.name,
+      num: record.num,
+      id: record.id,
+      patch: record.patch.into_iter().map(|p| p.info).collect(),
+    }
+  }
+}
+
+/// 単体テストで使う、手で組み立てた法令の情報
+#[cfg(test)]
+pub(crate) mod testing {
+  use super::*;
+  use crate::calendar::era_from_attr;
+
+  fn law_id(id: &str) -> LawId {
+    LawId::from_str(id).unwrap_or_else(|_| panic!("invalid law id: {id}"))
+  }
+
+  /// "Heisei"のような`Era`属性での元号の表記と年月日から日付を作る
+  pub fn date(era: &str, year: usize, month: usize, day: usize) -> Date {
+    Date::new(era_from_attr(era).unwrap(), year, Some(month), Some(day))
+  }
+
+  /// 法令IDの法令の、`patch_date`の版
+  pub fn patch(id: &str, patch_date: Date, patch_id: Option<&str>) -> PatchRecord {
+    PatchRecord {
+      info: LawPatchInfo {
+        id: law_id(id),
+        patch_date,
+        patch_id: patch_id.map(law_id),
+      },
+      file: format!("{id}/{id}.xml"),
+      source: None,
+      patch_name: None,
+      patch_num: None,
+      articles: Vec::new(),
+      digest: None,
+    }
+  }
+
+  /// 法令IDと法令名だけを決めた、平成15年5月30日に公布された法律
+  ///
+  /// 改正は公布の日の版1つだけとする
+  pub fn law(id: &str, name: &str) -> LawRecord {
+    LawRecord {
+      id: law_id(id),
+      name: name.to_string(),
+      name_kana: None,
+      abbrev: Vec::new(),
+      num: "平成十五年法律第五十七号".to_string(),
+      ministries: Vec::new(),
+      date: Date::new(era_from_attr("Heisei").unwrap(), 15, None, None),
+      promulgation_date: Some(date("Heisei", 15, 5, 30)),
+      calendar: CalendarSystem::Gregorian,
+      enforcement_dates: Vec::new(),
+      status: LawStatus::InForce,
+      historical_names: Vec::new(),
+      patch: vec![patch(id, date("Heisei", 15, 5, 30), None)],
+    }
+  }
+}
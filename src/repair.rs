@@ -0,0 +1,176 @@
+//! 法令XMLファイルのメタデータのうち、法令番号の文字列などから復元できる不備について修正候補を作る
+
+use crate::calendar::{era_from_kanji, EraExt, WAREKI_DATE_PATTERN};
+use crate::kansuji::parse_kansuji;
+use japanese_law_xml_schema::law::{Era, Law};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// XMLファイルのメタデータに対する修正候補
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixSuggestion {
+  /// 法令ID
+  pub id: String,
+  /// 修正候補が見つかったXMLファイルへのpath
+  pub file: String,
+  /// 修正対象となる`Law`タグの属性名
+  pub field: String,
+  /// 現在の値（属性が無い場合は`None`）
+  pub current: Option<String>,
+  /// 修正後の値の候補
+  pub suggested: String,
+  /// 修正候補の根拠となった文字列
+  pub evidence: String,
+}
+
+/// 法令番号の先頭に書かれている公布の日付
+pub(crate) struct LawNumDate {
+  pub(crate) era: Era,
+  pub(crate) year: usize,
+  pub(crate) month: Option<usize>,
+  pub(crate) day: Option<usize>,
+}
+
+pub(crate) fn parse_law_num_date(law_num: &str) -> Option<LawNumDate> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(&format!("^{WAREKI_DATE_PATTERN}")).unwrap());
+  let caps = re.captures(law_num.trim())?;
+  Some(LawNumDate {
+    era: era_from_kanji(&caps["era"])?,
+    year: parse_kansuji(&caps["year"])?,
+    month: caps.name("month").and_then(|m| parse_kansuji(m.as_str())),
+    day: caps.name("day").and_then(|m| parse_kansuji(m.as_str())),
+  })
+}
+
+/// 法令番号の文字列と`Law`タグの属性を突き合わせ、食い違っている・欠けている属性の修正候補を返す
+pub fn suggest_fixes(law: &Law, id: &str, file: &str) -> Vec<FixSuggestion> {
+  let mut fixes = Vec::new();
+  let Some(num_date) = parse_law_num_date(&law.law_num) else {
+    return fixes;
+  };
+  let mut push = |field: &str, current: Option<String>, suggested: String| {
+    fixes.push(FixSuggestion {
+      id: id.to_string(),
+      file: file.to_string(),
+      field: field.to_string(),
+      current,
+      suggested,
+      evidence: law.law_num.clone(),
+    })
+  };
+  if law.era.attr() != num_date.era.attr() {
+    push(
+      "Era",
+      Some(law.era.attr().to_string()),
+      num_date.era.attr().to_string(),
+    );
+  }
+  if law.year != num_date.year {
+    push(
+      "Year",
+      Some(law.year.to_string()),
+      num_date.year.to_string(),
+    );
+  }
+  if let (None, Some(month)) = (law.promulgate_month, num_date.month) {
+    push("PromulgateMonth", None, month.to_string());
+  }
+  if let (None, Some(day)) = (law.promulgate_day, num_date.day) {
+    push("PromulgateDay", None, day.to_string());
+  }
+  fixes
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const FILE: &str = "415AC0000000057_20030530_000000000000000.xml";
+
+  /// `Law`タグの属性と法令番号だけを変えた法令XML
+  fn parse(attrs: &str, num: &str) -> Law {
+    let xml = format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<Law {attrs} Num="57" LawType="Act" Lang="ja">
+  <LawNum>{num}</LawNum>
+  <LawBody>
+    <LawTitle>個人情報の保護に関する法律</LawTitle>
+    <MainProvision>
+      <Paragraph Num="1">
+        <ParagraphNum/>
+        <ParagraphSentence>
+          <Sentence Num="1">この法律は、テストのためのものである。</Sentence>
+        </ParagraphSentence>
+      </Paragraph>
+    </MainProvision>
+  </LawBody>
+</Law>
+"#
+    );
+    japanese_law_xml_schema::parse_xml(xml.as_bytes()).unwrap()
+  }
+
+  fn fields(fixes: &[FixSuggestion]) -> Vec<(&str, Option<&str>, &str)> {
+    fixes
+      .iter()
+      .map(|fix| {
+        (
+          fix.field.as_str(),
+          fix.current.as_deref(),
+          fix.suggested.as_str(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn suggests_era_and_year_from_the_law_number() {
+    let law = parse(
+      r#"Era="Showa" Year="15" PromulgateMonth="05" PromulgateDay="30""#,
+      "平成十五年法律第五十七号",
+    );
+    let fixes = suggest_fixes(&law, PRIVACY_ACT_ID, FILE);
+    assert_eq!(fields(&fixes), [("Era", Some("Showa"), "Heisei")]);
+    assert_eq!(fixes[0].id, PRIVACY_ACT_ID);
+    assert_eq!(fixes[0].file, FILE);
+    assert_eq!(fixes[0].evidence, "平成十五年法律第五十七号");
+
+    let law = parse(
+      r#"Era="Heisei" Year="16" PromulgateMonth="05" PromulgateDay="30""#,
+      "平成十五年法律第五十七号",
+    );
+    assert_eq!(
+      fields(&suggest_fixes(&law, PRIVACY_ACT_ID, FILE)),
+      [("Year", Some("16"), "15")]
+    );
+  }
+
+  #[test]
+  fn suggests_nothing_for_unreadable_law_numbers() {
+    let law = parse(r#"Era="Showa" Year="21""#, "日本国憲法");
+    assert!(suggest_fixes(&law, "321CONSTITUTION", FILE).is_empty());
+    assert!(parse_law_num_date("日本国憲法").is_none());
+    assert!(parse_law_num_date("未来十五年法律第五十七号").is_none());
+    assert!(parse_law_num_date("").is_none());
+  }
+
+  #[test]
+  fn suggests_nothing_for_consistent_metadata() {
+    let law = parse(
+      r#"Era="Heisei" Year="15" PromulgateMonth="05" PromulgateDay="30""#,
+      "平成十五年法律第五十七号",
+    );
+    assert!(suggest_fixes(&law, PRIVACY_ACT_ID, FILE).is_empty());
+
+    // 法令番号に月日が無い場合は、月日の属性が無くても修正候補にしない
+    let law = parse(r#"Era="Heisei" Year="15""#, "平成十五年法律第五十七号");
+    assert!(suggest_fixes(&law, PRIVACY_ACT_ID, FILE).is_empty());
+
+    let date = parse_law_num_date(" 平成元年法律第一号").unwrap();
+    assert_eq!((date.era.attr(), date.year), ("Heisei", 1));
+    assert_eq!((date.month, date.day), (None, None));
+  }
+}
@@ -0,0 +1,181 @@
+//! 出力の形式を表すJSON Schema
+//!
+//! 他の言語で出力を読む場合に、型を生成したりファイルを検証したりするのに使う。
+//! `LawRecord`などの項目を変えたときは、ここも合わせて変える
+
+use crate::record::SCHEMA_VERSION;
+use serde_json::{json, Value};
+
+/// 法令IDのschema
+fn law_id_schema() -> Value {
+  json!({
+    "description": "法令ID（\"415AC0000000057\"など）",
+    "type": "string"
+  })
+}
+
+/// `Date`のschema
+fn date_schema() -> Value {
+  json!({
+    "description": "元号と年、分かる場合は月日",
+    "type": "object",
+    "properties": {
+      "era": {
+        "type": "string",
+        "enum": ["Meiji", "Taisho", "Showa", "Heisei", "Reiwa"]
+      },
+      "year": { "description": "元号での年", "type": "integer", "minimum": 1 },
+      "month": { "type": ["integer", "null"], "minimum": 1, "maximum": 12 },
+      "day": { "type": ["integer", "null"], "minimum": 1, "maximum": 31 }
+    },
+    "required": ["era", "year"]
+  })
+}
+
+/// `LawPatchInfo`に`file`などを加えた、改正ごとの情報のschema
+fn patch_record_schema() -> Value {
+  json!({
+    "description": "改正ごとの情報",
+    "type": "object",
+    "properties": {
+      "id": { "$ref": "#/$defs/LawId" },
+      "patch_date": { "$ref": "#/$defs/Date" },
+      "patch_id": {
+        "description": "改正法令の法令ID（改正されていない版の場合は`null`）",
+        "oneOf": [{ "$ref": "#/$defs/LawId" }, { "type": "null" }]
+      },
+      "file": { "description": "この改正を反映したXMLファイルへのpath", "type": "string" },
+      "patch_name": { "description": "改正法令の法令名（解決できた場合のみ）", "type": "string" },
+      "patch_num": { "description": "改正法令の法令番号（解決できた場合のみ）", "type": "string" },
+      "source": {
+        "description": "XMLファイルを見つけた作業ディレクトリ（作業ディレクトリを複数指定した場合のみ）",
+        "type": "string"
+      }
+    },
+    "required": ["id", "patch_date"]
+  })
+}
+
+/// `LawRecord`のschema
+fn law_record_schema() -> Value {
+  json!({
+    "description": "法令ごとの情報",
+    "type": "object",
+    "properties": {
+      "id": { "$ref": "#/$defs/LawId" },
+      "name": { "description": "法令名", "type": "string" },
+      "name_kana": { "description": "法令名の読み", "type": "string" },
+      "abbrev": { "description": "法令の略称", "type": "array", "items": { "type": "string" } },
+      "num": { "description": "法令番号", "type": "string" },
+      "ministries": {
+        "description": "法令番号から読み取った、その命令を出した府省など",
+        "type": "array",
+        "items": { "type": "string" }
+      },
+      "date": { "$ref": "#/$defs/Date" },
+      "promulgation_date": { "$ref": "#/$defs/Date" },
+      "calendar": {
+        "description": "公布の年月日の月日が従う暦（太陰太陽暦の場合のみ）",
+        "type": "string",
+        "enum": ["gregorian", "lunisolar"]
+      },
+      "enforcement_dates": {
+        "description": "附則から読み取った施行日（古い順）",
+        "type": "array",
+        "items": { "$ref": "#/$defs/Date" }
+      },
+      "status": {
+        "description": "一覧を作った日の時点での効力の状態",
+        "type": "string",
+        "enum": ["InForce", "Repealed", "NotYetInForce"]
+      },
+      "historical_names": {
+        "description": "題名改正で変わった法令名の移り変わり（古い順、その法令名が使われた最初の版の改正の日付との組）",
+        "type": "array",
+        "items": {
+          "type": "array",
+          "prefixItems": [{ "$ref": "#/$defs/Date" }, { "type": "string" }],
+          "minItems": 2,
+          "maxItems": 2
+        }
+      },
+      "patch": {
+        "description": "改正の一覧（改正の日付の順）",
+        "type": "array",
+        "items": { "$ref": "#/$defs/PatchRecord" }
+      }
+    },
+    "required": ["id", "name", "num", "date", "patch"]
+  })
+}
+
+/// JSON形式の出力のschema
+///
+/// `legacy`が`true`の場合は、`--legacy-format`で書き出す法令の情報の配列だけの形のものを返す
+pub fn output_schema(legacy: bool) -> Value {
+  let defs = json!({
+    "LawId": law_id_schema(),
+    "Date": date_schema(),
+    "PatchRecord": patch_record_schema(),
+    "LawRecord": law_record_schema(),
+  });
+  let laws = json!({
+    "type": "array",
+    "items": { "$ref": "#/$defs/LawRecord" }
+  });
+  let mut schema = if legacy {
+    laws
+  } else {
+    json!({
+      "type": "object",
+      "properties": {
+        "schema_version": { "type": "integer", "const": SCHEMA_VERSION },
+        "generated_at": {
+          "description": "一覧を作成した日時（RFC 3339形式）",
+          "type": "string",
+          "format": "date-time"
+        },
+        "tool_version": { "description": "一覧を作成したlistup_lawの版", "type": "string" },
+        "input": {
+          "description": "読み込んだ作業ディレクトリなどへのpath",
+          "type": "array",
+          "items": { "type": "string" }
+        },
+        "warnings": {
+          "description": "法令の一覧を作るときに見つかった、元データの欠けを疑わせるもの",
+          "type": "object",
+          "properties": {
+            "dangling_patches": {
+              "description": "読み込んだ法令にも法令の一覧にも無い法令を指している改正",
+              "type": "array",
+              "items": {
+                "type": "object",
+                "properties": {
+                  "id": { "$ref": "#/$defs/LawId" },
+                  "file": { "type": "string" },
+                  "patch_id": { "$ref": "#/$defs/LawId" }
+                },
+                "required": ["id", "file", "patch_id"]
+              }
+            }
+          }
+        },
+        "laws": laws
+      },
+      "required": ["schema_version", "generated_at", "tool_version", "input", "laws"]
+    })
+  };
+  let obj = schema.as_object_mut().expect("schema is an object");
+  obj.insert(
+    "$schema".to_string(),
+    json!("https://json-schema.org/draft/2020-12/schema"),
+  );
+  obj.insert(
+    "title".to_string(),
+    json!(format!(
+      "listup_law output (schema version {SCHEMA_VERSION})"
+    )),
+  );
+  obj.insert("$defs".to_string(), defs);
+  schema
+}
@@ -0,0 +1,204 @@
+//! 書き出した法令の一覧から条件に合う法令を探す
+
+use crate::calendar::{date_to_ad, to_iso8601};
+use crate::record::LawRecord;
+use crate::stats::law_type_of_num;
+use serde::Serialize;
+
+/// 法令を探す条件
+///
+/// 指定しなかった条件では絞り込まない
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+  /// 法令名・法令名の読み・略称のいずれかにこの文字列を含む
+  pub name: Option<String>,
+  /// 法令番号から読み取った法令の種類（"法律"・"政令"など）
+  pub law_type: Option<String>,
+  /// 公布された西暦の年がこの年以降
+  pub since: Option<usize>,
+  /// 公布された西暦の年がこの年以前
+  pub until: Option<usize>,
+}
+
+impl SearchQuery {
+  /// 法令が条件に合うかどうか
+  pub fn matches(&self, law: &LawRecord) -> bool {
+    if let Some(name) = &self.name {
+      let found = law.name.contains(name.as_str())
+        || matches!(&law.name_kana, Some(kana) if kana.contains(name.as_str()))
+        || law
+          .abbrev
+          .iter()
+          .any(|abbrev| abbrev.contains(name.as_str()));
+      if !found {
+        return false;
+      }
+    }
+    if let Some(law_type) = &self.law_type {
+      if law_type_of_num(&law.num) != Some(law_type.as_str()) {
+        return false;
+      }
+    }
+    let year = date_to_ad(law.promulgation_date.as_ref().unwrap_or(&law.date)).year;
+    !(matches!(self.since, Some(since) if year < since)
+      || matches!(self.until, Some(until) if until < year))
+  }
+}
+
+/// 検索で見つかった法令1つ分の表示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+  /// 法令ID
+  pub id: String,
+  /// 公布の日付（"2003-05-30"の形、月日が分からない場合は年のみ）
+  pub date: String,
+  /// 法令番号
+  pub num: String,
+  /// 法令名
+  pub name: String,
+}
+
+impl SearchHit {
+  fn new(law: &LawRecord) -> Self {
+    SearchHit {
+      id: law.id.to_string(),
+      date: to_iso8601(law.promulgation_date.as_ref().unwrap_or(&law.date)),
+      num: law.num.clone(),
+      name: law.name.clone(),
+    }
+  }
+}
+
+impl std::fmt::Display for SearchHit {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}\t{}\t{}\t{}", self.id, self.date, self.num, self.name)
+  }
+}
+
+/// 条件に合う法令を、一覧の順のまま探す
+///
+/// 複数の一覧から探す場合は、`LawIndexSet::laws`で同じ法令IDの法令を優先する一覧のものだけにしてから渡す
+pub fn search_laws<'a>(
+  laws: impl IntoIterator<Item = &'a LawRecord>,
+  query: &SearchQuery,
+) -> Vec<SearchHit> {
+  laws
+    .into_iter()
+    .filter(|law| query.matches(law))
+    .map(SearchHit::new)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::calendar::era_from_attr;
+  use crate::record::testing::{date, law};
+  use jplaw_data_types::law::Date;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const PRIVACY_ORDER_ID: &str = "415CO0000000507";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn laws() -> Vec<LawRecord> {
+    let mut privacy = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    privacy.abbrev = vec!["個人情報保護法".to_string()];
+    let mut order = law(PRIVACY_ORDER_ID, "個人情報の保護に関する法律施行令");
+    order.num = "平成十五年政令第五百七号".to_string();
+    order.promulgation_date = Some(date("Heisei", 15, 12, 10));
+    let mut civil = law(CIVIL_CODE_ID, "民法");
+    civil.name_kana = Some("みんぽう".to_string());
+    civil.num = "明治二十九年法律第八十九号".to_string();
+    civil.date = Date::new(era_from_attr("Meiji").unwrap(), 29, None, None);
+    civil.promulgation_date = Some(date("Meiji", 29, 4, 27));
+    vec![privacy, order, civil]
+  }
+
+  fn ids(hits: &[SearchHit]) -> Vec<&str> {
+    hits.iter().map(|hit| hit.id.as_str()).collect()
+  }
+
+  #[test]
+  fn searches_by_name_type_and_year() {
+    let laws = laws();
+    let query = SearchQuery {
+      name: Some("個人情報".to_string()),
+      law_type: Some("法律".to_string()),
+      since: Some(2000),
+      ..Default::default()
+    };
+    let hits = search_laws(&laws, &query);
+    assert_eq!(ids(&hits), [PRIVACY_ACT_ID]);
+    assert_eq!(
+      hits[0].to_string(),
+      "415AC0000000057\t2003-05-30\t平成十五年法律第五十七号\t個人情報の保護に関する法律"
+    );
+
+    let query = SearchQuery {
+      law_type: Some("法律".to_string()),
+      until: Some(1900),
+      ..Default::default()
+    };
+    assert_eq!(ids(&search_laws(&laws, &query)), [CIVIL_CODE_ID]);
+
+    // 年の範囲は両端を含む
+    let query = SearchQuery {
+      since: Some(2003),
+      until: Some(2003),
+      ..Default::default()
+    };
+    assert_eq!(
+      ids(&search_laws(&laws, &query)),
+      [PRIVACY_ACT_ID, PRIVACY_ORDER_ID]
+    );
+    assert_eq!(search_laws(&laws, &SearchQuery::default()).len(), 3);
+  }
+
+  #[test]
+  fn searches_abbreviations_and_readings() {
+    let laws = laws();
+    let by_name = |name: &str| {
+      let query = SearchQuery {
+        name: Some(name.to_string()),
+        ..Default::default()
+      };
+      search_laws(&laws, &query)
+    };
+    assert_eq!(ids(&by_name("保護法")), [PRIVACY_ACT_ID]);
+    assert_eq!(ids(&by_name("みんぽう")), [CIVIL_CODE_ID]);
+    assert!(by_name("刑法").is_empty());
+  }
+
+  #[test]
+  fn uses_the_year_when_month_and_day_are_unknown() {
+    let mut laws = laws();
+    laws[2].promulgation_date = None;
+    let query = SearchQuery {
+      name: Some("民法".to_string()),
+      since: Some(1896),
+      until: Some(1896),
+      ..Default::default()
+    };
+    let hits = search_laws(&laws, &query);
+    assert_eq!(
+      hits[0].to_string(),
+      "129AC0000000089\t1896\t明治二十九年法律第八十九号\t民法"
+    );
+    let json = serde_json::to_value(&hits[0]).unwrap();
+    assert_eq!(json["date"], "1896");
+
+    // 法令番号から種類が読み取れない法令は、種類を指定した検索では見つからない
+    laws[0].num = "番号なし".to_string();
+    let query = SearchQuery {
+      name: Some("個人情報の保護に関する法律".to_string()),
+      law_type: Some("法律".to_string()),
+      ..Default::default()
+    };
+    assert!(search_laws(&laws, &query).is_empty());
+    let query = SearchQuery {
+      law_type: Some("条例".to_string()),
+      ..Default::default()
+    };
+    assert!(search_laws(&laws, &query).is_empty());
+  }
+}
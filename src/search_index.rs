@@ -0,0 +1,89 @@
+//! 法令の一覧からtantivyの全文検索用のインデックスを作る
+//!
+//! 日本語の法令名は空白で区切られていないため、1文字と2文字のN-gramに分けてインデックスにする。
+//! インデックスを開いて検索する側でも、`register_tokenizers`で同じトークナイザーを登録する必要がある
+
+use crate::record::LawRecord;
+use anyhow::Result;
+use std::path::Path;
+use tantivy::schema::{
+  Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, STORED, STRING,
+};
+use tantivy::tokenizer::{LowerCaser, NgramTokenizer, TextAnalyzer};
+use tantivy::{Index, IndexWriter, TantivyDocument};
+
+/// 法令名などのフィールドに使うトークナイザーの名前
+pub const NGRAM_TOKENIZER: &str = "listup_law_ngram";
+
+/// インデックスのフィールド
+#[derive(Debug, Clone, Copy)]
+pub struct LawIndexFields {
+  /// 法令ID（分割せずにそのまま）
+  pub id: Field,
+  /// 法令名（題名改正の前の法令名も含む）
+  pub name: Field,
+  /// 法令名の読み
+  pub name_kana: Field,
+  /// 法令の略称
+  pub abbrev: Field,
+  /// 法令番号
+  pub num: Field,
+}
+
+/// インデックスのschemaとそのフィールド
+pub fn law_index_schema() -> (Schema, LawIndexFields) {
+  let mut builder = Schema::builder();
+  let text = TextOptions::default()
+    .set_indexing_options(
+      TextFieldIndexing::default()
+        .set_tokenizer(NGRAM_TOKENIZER)
+        .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    )
+    .set_stored();
+  let fields = LawIndexFields {
+    id: builder.add_text_field("id", STRING | STORED),
+    name: builder.add_text_field("name", text.clone()),
+    name_kana: builder.add_text_field("name_kana", text.clone()),
+    abbrev: builder.add_text_field("abbrev", text.clone()),
+    num: builder.add_text_field("num", text),
+  };
+  (builder.build(), fields)
+}
+
+/// 法令名などのフィールドに使うトークナイザーを登録する
+pub fn register_tokenizers(index: &Index) -> Result<()> {
+  let tokenizer = TextAnalyzer::builder(NgramTokenizer::new(1, 2, false)?)
+    .filter(LowerCaser)
+    .build();
+  index.tokenizers().register(NGRAM_TOKENIZER, tokenizer);
+  Ok(())
+}
+
+/// 法令の一覧から、ディレクトリ`dir`にインデックスを作る
+///
+/// `dir`に既にインデックスがある場合はエラーにする。`memory_budget`はインデックスを書くときに使うメモリの量（バイト）
+pub fn build_law_index(laws: &[LawRecord], dir: &Path, memory_budget: usize) -> Result<Index> {
+  let (schema, fields) = law_index_schema();
+  std::fs::create_dir_all(dir)?;
+  let index = Index::create_in_dir(dir, schema)?;
+  register_tokenizers(&index)?;
+  let mut writer: IndexWriter = index.writer(memory_budget)?;
+  for law in laws {
+    let mut doc = TantivyDocument::default();
+    doc.add_text(fields.id, law.id.to_string());
+    doc.add_text(fields.name, &law.name);
+    for (_, name) in law.historical_names.iter().filter(|(_, n)| *n != law.name) {
+      doc.add_text(fields.name, name);
+    }
+    if let Some(kana) = &law.name_kana {
+      doc.add_text(fields.name_kana, kana);
+    }
+    for abbrev in &law.abbrev {
+      doc.add_text(fields.abbrev, abbrev);
+    }
+    doc.add_text(fields.num, &law.num);
+    writer.add_document(doc)?;
+  }
+  writer.commit()?;
+  Ok(index)
+}
@@ -0,0 +1,98 @@
+//! 複数のマシンで分担して処理するために、法令IDから担当する分割単位を決める
+
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// `--shard 3/8`のように指定される分割単位
+///
+/// 番号は1から数える。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+  pub index: usize,
+  pub count: usize,
+}
+
+impl FromStr for Shard {
+  type Err = anyhow::Error;
+  fn from_str(s: &str) -> Result<Self> {
+    let (index, count) = s
+      .split_once('/')
+      .ok_or(anyhow!("shard must be written as K/N: {s}"))?;
+    let index = index.trim().parse::<usize>()?;
+    let count = count.trim().parse::<usize>()?;
+    if count == 0 || index == 0 || count < index {
+      return Err(anyhow!("shard must satisfy 1 <= K <= N: {s}"));
+    }
+    Ok(Shard { index, count })
+  }
+}
+
+/// FNV-1aハッシュ
+///
+/// 実行環境やRustのバージョンによらず同じ値になるものを使う
+fn fnv1a(s: &str) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for b in s.bytes() {
+    hash ^= b as u64;
+    hash = hash.wrapping_mul(0x100000001b3);
+  }
+  hash
+}
+
+impl Shard {
+  /// 法令IDがこの分割単位の担当かどうか
+  pub fn contains(&self, law_id: &str) -> bool {
+    (fnv1a(law_id) % self.count as u64) as usize == self.index - 1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_shards() {
+    assert_eq!(
+      "3 / 8".parse::<Shard>().unwrap(),
+      Shard { index: 3, count: 8 }
+    );
+    // K = Nと、1つだけに分ける場合も受け付ける
+    assert_eq!(
+      "8/8".parse::<Shard>().unwrap(),
+      Shard { index: 8, count: 8 }
+    );
+    assert_eq!(
+      "1/1".parse::<Shard>().unwrap(),
+      Shard { index: 1, count: 1 }
+    );
+    for s in [
+      "0/8", "9/8", "1/0", "0/0", "3", "", "a/8", "3/b", "-1/8", "1/8/2",
+    ] {
+      assert!(s.parse::<Shard>().is_err(), "{s}");
+    }
+  }
+
+  #[test]
+  fn assigns_each_law_id_to_exactly_one_shard() {
+    let ids = [
+      "129AC0000000089",
+      "140AC0000000045",
+      "321CONSTITUTION",
+      "345AC0000000048",
+      "415AC0000000057",
+      "415CO0000000507",
+    ];
+    let shards = (1..=3)
+      .map(|index| Shard { index, count: 3 })
+      .collect::<Vec<_>>();
+    for id in ids {
+      assert_eq!(shards.iter().filter(|s| s.contains(id)).count(), 1, "{id}");
+    }
+    // 実行環境によらず同じ分け方になる
+    assert_eq!(fnv1a(""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a("a"), 0xaf63dc4c8601ec8c);
+    assert!(ids
+      .iter()
+      .all(|id| Shard { index: 1, count: 1 }.contains(id)));
+  }
+}
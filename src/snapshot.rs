@@ -0,0 +1,63 @@
+//! 法令ごとではなく、XMLファイル（改正ごとの版）ごとに1行とする平らな出力
+
+use crate::calendar::to_iso8601;
+use crate::enforcement::in_force_at;
+use crate::record::{FileDigest, LawRecord};
+use anyhow::{anyhow, Result};
+use jplaw_data_types::law::Date;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// XMLファイル1つの情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotRecord {
+  /// 法令ID
+  pub id: String,
+  /// 改正の日付（"YYYY-MM-DD"）
+  pub patch_date: String,
+  /// 改正法令の法令ID
+  pub patch_id: Option<String>,
+  /// XMLファイルへのpath
+  pub file: String,
+  /// XMLファイルの大きさ（バイト数）
+  pub size: u64,
+  /// XMLファイルの中身のSHA-256ハッシュ値（16進数）
+  pub sha256: String,
+  /// この版の日付に、最後の附則の改正が施行済みだったかどうか（附則から読み取れない場合は`None`）
+  pub in_force_at_snapshot: Option<bool>,
+}
+
+/// XMLファイルの中身と改正の日付から、大きさとハッシュ値などを求める
+pub fn file_digest(buf: &[u8], patch_date: &Date) -> FileDigest {
+  FileDigest {
+    size: buf.len() as u64,
+    sha256: format!("{:x}", Sha256::digest(buf)),
+    in_force_at_snapshot: in_force_at(&String::from_utf8_lossy(buf), patch_date),
+  }
+}
+
+/// 法令の一覧から、改正ごとのXMLファイルを1行とする一覧を作る
+///
+/// 大きさとハッシュ値は一覧を作るときに読み取ったもの（`ListupOptions::snapshots`を指定した場合のみ）を使う。
+/// ZIPファイルの中のXMLファイルも読み直さずに済むよう、ここではファイルを開かない
+pub fn snapshot_records(laws: &[LawRecord]) -> Result<Vec<SnapshotRecord>> {
+  let mut lst = Vec::new();
+  for law in laws {
+    for patch in &law.patch {
+      let digest = patch
+        .digest
+        .as_ref()
+        .ok_or_else(|| anyhow!("file digest was not read: {}", patch.file))?;
+      lst.push(SnapshotRecord {
+        id: law.id.to_string(),
+        patch_date: to_iso8601(&patch.info.patch_date),
+        patch_id: patch.info.patch_id.as_ref().map(|id| id.to_string()),
+        file: patch.file.clone(),
+        size: digest.size,
+        sha256: digest.sha256.clone(),
+        in_force_at_snapshot: digest.in_force_at_snapshot,
+      });
+    }
+  }
+  Ok(lst)
+}
@@ -0,0 +1,267 @@
+//! 全法令分の情報をメモリに載せずに法令の一覧を作る
+//!
+//! 解析したXMLファイルの情報は、一定の数がたまるごとに法令IDの先頭3文字（元号と年）ごとの
+//! 一時ファイルに書き出す。書き出しのときは一時ファイルを1つずつ読んで法令ごとにまとめるので、
+//! 同時にメモリに載るのは同じ元号と年の法令の分だけになる。
+
+use crate::consistency::{find_dangling_patches, DanglingPatch, EraInconsistency, LawConflict};
+use crate::error::FileError;
+use crate::listup::{LawFileInfo, LawList, ListupOptions};
+use crate::output::{JsonLawWriter, OutputFormat};
+use crate::pipeline::{spawn_parser, spawn_walkers, ParsedFile};
+use crate::record::{LawRecord, OutputHeader, PatchRecord, SortKey};
+use crate::repair::FixSuggestion;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+
+/// 一時ファイルに書き出した法令の一覧
+pub struct SpilledLawList {
+  /// 一時ファイルを置くディレクトリ
+  dir: PathBuf,
+  /// 書き出す前にメモリにためておくXMLファイルの数
+  cap: usize,
+  /// まだ書き出していないXMLファイルの情報
+  buffered: Vec<LawFileInfo>,
+  /// 一時ファイルの名前（法令IDの先頭3文字）
+  buckets: BTreeSet<String>,
+  /// 法令番号から復元できるメタデータの修正候補
+  pub fixes: Vec<FixSuggestion>,
+  /// 法令ID・法令番号・`Law`タグの属性の元号と年の食い違い
+  pub era_inconsistencies: Vec<EraInconsistency>,
+  /// 同じ法令IDのXMLファイルの間での法令名・法令番号の食い違い
+  ///
+  /// 同じ法令の情報は同じ一時ファイルに入るので、一時ファイルを読むときに調べる
+  pub conflicts: Vec<LawConflict>,
+  /// 読み込んだ法令の法令ID
+  ///
+  /// 一時ファイルを読む前に改正法令の有無を調べられるように、法令IDだけはメモリに持っておく
+  law_ids: HashSet<String>,
+  /// 改正法令の法令IDを持つ改正
+  patch_refs: Vec<PatchRecord>,
+  /// 読み込めずに飛ばしたXMLファイル
+  pub errors: Vec<FileError>,
+  /// `check`で食い違いなどを集め終えたかどうか
+  checked: bool,
+}
+
+impl SpilledLawList {
+  /// `dir`に一時ファイルを置き、`cap`個のXMLファイルの情報がたまるごとに書き出す
+  pub async fn new(dir: &Path, cap: usize) -> Result<Self> {
+    if cap == 0 {
+      return Err(anyhow!("memory cap must be at least 1"));
+    }
+    fs::create_dir_all(dir).await?;
+    Ok(SpilledLawList {
+      dir: dir.to_path_buf(),
+      cap,
+      buffered: Vec::new(),
+      buckets: BTreeSet::new(),
+      fixes: Vec::new(),
+      era_inconsistencies: Vec::new(),
+      conflicts: Vec::new(),
+      law_ids: HashSet::new(),
+      patch_refs: Vec::new(),
+      errors: Vec::new(),
+      checked: false,
+    })
+  }
+
+  fn bucket_path(&self, bucket: &str) -> PathBuf {
+    self.dir.join(format!("{bucket}.jsonl"))
+  }
+
+  /// XMLファイル1つ分の情報を加える
+  pub async fn push(&mut self, mut info: LawFileInfo) -> Result<()> {
+    self.fixes.append(&mut info.fixes);
+    self
+      .era_inconsistencies
+      .extend(info.era_inconsistency.take());
+    self.law_ids.insert(info.id.to_string());
+    if info.patch.info.patch_id.is_some() {
+      self.patch_refs.push(info.patch.clone());
+    }
+    self.buffered.push(info);
+    if self.buffered.len() >= self.cap {
+      self.spill().await?;
+    }
+    Ok(())
+  }
+
+  /// ためておいた情報を一時ファイルに追記する
+  async fn spill(&mut self) -> Result<()> {
+    let mut buffered = std::mem::take(&mut self.buffered);
+    buffered.sort_by_cached_key(|info| info.id.to_string());
+    let mut i = 0;
+    while i < buffered.len() {
+      let bucket = bucket_of(&buffered[i]);
+      let mut file = BufWriter::new(
+        OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(self.bucket_path(&bucket))
+          .await?,
+      );
+      while i < buffered.len() && bucket_of(&buffered[i]) == bucket {
+        let mut line = serde_json::to_string(&buffered[i])?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+        i += 1;
+      }
+      file.flush().await?;
+      self.buckets.insert(bucket);
+    }
+    Ok(())
+  }
+
+  /// 改正法令の法令IDが、読み込んだ法令にも`extra`（法令の一覧の法令IDなど）にも無い改正を探す
+  pub fn dangling_patches(&self, extra: &HashSet<String>) -> Vec<DanglingPatch> {
+    let known = self.law_ids.union(extra).cloned().collect();
+    let mut dangling = find_dangling_patches(&self.patch_refs, &known);
+    dangling.sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    dangling
+  }
+
+  /// 一時ファイル1つを読み、法令ごとにまとめる
+  ///
+  /// 一時ファイルの中の順番は解析が終わった順番なので、`LawList::extend_ordered`で並べてから加える
+  async fn read_bucket(&self, bucket: &str, options: &ListupOptions) -> Result<LawList> {
+    let mut infos = Vec::new();
+    let mut lines = BufReader::new(File::open(self.bucket_path(bucket)).await?).lines();
+    while let Some(line) = lines.next_line().await? {
+      infos.push(serde_json::from_str(&line)?);
+    }
+    let mut law_list = LawList::default();
+    law_list.extend_ordered(infos, options);
+    Ok(law_list)
+  }
+
+  /// 出力を書き出す前に一時ファイルをすべて読み、食い違いと一覧に加えられなかったXMLファイルを集める
+  ///
+  /// 一時ファイルは1つずつ読むので、同時にメモリに載るのは`next_laws`と同じく1つ分だけになる
+  pub async fn check(&mut self, options: &ListupOptions) -> Result<()> {
+    if !self.buffered.is_empty() {
+      self.spill().await?;
+    }
+    for bucket in self.buckets.clone() {
+      let mut law_list = self.read_bucket(&bucket, options).await?;
+      self.conflicts.append(&mut law_list.conflicts);
+      self.errors.append(&mut law_list.errors);
+    }
+    self.sort_reports();
+    self.checked = true;
+    Ok(())
+  }
+
+  /// `LawList::sort_reports`と同じく、食い違いなどを法令IDとファイルの順に並べる
+  fn sort_reports(&mut self) {
+    self
+      .fixes
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self
+      .era_inconsistencies
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self
+      .conflicts
+      .sort_by(|a, b| (&a.id, &a.file).cmp(&(&b.id, &b.file)));
+    self.errors.sort_by(|a, b| a.file.cmp(&b.file));
+  }
+
+  /// 一時ファイルを法令IDの順に1つずつ読み、法令ごとにまとめて返す
+  ///
+  /// すべて読み終わった場合は`None`を返す。読み終わった一時ファイルは消す。
+  /// `check`を先に呼んだ場合は、食い違いなどを集め直さない
+  pub async fn next_laws(&mut self, options: &ListupOptions) -> Result<Option<Vec<LawRecord>>> {
+    if !self.buffered.is_empty() {
+      self.spill().await?;
+    }
+    let Some(bucket) = self.buckets.pop_first() else {
+      return Ok(None);
+    };
+    let mut law_list = self.read_bucket(&bucket, options).await?;
+    if !self.checked {
+      self.conflicts.append(&mut law_list.conflicts);
+      self.errors.append(&mut law_list.errors);
+    }
+    fs::remove_file(self.bucket_path(&bucket)).await?;
+    Ok(Some(law_list.into_laws(SortKey::Id)))
+  }
+}
+
+fn bucket_of(info: &LawFileInfo) -> String {
+  info.id.to_string().chars().take(3).collect()
+}
+
+/// 作業ディレクトリ群の中のXMLファイルを解析し、一時ファイルに書き出しながら法令の一覧を作る
+pub async fn spill_law_info_lst(
+  work_dirs: &[String],
+  options: &ListupOptions,
+  dir: &Path,
+  cap: usize,
+) -> Result<SpilledLawList> {
+  let mut spilled = SpilledLawList::new(dir, cap).await?;
+  let (files, walker) = spawn_walkers(work_dirs, options, false);
+  let (mut parsed, parser) = spawn_parser(files, options, None);
+  while let Some(ParsedFile { info, .. }) = parsed.recv().await {
+    if options.accepts(&info) {
+      spilled.push(info).await?;
+    }
+  }
+  spilled.errors.extend(walker.await??);
+  spilled.errors.extend(parser.await??);
+  spilled.sort_reports();
+  Ok(spilled)
+}
+
+/// 法令を1つずつ受け取ってファイルに書き出す
+///
+/// JSONとJSON Linesのみ対応する
+pub enum LawWriter {
+  /// 法令の一覧を持つJSONファイル
+  Json(JsonLawWriter),
+  /// 1行に1つの法令の情報を書くJSON Linesファイル
+  Jsonl(BufWriter<File>),
+}
+
+impl LawWriter {
+  /// `path`に`format`の形式で書き出す準備をする
+  ///
+  /// `header`はJSON形式の場合にだけ書く
+  pub async fn create(
+    path: &str,
+    format: OutputFormat,
+    header: Option<&OutputHeader>,
+  ) -> Result<Self> {
+    match format {
+      OutputFormat::Json => Ok(LawWriter::Json(JsonLawWriter::create(path, header).await?)),
+      OutputFormat::Jsonl => Ok(LawWriter::Jsonl(BufWriter::new(File::create(path).await?))),
+      OutputFormat::Csv => Err(anyhow!(
+        "csv output is not supported with --memory-cap: {path}"
+      )),
+    }
+  }
+
+  /// 法令1つ分を書き出す
+  pub async fn write(&mut self, law: LawRecord) -> Result<()> {
+    match self {
+      LawWriter::Json(writer) => writer.write(law).await?,
+      LawWriter::Jsonl(file) => {
+        let mut line = serde_json::to_string(&law)?;
+        line.push('\n');
+        file.write_all(line.as_bytes()).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// 書き出しを終える
+  pub async fn finish(self) -> Result<()> {
+    match self {
+      LawWriter::Json(writer) => writer.finish().await?,
+      LawWriter::Jsonl(mut file) => file.flush().await?,
+    }
+    Ok(())
+  }
+}
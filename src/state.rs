@@ -0,0 +1,150 @@
+//! 前回の実行で解析したXMLファイルの情報を記録しておき、変更の無いファイルの解析を省く
+
+use crate::calendar::ExtraEra;
+use crate::listup::{LawFileInfo, ListupOptions, Utf8Policy};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tokio::fs::*;
+use tracing::*;
+
+/// 記録の形式の版
+///
+/// `LawFileInfo`などの記録する項目を変えた場合は上げ、古い記録を読み捨てるようにする
+pub const STATE_VERSION: u32 = 1;
+
+/// ファイルが変更されたかどうかを判定するための情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+  /// 更新日時（UNIX時間の秒）
+  pub modified_secs: u64,
+  /// 更新日時の秒未満の部分
+  pub modified_nanos: u32,
+  /// ファイルの大きさ
+  pub size: u64,
+}
+
+impl FileStamp {
+  pub fn new(metadata: &std::fs::Metadata) -> Result<Self> {
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+    Ok(FileStamp {
+      modified_secs: modified.as_secs(),
+      modified_nanos: modified.subsec_nanos(),
+      size: metadata.len(),
+    })
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StateEntry {
+  stamp: FileStamp,
+  info: LawFileInfo,
+}
+
+/// 解析結果を変える解析の設定
+///
+/// 記録したときと違う場合は、記録されている解析結果を使わない
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ParseSettings {
+  /// `ListupOptions::fast`
+  pub fast: bool,
+  /// `ListupOptions::invalid_utf8`
+  pub invalid_utf8: Utf8Policy,
+  /// `ListupOptions::extra_eras`
+  #[serde(default)]
+  pub extra_eras: Vec<ExtraEra>,
+}
+
+impl ParseSettings {
+  pub fn new(options: &ListupOptions) -> Self {
+    ParseSettings {
+      fast: options.fast,
+      invalid_utf8: options.invalid_utf8,
+      extra_eras: options.extra_eras.clone(),
+    }
+  }
+}
+
+/// XMLファイルへのpathごとの解析結果の記録
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListupState {
+  /// 記録の形式の版（`STATE_VERSION`）
+  #[serde(default)]
+  version: u32,
+  /// 記録したときの解析の設定
+  #[serde(default)]
+  settings: ParseSettings,
+  files: HashMap<String, StateEntry>,
+}
+
+impl ListupState {
+  /// `options`で解析する場合の空の記録
+  pub fn new(options: &ListupOptions) -> Self {
+    ListupState {
+      version: STATE_VERSION,
+      settings: ParseSettings::new(options),
+      files: HashMap::new(),
+    }
+  }
+
+  /// 記録を読み込む。ファイルが無い場合は空の記録を返す
+  ///
+  /// 読み込めない記録や、形式の版か解析の設定が`options`と違う記録は、警告を出して空の記録にする
+  pub async fn load(path: &str, options: &ListupOptions) -> Result<Self> {
+    if !Path::new(path).exists() {
+      return Ok(Self::new(options));
+    }
+    let s = read_to_string(path).await?;
+    let state = match serde_json::from_str::<Self>(&s) {
+      Ok(state) => state,
+      Err(e) => {
+        warn!("discard state file {path}: {e}");
+        return Ok(Self::new(options));
+      }
+    };
+    if !state.matches(options) {
+      warn!(
+        "discard state file {path}: it was written with version {} and {:?}",
+        state.version, state.settings
+      );
+      return Ok(Self::new(options));
+    }
+    Ok(state)
+  }
+
+  /// 記録を書き出す
+  pub async fn save(&self, path: &str) -> Result<()> {
+    write(path, serde_json::to_string(self)?).await?;
+    Ok(())
+  }
+
+  /// この記録の形式の版と解析の設定が、`options`で解析する場合と同じかどうか
+  pub fn matches(&self, options: &ListupOptions) -> bool {
+    self.version == STATE_VERSION && self.settings == ParseSettings::new(options)
+  }
+
+  /// 記録されているファイルの数
+  pub fn len(&self) -> usize {
+    self.files.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.files.is_empty()
+  }
+
+  /// ファイルが前回から変更されていなければ、記録されている解析結果を返す
+  pub fn get(&self, file: &str, stamp: &FileStamp) -> Option<&LawFileInfo> {
+    self
+      .files
+      .get(file)
+      .filter(|entry| entry.stamp == *stamp)
+      .map(|entry| &entry.info)
+  }
+
+  /// 解析結果を記録する
+  pub fn insert(&mut self, file: String, stamp: FileStamp, info: LawFileInfo) {
+    self.files.insert(file, StateEntry { stamp, info });
+  }
+}
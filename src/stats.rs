@@ -0,0 +1,336 @@
+//! 法令の一覧の集計
+
+use crate::calendar::{date_to_ad, era_to_ad, EraExt};
+use crate::record::LawRecord;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// 法令の一覧の集計結果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LawStats {
+  /// 法令の数
+  pub laws: usize,
+  /// 公布された元号ごとの法令の数
+  pub per_era: BTreeMap<String, usize>,
+  /// 公布された年代（"1990s"のような西暦の10年ごと）ごとの法令の数
+  pub per_decade: BTreeMap<String, usize>,
+  /// 法令の種類（"法律"・"政令"・"省令"など）ごとの法令の数
+  pub per_law_type: BTreeMap<String, usize>,
+  /// 改正の日付の西暦の年ごとの改正の数
+  pub patches_per_year: BTreeMap<usize, usize>,
+}
+
+/// 法令番号から法令の種類を読み取る
+///
+/// "平成十一年厚生省令第三十三号"の場合は"省令"のように、府省などの名前を除いた種類を返す
+pub fn law_type_of_num(law_num: &str) -> Option<&'static str> {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| {
+    Regex::new(r"^(?:明治|大正|昭和|平成|令和)[^年]+年[^第]*?(?P<kind>法律|政令|勅令|省令|府令|閣令|規則|告示|訓令|条約|布告|達)第")
+      .unwrap()
+  });
+  const KINDS: [&str; 12] = [
+    "法律", "政令", "勅令", "省令", "府令", "閣令", "規則", "告示", "訓令", "条約", "布告", "達",
+  ];
+  let caps = re.captures(law_num.trim())?;
+  KINDS.iter().find(|kind| **kind == &caps["kind"]).copied()
+}
+
+impl LawStats {
+  /// 法令の一覧を集計する
+  pub fn new(laws: &[LawRecord]) -> Self {
+    let mut stats = LawStats {
+      laws: laws.len(),
+      ..Default::default()
+    };
+    for law in laws {
+      *stats
+        .per_era
+        .entry(law.date.era.kanji().to_string())
+        .or_insert(0) += 1;
+      let year = era_to_ad(&law.date.era, law.date.year);
+      *stats
+        .per_decade
+        .entry(format!("{}s", year / 10 * 10))
+        .or_insert(0) += 1;
+      let law_type = if law.num.contains("憲法") {
+        "憲法"
+      } else {
+        law_type_of_num(&law.num).unwrap_or("その他")
+      };
+      *stats.per_law_type.entry(law_type.to_string()).or_insert(0) += 1;
+      for patch in &law.patch {
+        let year = era_to_ad(&patch.info.patch_date.era, patch.info.patch_date.year);
+        *stats.patches_per_year.entry(year).or_insert(0) += 1;
+      }
+    }
+    stats
+  }
+}
+
+/// 府省など・年ごとの改正の数（CSVの1行）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MinistryYearChurn {
+  /// 府省など（法令番号から読み取れない法律・政令などは法令の種類）
+  pub ministry: String,
+  /// 改正の日付の西暦の年
+  pub year: usize,
+  /// 改正の数
+  pub amendments: usize,
+}
+
+/// 法令ごとの改正の間隔（CSVの1行）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LawChurn {
+  /// 法令ID
+  pub id: String,
+  /// 府省など（複数の場合は"・"でつなぐ）
+  pub ministry: String,
+  /// 改正ごとのXMLファイルの数
+  pub snapshots: usize,
+  /// 改正の間の日数の平均（XMLファイルが1つだけの場合は`None`）
+  pub mean_days_between: Option<f64>,
+}
+
+/// 集計のときに法令をまとめる府省などの名前
+fn ministries_for_stats(law: &LawRecord) -> Vec<String> {
+  if law.ministries.is_empty() {
+    vec![law_type_of_num(&law.num).unwrap_or("その他").to_string()]
+  } else {
+    law.ministries.clone()
+  }
+}
+
+/// 府省など・年ごとの改正の数を求める
+///
+/// 最初の版（改正法令の無いもの）も1つと数える。共同命令の場合はそれぞれの府省などに数える
+pub fn churn_by_ministry_year(laws: &[LawRecord]) -> Vec<MinistryYearChurn> {
+  let mut counts: BTreeMap<(String, usize), usize> = BTreeMap::new();
+  for law in laws {
+    let ministries = ministries_for_stats(law);
+    for patch in &law.patch {
+      let year = date_to_ad(&patch.info.patch_date).year;
+      for ministry in &ministries {
+        *counts.entry((ministry.clone(), year)).or_insert(0) += 1;
+      }
+    }
+  }
+  counts
+    .into_iter()
+    .map(|((ministry, year), amendments)| MinistryYearChurn {
+      ministry,
+      year,
+      amendments,
+    })
+    .collect()
+}
+
+/// 法令ごとの改正の間の日数の平均を求める
+pub fn law_churn(laws: &[LawRecord]) -> Vec<LawChurn> {
+  laws
+    .iter()
+    .map(|law| {
+      let mut days = law
+        .patch
+        .iter()
+        .map(|patch| date_to_ad(&patch.info.patch_date).days_since_epoch())
+        .collect::<Vec<_>>();
+      days.sort_unstable();
+      let mean_days_between = match (days.first(), days.last()) {
+        (Some(first), Some(last)) if days.len() > 1 => {
+          Some((last - first) as f64 / (days.len() - 1) as f64)
+        }
+        _ => None,
+      };
+      LawChurn {
+        id: law.id.to_string(),
+        ministry: ministries_for_stats(law).join("・"),
+        snapshots: law.patch.len(),
+        mean_days_between,
+      }
+    })
+    .collect()
+}
+
+impl std::fmt::Display for LawStats {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "laws: {}", self.laws)?;
+    let mut table = |title: &str, rows: Vec<(String, usize)>| -> std::fmt::Result {
+      writeln!(f)?;
+      writeln!(f, "{title}")?;
+      for (key, count) in rows {
+        writeln!(f, "  {key}\t{count}")?;
+      }
+      Ok(())
+    };
+    table("per era", self.per_era.clone().into_iter().collect())?;
+    table("per decade", self.per_decade.clone().into_iter().collect())?;
+    table(
+      "per law type",
+      self.per_law_type.clone().into_iter().collect(),
+    )?;
+    table(
+      "patches per year",
+      self
+        .patches_per_year
+        .iter()
+        .map(|(year, count)| (year.to_string(), *count))
+        .collect(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::calendar::era_from_attr;
+  use crate::record::testing::{date, law, patch};
+  use jplaw_data_types::law::Date;
+
+  const PRIVACY_ACT_ID: &str = "415AC0000000057";
+  const PRIVACY_RULE_ID: &str = "428M60020000003";
+  const CIVIL_CODE_ID: &str = "129AC0000000089";
+
+  fn laws() -> Vec<LawRecord> {
+    let mut privacy = law(PRIVACY_ACT_ID, "個人情報の保護に関する法律");
+    privacy.patch.push(patch(
+      PRIVACY_ACT_ID,
+      date("Heisei", 29, 5, 30),
+      Some("427AC0000000065"),
+    ));
+    let mut rule = law(PRIVACY_RULE_ID, "個人情報の保護に関する法律施行規則");
+    rule.num = "平成二十八年個人情報保護委員会規則第三号".to_string();
+    rule.date = Date::new(era_from_attr("Heisei").unwrap(), 28, None, None);
+    rule.ministries = vec!["個人情報保護委員会".to_string()];
+    rule.patch = vec![patch(PRIVACY_RULE_ID, date("Heisei", 29, 5, 30), None)];
+    let mut civil = law(CIVIL_CODE_ID, "民法");
+    civil.num = "明治二十九年法律第八十九号".to_string();
+    civil.date = Date::new(era_from_attr("Meiji").unwrap(), 29, None, None);
+    civil.patch = vec![patch(CIVIL_CODE_ID, date("Meiji", 29, 4, 27), None)];
+    vec![privacy, rule, civil]
+  }
+
+  #[test]
+  fn reads_law_types_from_numbers() {
+    assert_eq!(law_type_of_num("平成十五年法律第五十七号"), Some("法律"));
+    assert_eq!(
+      law_type_of_num("平成十一年厚生省令第三十三号"),
+      Some("省令")
+    );
+    assert_eq!(
+      law_type_of_num("平成二十八年個人情報保護委員会規則第三号"),
+      Some("規則")
+    );
+    assert_eq!(law_type_of_num("日本国憲法"), None);
+  }
+
+  #[test]
+  fn counts_laws_and_patches() {
+    let stats = LawStats::new(&laws());
+    assert_eq!(stats.laws, 3);
+    let counts = |map: &BTreeMap<String, usize>| {
+      map
+        .iter()
+        .map(|(key, count)| (key.as_str(), *count))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(counts(&stats.per_era), [("平成", 2), ("明治", 1)]);
+    assert_eq!(
+      counts(&stats.per_decade),
+      [("1890s", 1), ("2000s", 1), ("2010s", 1)]
+    );
+    assert_eq!(counts(&stats.per_law_type), [("法律", 2), ("規則", 1)]);
+    let patches = stats.patches_per_year.into_iter().collect::<Vec<_>>();
+    assert_eq!(patches, [(1896, 1), (2003, 1), (2017, 2)]);
+  }
+
+  #[test]
+  fn counts_constitution_unknown_types_and_decade_bounds() {
+    let mut constitution = law("321CONSTITUTION", "日本国憲法");
+    constitution.num = "日本国憲法".to_string();
+    constitution.date = Date::new(era_from_attr("Showa").unwrap(), 21, None, None);
+    // 平成元年は1989年なので1980年代に数える
+    let mut first = law(PRIVACY_ACT_ID, "平成元年の法令");
+    first.num = String::new();
+    first.date = Date::new(era_from_attr("Heisei").unwrap(), 1, None, None);
+    let mut turn = law(CIVIL_CODE_ID, "平成十二年の法令");
+    turn.num = "平成十二年法律第一号".to_string();
+    turn.date = Date::new(era_from_attr("Heisei").unwrap(), 12, None, None);
+
+    let stats = LawStats::new(&[constitution, first, turn]);
+    let keys = |map: &BTreeMap<String, usize>| map.keys().cloned().collect::<Vec<_>>();
+    assert_eq!(keys(&stats.per_decade), ["1940s", "1980s", "2000s"]);
+    assert_eq!(keys(&stats.per_law_type), ["その他", "憲法", "法律"]);
+    assert_eq!(keys(&stats.per_era), ["平成", "昭和"]);
+
+    let table = stats.to_string();
+    assert!(table.starts_with("laws: 3\n"), "{table}");
+    assert!(table.contains("\nper law type\n  その他\t1\n"), "{table}");
+    assert!(table.ends_with("patches per year\n  2003\t3\n"), "{table}");
+  }
+
+  #[test]
+  fn computes_churn_by_ministry_and_law() {
+    let laws = laws();
+    let churn = churn_by_ministry_year(&laws)
+      .into_iter()
+      .map(|row| (row.ministry, row.year, row.amendments))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      churn,
+      [
+        ("個人情報保護委員会".to_string(), 2017, 1),
+        ("法律".to_string(), 1896, 1),
+        ("法律".to_string(), 2003, 1),
+        ("法律".to_string(), 2017, 1),
+      ]
+    );
+
+    let churn = law_churn(&laws);
+    assert_eq!(churn[0].snapshots, 2);
+    // 2003年5月30日から2017年5月30日までの日数
+    assert_eq!(churn[0].mean_days_between, Some(5114.0));
+    assert_eq!(churn[1].ministry, "個人情報保護委員会");
+    assert_eq!(churn[1].mean_days_between, None);
+  }
+
+  #[test]
+  fn counts_joint_ordinances_for_each_ministry() {
+    let id = "415M60000200001";
+    let mut joint = law(id, "共同命令");
+    joint.num = "平成十五年総務省・財務省令第一号".to_string();
+    joint.ministries = vec!["総務省".to_string(), "財務省".to_string()];
+    joint.patch.push(patch(
+      id,
+      date("Heisei", 15, 10, 1),
+      Some("415AC0000000057"),
+    ));
+    joint.patch.push(patch(
+      id,
+      date("Heisei", 16, 5, 30),
+      Some("416AC0000000001"),
+    ));
+
+    let churn = churn_by_ministry_year(std::slice::from_ref(&joint))
+      .into_iter()
+      .map(|row| (row.ministry, row.year, row.amendments))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      churn,
+      [
+        ("総務省".to_string(), 2003, 2),
+        ("総務省".to_string(), 2004, 1),
+        ("財務省".to_string(), 2003, 2),
+        ("財務省".to_string(), 2004, 1),
+      ]
+    );
+
+    let churn = law_churn(&[joint]);
+    assert_eq!(churn[0].ministry, "総務省・財務省");
+    assert_eq!(churn[0].snapshots, 3);
+    // 2003年5月30日から2004年5月30日までの366日を2つの間隔で割る
+    assert_eq!(churn[0].mean_days_between, Some(183.0));
+  }
+}
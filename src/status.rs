@@ -0,0 +1,66 @@
+//! 法令が現に効力を持つかどうか
+
+use crate::calendar::{date_to_ad, AdDate};
+use jplaw_data_types::law::Date;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 法令の効力の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LawStatus {
+  /// 施行されている
+  #[default]
+  InForce,
+  /// 廃止されている
+  Repealed,
+  /// まだ施行されていない
+  NotYetInForce,
+}
+
+impl LawStatus {
+  /// 廃止されているかどうかと施行日から、`today`時点の状態を求める
+  ///
+  /// 施行日がすべて`today`より後の場合は未施行とする。施行日が分からない場合は施行されているものとする
+  pub fn new(repealed: bool, enforcement_dates: &[Date], today: AdDate) -> Self {
+    if repealed {
+      LawStatus::Repealed
+    } else if !enforcement_dates.is_empty()
+      && enforcement_dates
+        .iter()
+        .all(|date| today < date_to_ad(date))
+    {
+      LawStatus::NotYetInForce
+    } else {
+      LawStatus::InForce
+    }
+  }
+
+  /// e-Govの法令の一覧（all_law_list.csv）の"未施行"の列の値で補正した状態
+  ///
+  /// 附則の文言から施行日を読み取れない場合もあるので、廃止されていない法令は一覧の方に合わせる
+  pub fn with_law_list(self, not_yet_in_force: bool) -> Self {
+    match self {
+      LawStatus::Repealed => LawStatus::Repealed,
+      _ if not_yet_in_force => LawStatus::NotYetInForce,
+      _ => LawStatus::InForce,
+    }
+  }
+}
+
+/// XMLの文字列に廃止を表す印があるかどうか
+///
+/// 法令名が"（廃止）"で終わっているか、本則が"削除"や"廃止"だけになっている場合に廃止されているものとする
+pub fn is_repealed(xml: &str) -> bool {
+  static TITLE_RE: OnceLock<Regex> = OnceLock::new();
+  static MAIN_RE: OnceLock<Regex> = OnceLock::new();
+  let title_re = TITLE_RE
+    .get_or_init(|| Regex::new(r"<LawTitle[^>]*>[^<]*[（(]廃止[）)]\s*</LawTitle>").unwrap());
+  let main_re = MAIN_RE.get_or_init(|| {
+    Regex::new(
+      r"<MainProvision[^>]*>(?:\s*<[^>]+>)*\s*(?:削除|廃止)\s*(?:</[^>]+>\s*)*</MainProvision>",
+    )
+    .unwrap()
+  });
+  title_re.is_match(xml) || main_re.is_match(xml)
+}
@@ -0,0 +1,49 @@
+//! 法令の一覧から一部の法令だけを取り出す
+
+use crate::record::LawRecord;
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::path::Path;
+use tokio::fs::*;
+
+/// 1行に1つ法令IDが書かれたファイルを読み込む
+///
+/// 空行と`#`で始まる行は読み飛ばす
+pub async fn read_id_list(path: &str) -> Result<HashSet<String>> {
+  let s = read_to_string(path).await?;
+  Ok(
+    s.lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty() && !line.starts_with('#'))
+      .map(str::to_string)
+      .collect(),
+  )
+}
+
+/// 法令IDが`ids`に含まれる法令だけを取り出す
+pub fn subset_laws(laws: Vec<LawRecord>, ids: &HashSet<String>) -> Vec<LawRecord> {
+  laws
+    .into_iter()
+    .filter(|law| ids.contains(&law.id.to_string()))
+    .collect()
+}
+
+/// 法令の改正ごとのXMLファイルを`dir`の下の法令IDのフォルダにコピーし、`file`をコピー先のpathに書き換える
+pub async fn copy_law_files(laws: &mut [LawRecord], dir: &str) -> Result<()> {
+  for law in laws.iter_mut() {
+    let law_dir = Path::new(dir).join(law.id.to_string());
+    create_dir_all(&law_dir).await?;
+    for patch in law.patch.iter_mut() {
+      let file_name = Path::new(&patch.file)
+        .file_name()
+        .ok_or(anyhow!("cannot get file name: {}", patch.file))?;
+      let dest = law_dir.join(file_name);
+      copy(&patch.file, &dest).await?;
+      patch.file = dest
+        .to_str()
+        .ok_or(anyhow!("cannot convert path to string: {}", dest.display()))?
+        .to_string();
+    }
+  }
+  Ok(())
+}
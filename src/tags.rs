@@ -0,0 +1,49 @@
+//! 開発者向けに、XMLファイルに現れるが一覧の作成に使っていない要素を数える
+
+use crate::listup::XmlFileReader;
+use crate::record::LawRecord;
+use crate::{article, enforcement, metadata};
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// 一覧の作成で読み取っている要素かどうか
+///
+/// 読み取る側のモジュールの`READ_TAGS`から判定するので、読み取る要素を増やした場合はそちらに加える
+pub fn is_handled_tag(name: &str) -> bool {
+  [
+    metadata::READ_TAGS,
+    enforcement::READ_TAGS,
+    article::READ_TAGS,
+  ]
+  .iter()
+  .any(|tags| tags.contains(&name))
+}
+
+/// XMLの文字列に現れる要素名のうち、一覧の作成で読み取っていないものを数える
+pub fn count_unknown_tags(xml: &str, counts: &mut BTreeMap<String, usize>) {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  let re = RE.get_or_init(|| Regex::new(r"<(?P<name>[A-Za-z][\w.-]*)").unwrap());
+  for caps in re.captures_iter(xml) {
+    let name = &caps["name"];
+    if !is_handled_tag(name) {
+      *counts.entry(name.to_string()).or_insert(0) += 1;
+    }
+  }
+}
+
+/// 一覧に含まれるすべてのXMLファイルを読み、読み取っていない要素の出現回数をまとめる
+///
+/// `--work-zip`で作った一覧の場合は、ZIPファイルの中のXMLファイルを読む
+pub fn unknown_tag_report(laws: &[LawRecord]) -> Result<BTreeMap<String, usize>> {
+  let mut reader = XmlFileReader::default();
+  let mut counts = BTreeMap::new();
+  for law in laws {
+    for patch in &law.patch {
+      let buf = reader.read(&patch.file)?;
+      count_unknown_tags(&String::from_utf8_lossy(&buf), &mut counts);
+    }
+  }
+  Ok(counts)
+}
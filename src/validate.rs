@@ -0,0 +1,461 @@
+//! e-Govで配布されている法令の一覧（all_law_list.csv）と、手元のXMLファイル群を突き合わせる
+
+use crate::consistency::LawConflict;
+use crate::record::LawRecord;
+use anyhow::{anyhow, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// all_law_list.csvの1行のうち、突き合わせに使う列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LawListEntry {
+  /// 法令ID
+  pub id: String,
+  /// 法令番号
+  pub num: String,
+  /// 法令名
+  pub name: String,
+  /// "法令名読み"の列の読み仮名（一覧にその列が無いか、空の場合は`None`）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub name_kana: Option<String>,
+  /// "未施行"の列に印があるかどうか（一覧に"未施行"の列が無い場合は`None`）
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub not_yet_in_force: Option<bool>,
+}
+
+/// 手元の法令と一覧とで法令名が異なるもの
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameMismatch {
+  /// 法令ID
+  pub id: String,
+  /// 一覧での法令名
+  pub csv_name: String,
+  /// 手元のXMLファイルでの法令名
+  pub local_name: String,
+}
+
+/// 突き合わせの結果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+  /// 一覧にあるが、手元にXMLファイルが無い法令
+  pub missing_local: Vec<LawListEntry>,
+  /// 手元にXMLファイルがあるが、法令番号が一覧に無い法令
+  pub unknown_num: Vec<LawListEntry>,
+  /// 法令名が一覧と異なる法令
+  pub name_mismatches: Vec<NameMismatch>,
+}
+
+impl ValidationReport {
+  /// 食い違いが無いかどうか
+  pub fn is_empty(&self) -> bool {
+    self.missing_local.is_empty() && self.unknown_num.is_empty() && self.name_mismatches.is_empty()
+  }
+}
+
+/// all_law_list.csvのバイト列を文字列にする
+///
+/// e-Govで配布されているものはShift_JIS（CP932）で書かれているので、UTF-8として読めない場合はCP932として読む。
+/// 先頭のBOMは取り除く
+pub fn decode_law_list_csv(bytes: &[u8]) -> String {
+  if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+    return String::from_utf8_lossy(rest).into_owned();
+  }
+  match std::str::from_utf8(bytes) {
+    Ok(s) => s.to_string(),
+    // encoding_rsのSHIFT_JISはWHATWGの定義に従い、CP932の拡張文字も読める
+    Err(_) => encoding_rs::SHIFT_JIS.decode(bytes).0.into_owned(),
+  }
+}
+
+/// all_law_list.csvを読み込む
+///
+/// 見出しの行の"法令ID"・"法令番号"・"法令名"の列と、あれば"法令名読み"の列と"未施行"の列（空でない場合に未施行とする）を使う
+pub fn read_law_list_csv(path: &str) -> Result<Vec<LawListEntry>> {
+  let bytes = std::fs::read(path).map_err(|e| anyhow!("cannot read {path}: {e}"))?;
+  let text = decode_law_list_csv(&bytes);
+  let mut rdr = csv::Reader::from_reader(text.as_bytes());
+  let headers = rdr.headers()?.clone();
+  let column = |name: &str| {
+    headers
+      .iter()
+      .position(|h| h.trim_start_matches('\u{feff}').trim() == name)
+      .ok_or(anyhow!("column `{name}` is not found in {path}"))
+  };
+  let (id_col, num_col, name_col) = (column("法令ID")?, column("法令番号")?, column("法令名")?);
+  let name_kana_col = column("法令名読み").ok();
+  let not_yet_in_force_col = column("未施行").ok();
+  let mut lst = Vec::new();
+  for record in rdr.records() {
+    let record = record?;
+    let get = |i: usize| record.get(i).unwrap_or_default().trim().to_string();
+    lst.push(LawListEntry {
+      id: get(id_col),
+      num: get(num_col),
+      name: get(name_col),
+      name_kana: name_kana_col.map(get).filter(|kana| !kana.is_empty()),
+      not_yet_in_force: not_yet_in_force_col.map(|i| !get(i).is_empty()),
+    });
+  }
+  Ok(lst)
+}
+
+/// e-Govの法令API（Version 1）の法令名一覧取得（`lawlists`）の応答のXMLを読み込む
+///
+/// `LawNameListInfo`ごとの`LawId`・`LawNo`・`LawName`を使う。応答の`Result`の`Code`が`0`でない場合は、
+/// `Message`を含むエラーにする
+pub fn parse_law_lists_response(bytes: &[u8]) -> Result<Vec<LawListEntry>> {
+  let mut reader = Reader::from_reader(bytes);
+  let mut lst = Vec::new();
+  let mut entry: Option<LawListEntry> = None;
+  let (mut code, mut message) = (None, String::new());
+  let mut path: Vec<Vec<u8>> = Vec::new();
+  loop {
+    match reader.read_event()? {
+      Event::Start(e) => {
+        if e.name().as_ref() == b"LawNameListInfo" {
+          entry = Some(LawListEntry {
+            id: String::new(),
+            num: String::new(),
+            name: String::new(),
+            name_kana: None,
+            not_yet_in_force: None,
+          });
+        }
+        path.push(e.name().as_ref().to_vec());
+      }
+      Event::Text(t) => {
+        let text = t.unescape()?;
+        let text = text.trim();
+        match (path.last().map(Vec::as_slice), entry.as_mut()) {
+          (Some(b"LawId"), Some(entry)) => entry.id.push_str(text),
+          (Some(b"LawNo"), Some(entry)) => entry.num.push_str(text),
+          (Some(b"LawName"), Some(entry)) => entry.name.push_str(text),
+          (Some(b"Code"), None) => code = Some(text.to_string()),
+          (Some(b"Message"), None) => message.push_str(text),
+          _ => (),
+        }
+      }
+      Event::End(e) => {
+        if e.name().as_ref() == b"LawNameListInfo" {
+          lst.extend(entry.take());
+        }
+        path.pop();
+      }
+      Event::Eof => break,
+      _ => (),
+    }
+  }
+  match code.as_deref() {
+    Some("0") => Ok(lst),
+    Some(code) => Err(anyhow!("e-Gov API returned code {code}: {message}")),
+    None => Err(anyhow!("e-Gov API response has no Result Code")),
+  }
+}
+
+/// 法令の一覧のファイルを読み込む
+///
+/// 拡張子が`.xml`のものはe-Govの法令APIの`lawlists`の応答を保存したものとして`parse_law_lists_response`で、
+/// それ以外はall_law_list.csvとして`read_law_list_csv`で読む
+pub fn read_law_list_file(path: &str) -> Result<Vec<LawListEntry>> {
+  let is_xml = std::path::Path::new(path)
+    .extension()
+    .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+  if is_xml {
+    let bytes = std::fs::read(path).map_err(|e| anyhow!("cannot read {path}: {e}"))?;
+    parse_law_lists_response(&bytes).map_err(|e| anyhow!("cannot read {path}: {e}"))
+  } else {
+    read_law_list_csv(path)
+  }
+}
+
+/// 法律・政令ごとの一覧や都道府県ごとの一覧のような、複数の法令の一覧を法令IDごとに1つにまとめる
+///
+/// `lists`は一覧のファイルへのpathとその中身の組。同じ法令IDが複数の一覧にある場合は先の一覧のものを使い、
+/// 法令番号か法令名が食い違っている場合は、後の一覧のファイルへのpathとともに食い違いとして返す
+pub fn merge_law_list_entries(
+  lists: Vec<(String, Vec<LawListEntry>)>,
+) -> (Vec<LawListEntry>, Vec<LawConflict>) {
+  let mut merged: Vec<LawListEntry> = Vec::new();
+  let mut positions: HashMap<String, usize> = HashMap::new();
+  let mut conflicts = Vec::new();
+  for (path, entries) in lists {
+    for entry in entries {
+      let Some(&i) = positions.get(&entry.id) else {
+        positions.insert(entry.id.clone(), merged.len());
+        merged.push(entry);
+        continue;
+      };
+      let existing = &merged[i];
+      for (field, a, b) in [
+        ("num", &existing.num, &entry.num),
+        ("name", &existing.name, &entry.name),
+      ] {
+        if a != b {
+          conflicts.push(LawConflict {
+            id: entry.id.clone(),
+            file: path.clone(),
+            field: field.to_string(),
+            existing: a.clone(),
+            conflicting: b.clone(),
+          });
+        }
+      }
+    }
+  }
+  (merged, conflicts)
+}
+
+/// 複数の法令の一覧のファイルを`read_law_list_file`で読み込み、`merge_law_list_entries`で1つにまとめる
+pub fn read_law_list_csvs(paths: &[String]) -> Result<(Vec<LawListEntry>, Vec<LawConflict>)> {
+  let mut lists = Vec::new();
+  for path in paths {
+    lists.push((path.clone(), read_law_list_file(path)?));
+  }
+  Ok(merge_law_list_entries(lists))
+}
+
+/// 法令の一覧の"未施行"の列で、附則から求めた効力の状態を補う
+///
+/// 廃止の印があるものは廃止のままにする。一覧に無い法令や、一覧に"未施行"の列が無い場合は変えない
+pub fn apply_law_list_status(laws: &mut [LawRecord], entries: &[LawListEntry]) {
+  let not_yet_in_force = entries
+    .iter()
+    .filter_map(|entry| Some((entry.id.as_str(), entry.not_yet_in_force?)))
+    .collect::<HashMap<_, _>>();
+  for law in laws.iter_mut() {
+    if let Some(not_yet_in_force) = not_yet_in_force.get(law.id.to_string().as_str()) {
+      law.status = law.status.with_law_list(*not_yet_in_force);
+    }
+  }
+}
+
+/// XMLファイルの`LawTitle`に`Kana`の属性が無い法令の読み仮名を、法令の一覧の"法令名読み"の列で補う
+pub fn apply_law_list_kana(laws: &mut [LawRecord], entries: &[LawListEntry]) {
+  let kana = entries
+    .iter()
+    .filter_map(|entry| Some((entry.id.as_str(), entry.name_kana.as_deref()?)))
+    .collect::<HashMap<_, _>>();
+  for law in laws.iter_mut().filter(|law| law.name_kana.is_none()) {
+    if let Some(kana) = kana.get(law.id.to_string().as_str()) {
+      law.name_kana = Some(kana.to_string());
+    }
+  }
+}
+
+/// 手元の法令の一覧とall_law_list.csvを突き合わせる
+pub fn validate_laws(laws: &[LawRecord], entries: &[LawListEntry]) -> ValidationReport {
+  let mut report = ValidationReport::default();
+  let local = laws
+    .iter()
+    .map(|law| (law.id.to_string(), law))
+    .collect::<HashMap<_, _>>();
+  let nums = entries
+    .iter()
+    .map(|entry| entry.num.as_str())
+    .collect::<HashSet<_>>();
+  for entry in entries {
+    match local.get(&entry.id) {
+      None => report.missing_local.push(entry.clone()),
+      Some(law) if law.name != entry.name => report.name_mismatches.push(NameMismatch {
+        id: entry.id.clone(),
+        csv_name: entry.name.clone(),
+        local_name: law.name.clone(),
+      }),
+      Some(_) => (),
+    }
+  }
+  for law in laws {
+    if !nums.contains(law.num.as_str()) {
+      report.unknown_num.push(LawListEntry {
+        id: law.id.to_string(),
+        num: law.num.clone(),
+        name: law.name.clone(),
+        name_kana: law.name_kana.clone(),
+        not_yet_in_force: None,
+      });
+    }
+  }
+  report
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::record::testing::law;
+  use crate::status::LawStatus;
+
+  fn entry(id: &str, num: &str, name: &str) -> LawListEntry {
+    LawListEntry {
+      id: id.to_string(),
+      num: num.to_string(),
+      name: name.to_string(),
+      name_kana: None,
+      not_yet_in_force: None,
+    }
+  }
+
+  #[test]
+  fn merges_law_lists_and_reports_conflicts() {
+    let acts = vec![
+      entry(
+        "415AC0000000057",
+        "平成十五年法律第五十七号",
+        "個人情報の保護に関する法律",
+      ),
+      entry("129AC0000000089", "明治二十九年法律第八十九号", "民法"),
+    ];
+    let orders = vec![
+      entry(
+        "415CO0000000507",
+        "平成十五年政令第五百七号",
+        "個人情報の保護に関する法律施行令",
+      ),
+      entry(
+        "415AC0000000057",
+        "平成十五年法律第五十七号",
+        "個人情報保護法",
+      ),
+      entry("129AC0000000089", "明治二十九年法律第八十九号", "民法"),
+    ];
+    let (merged, conflicts) = merge_law_list_entries(vec![
+      ("acts.csv".to_string(), acts),
+      ("orders.csv".to_string(), orders),
+    ]);
+
+    let ids = merged.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+      ids,
+      ["415AC0000000057", "129AC0000000089", "415CO0000000507"]
+    );
+    // 同じ法令IDは先の一覧のものを使う
+    assert_eq!(merged[0].name, "個人情報の保護に関する法律");
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].id, "415AC0000000057");
+    assert_eq!(conflicts[0].file, "orders.csv");
+    assert_eq!(conflicts[0].field, "name");
+    assert_eq!(conflicts[0].existing, "個人情報の保護に関する法律");
+    assert_eq!(conflicts[0].conflicting, "個人情報保護法");
+  }
+
+  #[test]
+  fn corrects_status_with_not_yet_in_force_column() {
+    let mut laws = vec![
+      law("415AC0000000057", "個人情報の保護に関する法律"),
+      law("129AC0000000089", "民法"),
+      law("132AC0000000048", "商法"),
+    ];
+    laws[1].status = LawStatus::Repealed;
+    laws[2].status = LawStatus::NotYetInForce;
+    let entries = vec![
+      LawListEntry {
+        not_yet_in_force: Some(true),
+        ..entry(
+          "415AC0000000057",
+          "平成十五年法律第五十七号",
+          "個人情報の保護に関する法律",
+        )
+      },
+      LawListEntry {
+        not_yet_in_force: Some(true),
+        ..entry("129AC0000000089", "明治二十九年法律第八十九号", "民法")
+      },
+      LawListEntry {
+        not_yet_in_force: Some(false),
+        ..entry("132AC0000000048", "明治三十二年法律第四十八号", "商法")
+      },
+    ];
+    apply_law_list_status(&mut laws, &entries);
+
+    assert_eq!(laws[0].status, LawStatus::NotYetInForce);
+    // 廃止の印は一覧より優先する
+    assert_eq!(laws[1].status, LawStatus::Repealed);
+    assert_eq!(laws[2].status, LawStatus::InForce);
+
+    // "未施行"の列が無い一覧では変えない
+    apply_law_list_status(&mut laws, &[entry("415AC0000000057", "", "")]);
+    assert_eq!(laws[0].status, LawStatus::NotYetInForce);
+  }
+
+  #[test]
+  fn fills_missing_name_kana_from_law_list() {
+    let mut laws = vec![
+      law("415AC0000000057", "個人情報の保護に関する法律"),
+      law("129AC0000000089", "民法"),
+      law("132AC0000000048", "商法"),
+    ];
+    laws[1].name_kana = Some("みんぽう".to_string());
+    let entries = vec![
+      LawListEntry {
+        name_kana: Some("こじんじょうほうのほごにかんするほうりつ".to_string()),
+        ..entry(
+          "415AC0000000057",
+          "平成十五年法律第五十七号",
+          "個人情報の保護に関する法律",
+        )
+      },
+      LawListEntry {
+        name_kana: Some("べつのよみ".to_string()),
+        ..entry("129AC0000000089", "明治二十九年法律第八十九号", "民法")
+      },
+    ];
+    apply_law_list_kana(&mut laws, &entries);
+
+    assert_eq!(
+      laws[0].name_kana.as_deref(),
+      Some("こじんじょうほうのほごにかんするほうりつ")
+    );
+    // XMLファイルの`Kana`の属性を優先する
+    assert_eq!(laws[1].name_kana.as_deref(), Some("みんぽう"));
+    // 一覧に無い法令は変えない
+    assert_eq!(laws[2].name_kana, None);
+  }
+
+  #[test]
+  fn reads_law_lists_response() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DataRoot>
+  <Result><Code>0</Code><Message/></Result>
+  <ApplData>
+    <Category>2</Category>
+    <LawNameListInfo>
+      <LawId>129AC0000000089</LawId>
+      <LawName>民法</LawName>
+      <LawNo>明治二十九年法律第八十九号</LawNo>
+      <PromulgationDate>18960427</PromulgationDate>
+    </LawNameListInfo>
+    <LawNameListInfo>
+      <LawId>415AC0000000057</LawId>
+      <LawName>個人情報の保護に関する法律</LawName>
+      <LawNo>平成十五年法律第五十七号</LawNo>
+      <PromulgationDate>20030530</PromulgationDate>
+    </LawNameListInfo>
+  </ApplData>
+</DataRoot>"#;
+    let entries = parse_law_lists_response(xml.as_bytes()).unwrap();
+    let entries = entries
+      .iter()
+      .map(|e| (e.id.as_str(), e.num.as_str(), e.name.as_str()))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      entries,
+      [
+        ("129AC0000000089", "明治二十九年法律第八十九号", "民法"),
+        (
+          "415AC0000000057",
+          "平成十五年法律第五十七号",
+          "個人情報の保護に関する法律"
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn rejects_law_lists_error_response() {
+    let xml = "<DataRoot><Result><Code>1</Code><Message>法令名一覧の取得に失敗しました。</Message></Result></DataRoot>";
+    let err = parse_law_lists_response(xml.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("法令名一覧の取得に失敗しました。"));
+    assert!(parse_law_lists_response(b"<DataRoot/>").is_err());
+  }
+}
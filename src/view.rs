@@ -0,0 +1,96 @@
+//! 読み込んだ法令の一覧の文字列を借用したまま参照する
+//!
+//! 一覧を一度読み込んで多くの問い合わせに答えるサービス向けに、
+//! 法令ごとに`String`を確保せずに一覧を引けるようにする。
+//! エスケープを含まない文字列は元のバッファを借用する。
+
+use crate::record::{check_schema_version, LawRecord};
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+
+/// 改正1つの借用した参照
+#[derive(Debug, Deserialize)]
+pub struct PatchRef<'a> {
+  /// 改正の日付（JSONのまま）
+  #[serde(borrow)]
+  pub patch_date: &'a RawValue,
+  /// 改正法令の法令ID（JSONのまま）
+  #[serde(borrow, default)]
+  pub patch_id: Option<&'a RawValue>,
+  /// この改正を反映したXMLファイルへのpath
+  #[serde(borrow, default)]
+  pub file: Cow<'a, str>,
+}
+
+/// 法令1つの借用した参照
+///
+/// 日付などの構造を持つ値はJSONのまま持ち、必要になったときに`to_record`で読み込む
+#[derive(Debug, Deserialize)]
+pub struct LawInfoRef<'a> {
+  /// 法令ID（JSONのまま）
+  #[serde(borrow)]
+  pub id: &'a RawValue,
+  /// 法令名
+  #[serde(borrow)]
+  pub name: Cow<'a, str>,
+  /// 法令番号
+  #[serde(borrow)]
+  pub num: Cow<'a, str>,
+  /// 改正の一覧
+  #[serde(borrow)]
+  pub patch: Vec<PatchRef<'a>>,
+  /// 元のJSONの文字列
+  #[serde(skip)]
+  raw: &'a str,
+}
+
+impl<'a> LawInfoRef<'a> {
+  /// 法令IDの文字列
+  pub fn id_str(&self) -> &'a str {
+    self.id.get().trim_matches('"')
+  }
+
+  /// すべての項目を読み込み、所有する`LawRecord`にする
+  pub fn to_record(&self) -> Result<LawRecord> {
+    Ok(serde_json::from_str(self.raw)?)
+  }
+}
+
+/// 書き出したときの情報を持つJSON形式の出力のうち、借用して読み込む部分
+#[derive(Deserialize)]
+struct EnvelopeRef<'a> {
+  schema_version: u32,
+  #[serde(borrow)]
+  laws: Vec<&'a RawValue>,
+}
+
+/// JSON形式で書き出した法令の一覧の文字列を借用して読み込む
+///
+/// `--legacy-format`で書き出した法令の配列だけの文字列も読める
+pub fn law_refs_from_json(buf: &str) -> Result<Vec<LawInfoRef<'_>>> {
+  let raws: Vec<&RawValue> = if buf.trim_start().starts_with('[') {
+    serde_json::from_str(buf)?
+  } else {
+    let envelope: EnvelopeRef = serde_json::from_str(buf)?;
+    check_schema_version(envelope.schema_version)?;
+    envelope.laws
+  };
+  raws.into_iter().map(law_ref_from_raw).collect()
+}
+
+/// JSON Lines形式で書き出した法令の一覧の文字列を借用して読み込む
+pub fn law_refs_from_jsonl(buf: &str) -> Result<Vec<LawInfoRef<'_>>> {
+  buf
+    .lines()
+    .filter(|line| !line.trim().is_empty())
+    .map(|line| law_ref_from_raw(serde_json::from_str(line)?))
+    .collect()
+}
+
+fn law_ref_from_raw(raw: &RawValue) -> Result<LawInfoRef<'_>> {
+  let mut law: LawInfoRef = serde_json::from_str(raw.get())?;
+  law.raw = raw.get();
+  Ok(law)
+}
@@ -0,0 +1,161 @@
+use japanese_law_xml_schema::law::Era;
+use jplaw_data_types::law::Date;
+use listup_law::calendar::{
+  ad_to_era, calendar_of, cmp_dates, date_to_ad, try_date_to_ad, AdDate, CalendarSystem, DateExt,
+  EraExt, ExtraEra,
+};
+use std::cmp::Ordering;
+
+#[test]
+fn converts_dates_to_and_from_chrono() {
+  let date = Date::new(Era::Heisei, 15, Some(5), Some(30));
+  let naive = date.to_chrono().unwrap();
+  assert_eq!(naive.to_string(), "2003-05-30");
+  assert_eq!(Date::from_chrono(naive).unwrap().to_iso8601(), "2003-05-30");
+
+  // 改元の日は新しい元号の元年とする
+  let reiwa = Date::from_chrono("2019-05-01".parse().unwrap()).unwrap();
+  assert_eq!((reiwa.era.attr(), reiwa.year), ("Reiwa", 1));
+
+  assert!(Date::new(Era::Heisei, 15, None, None).to_chrono().is_err());
+  assert!(Date::new(Era::Heisei, 15, Some(2), Some(30))
+    .to_chrono()
+    .is_err());
+  assert!(Date::from_chrono("1867-01-01".parse().unwrap()).is_err());
+}
+
+#[test]
+fn converts_dates_around_every_era_boundary() {
+  // 改元の前日と改元の日、それぞれの元号と年
+  let boundaries = [
+    ((1912, 7, 29), ("Meiji", 45), (1912, 7, 30), ("Taisho", 1)),
+    ((1926, 12, 24), ("Taisho", 15), (1926, 12, 25), ("Showa", 1)),
+    ((1989, 1, 7), ("Showa", 64), (1989, 1, 8), ("Heisei", 1)),
+    ((2019, 4, 30), ("Heisei", 31), (2019, 5, 1), ("Reiwa", 1)),
+  ];
+  for (before, before_era, start, start_era) in boundaries {
+    for ((y, m, d), (era, year)) in [(before, before_era), (start, start_era)] {
+      let ad = AdDate::new(y, m, d);
+      let (actual_era, actual_year) = ad_to_era(&ad).unwrap();
+      assert_eq!((actual_era.attr(), actual_year), (era, year), "{ad}");
+      let date = Date::new(actual_era, actual_year, Some(m), Some(d));
+      assert_eq!(try_date_to_ad(&date).unwrap(), ad);
+    }
+  }
+  assert_eq!(ad_to_era(&AdDate::new(1868, 1, 25)).unwrap().1, 1);
+  assert!(ad_to_era(&AdDate::new(1868, 1, 24)).is_err());
+}
+
+#[test]
+fn rejects_dates_outside_their_era() {
+  for date in [
+    Date::new(Era::Heisei, 1, Some(1), Some(7)),
+    Date::new(Era::Showa, 64, Some(1), Some(8)),
+    Date::new(Era::Reiwa, 1, Some(4), Some(30)),
+    Date::new(Era::Reiwa, 1, Some(4), None),
+    Date::new(Era::Heisei, 31, Some(5), Some(1)),
+    Date::new(Era::Heisei, 32, None, None),
+  ] {
+    assert!(try_date_to_ad(&date).is_err(), "{}", date.to_iso8601());
+  }
+  assert!(try_date_to_ad(&Date::new(Era::Heisei, 31, Some(4), None)).is_ok());
+  assert!(ad_to_era(&AdDate::new(2019, 2, 29)).is_err());
+}
+
+/// 月や日が欠けたものを含む、比べるための日付の集まり
+fn sample_dates() -> Vec<Date> {
+  let mut dates = Vec::new();
+  for era in [Era::Meiji, Era::Taisho, Era::Showa, Era::Heisei, Era::Reiwa] {
+    for year in [1, 31, 64] {
+      for month in [None, Some(1), Some(12)] {
+        for day in [None, Some(1), Some(31)] {
+          dates.push(Date::new(era.clone(), year, month, day));
+        }
+      }
+    }
+  }
+  dates
+}
+
+#[test]
+fn date_order_is_total() {
+  let dates = sample_dates();
+  for a in &dates {
+    for b in &dates {
+      let ab = cmp_dates(a, b);
+      assert_eq!(ab, cmp_dates(b, a).reverse());
+      let same =
+        a.era.attr() == b.era.attr() && (a.year, a.month, a.day) == (b.year, b.month, b.day);
+      assert_eq!(ab == Ordering::Equal, same);
+      if a.month.is_some() && a.day.is_some() && b.month.is_some() && b.day.is_some() {
+        assert!(ab == date_to_ad(a).cmp(&date_to_ad(b)) || date_to_ad(a) == date_to_ad(b));
+      }
+      for c in &dates {
+        if ab.is_le() && cmp_dates(b, c).is_le() {
+          assert!(cmp_dates(a, c).is_le());
+        }
+      }
+    }
+  }
+  // 月や日が無い日付は、同じ年のある日付より前にする
+  let year = Date::new(Era::Heisei, 15, None, None);
+  let month = Date::new(Era::Heisei, 15, Some(1), None);
+  let day = Date::new(Era::Heisei, 15, Some(1), Some(1));
+  assert_eq!(cmp_dates(&year, &month), Ordering::Less);
+  assert_eq!(cmp_dates(&month, &day), Ordering::Less);
+}
+
+#[test]
+fn marks_dates_before_gregorian_adoption_as_lunisolar() {
+  let cases = [
+    (
+      Date::new(Era::Meiji, 5, Some(11), Some(9)),
+      CalendarSystem::Lunisolar,
+    ),
+    (
+      Date::new(Era::Meiji, 5, None, None),
+      CalendarSystem::Gregorian,
+    ),
+    (
+      Date::new(Era::Meiji, 6, Some(1), Some(1)),
+      CalendarSystem::Gregorian,
+    ),
+  ];
+  for (date, calendar) in cases {
+    assert_eq!(calendar_of(&date), calendar, "{}", date.to_iso8601());
+  }
+}
+
+#[test]
+fn formats_and_parses_wareki() {
+  let date = Date::new(Era::Reiwa, 5, Some(6), Some(16));
+  assert_eq!(date.to_wareki_string(), "令和5年6月16日");
+  assert_eq!(
+    Date::new(Era::Reiwa, 1, Some(5), None).to_wareki_string(),
+    "令和元年5月"
+  );
+
+  let parsed = Date::from_wareki_str("平成十五年五月三十日").unwrap();
+  assert_eq!(parsed.to_iso8601(), "2003-05-30");
+  let parsed = Date::from_wareki_str(&date.to_wareki_string()).unwrap();
+  assert_eq!(parsed.to_iso8601(), "2023-06-16");
+  let parsed = Date::from_wareki_str("令和元年").unwrap();
+  assert_eq!((parsed.year, parsed.month), (1, None));
+
+  assert!(Date::from_wareki_str("平成十五年法律第五十七号").is_err());
+  assert!(Date::from_wareki_str("令和元年四月一日").is_err());
+}
+
+#[test]
+fn reads_extra_eras_as_reiwa_years() {
+  let extra: ExtraEra = "Future:2040".parse().unwrap();
+  assert_eq!(extra.attr, "Future");
+  let (era, year) = extra.to_known_era(2).unwrap();
+  assert_eq!((era.attr(), year), ("Reiwa", 23));
+
+  let old: ExtraEra = "Old:2000".parse().unwrap();
+  assert!(old.to_known_era(1).is_none());
+  assert!("Heisei:1989".parse::<ExtraEra>().is_err());
+  assert!("Future".parse::<ExtraEra>().is_err());
+  assert!(":2040".parse::<ExtraEra>().is_err());
+}
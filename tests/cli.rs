@@ -0,0 +1,390 @@
+//! コマンドラインから実行したときの振る舞いの結合テスト
+//!
+//! ライブラリのAPIだけでは確かめられない、サブコマンドを持たない一覧の作成の流れ全体を確かめる。
+
+mod harness;
+
+use harness::{FakeEgov, LawXml, CIVIL_CODE_ID, NO_PATCH_ID, PRIVACY_ACT_ID};
+use serde_json::Value;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// `listup_law`を`args`で実行する
+fn run(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_listup_law"))
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+/// `listup_law`を`args`で実行し、成功したことを確かめる
+fn run_ok(args: &[&str]) {
+  let output = run(args);
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+}
+
+/// 書き出されたJSONファイルを、実行ごとに変わる作成日時を除いて読み込む
+fn read_output(path: &Path) -> Value {
+  let mut value: Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+  value.as_object_mut().unwrap().remove("generated_at");
+  value
+}
+
+fn path_str(path: &Path) -> &str {
+  path.to_str().unwrap()
+}
+
+#[test]
+fn memory_cap_writes_same_output_as_in_memory() {
+  let egov = FakeEgov::standard("cli_memory_cap");
+  let out = FakeEgov::new("cli_memory_cap_out");
+  let in_memory = out.path().join("in_memory.json");
+  let spilled = out.path().join("spilled.json");
+  let work = egov.work_dir();
+  let common = [
+    "--work",
+    work.as_str(),
+    "--skip-errors",
+    "--latest-only",
+    "--normalize",
+    "nfkc",
+  ];
+
+  let mut args = common.to_vec();
+  args.extend(["--output", path_str(&in_memory)]);
+  run_ok(&args);
+  let spill_dir = out.path().join("spill");
+  let mut args = common.to_vec();
+  args.extend([
+    "--output",
+    path_str(&spilled),
+    "--memory-cap",
+    "1",
+    "--spill-dir",
+    path_str(&spill_dir),
+  ]);
+  run_ok(&args);
+
+  let in_memory = read_output(&in_memory);
+  assert_eq!(in_memory["laws"].as_array().unwrap().len(), 2);
+  assert_eq!(read_output(&spilled), in_memory);
+}
+
+#[test]
+fn memory_cap_reports_conflicts_before_writing() {
+  let egov = FakeEgov::new("cli_conflict");
+  let privacy = LawXml {
+    era: "Heisei",
+    year: 15,
+    month: 5,
+    day: 30,
+    num: "平成十五年法律第五十七号",
+    title: "個人情報の保護に関する法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  egov.add_law(PRIVACY_ACT_ID, "20030530", NO_PATCH_ID, &privacy.render());
+  egov.add_law(
+    PRIVACY_ACT_ID,
+    "20170530",
+    NO_PATCH_ID,
+    &LawXml {
+      num: "平成十五年法律第五十八号",
+      ..privacy
+    }
+    .render(),
+  );
+  let out = FakeEgov::new("cli_conflict_out");
+  let output = out.path().join("laws.json");
+  let work = egov.work_dir();
+
+  let res = run(&[
+    "--work",
+    work.as_str(),
+    "--output",
+    path_str(&output),
+    "--on-conflict",
+    "error",
+    "--memory-cap",
+    "1",
+    "--spill-dir",
+    path_str(&out.path().join("spill")),
+  ]);
+
+  assert!(!res.status.success());
+  assert!(!output.exists());
+}
+
+#[test]
+fn parallel_parsing_writes_same_output() {
+  let egov = FakeEgov::standard("cli_jobs");
+  let privacy = LawXml {
+    era: "Heisei",
+    year: 15,
+    month: 5,
+    day: 30,
+    num: "平成十五年法律第五十七号",
+    title: "個人情報の保護に関する法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  // 同じ版で法令名が食い違うXMLファイル
+  for (dir, title) in [("dup_a", "個人情報保護法"), ("dup_b", "個人情報法")] {
+    egov.add_file(
+      dir,
+      &format!("{PRIVACY_ACT_ID}_20170530_{NO_PATCH_ID}.xml"),
+      LawXml { title, ..privacy }.render().as_bytes(),
+    );
+  }
+  // 元号が法令番号と食い違うXMLファイル
+  egov.add_law(
+    PRIVACY_ACT_ID,
+    "20200401",
+    NO_PATCH_ID,
+    &LawXml {
+      era: "Showa",
+      ..privacy
+    }
+    .render(),
+  );
+  for i in 0..8 {
+    egov.add_file(
+      &format!("broken_{i}"),
+      &format!("{PRIVACY_ACT_ID}_2021040{}_{NO_PATCH_ID}.xml", i % 9 + 1),
+      b"<Law><LawNum>",
+    );
+  }
+  let out = FakeEgov::new("cli_jobs_out");
+  let work = egov.work_dir();
+
+  for policy in ["latest-patch", "first"] {
+    let run_with = |name: &str, jobs: &str| {
+      let path = |kind: &str| out.path().join(format!("{policy}_{name}_{kind}.json"));
+      run_ok(&[
+        "--work",
+        work.as_str(),
+        "--skip-errors",
+        "--on-conflict",
+        policy,
+        "--jobs",
+        jobs,
+        "--walk-jobs",
+        jobs,
+        "--output",
+        path_str(&path("laws")),
+        "--conflict-report",
+        path_str(&path("conflicts")),
+        "--error-report",
+        path_str(&path("errors")),
+        "--era-report",
+        path_str(&path("era")),
+        "--suggest-fixes",
+        path_str(&path("fixes")),
+      ]);
+      let report = |kind: &str| -> Value {
+        serde_json::from_slice(&std::fs::read(path(kind)).unwrap()).unwrap()
+      };
+      (
+        read_output(&path("laws")),
+        report("conflicts"),
+        report("errors"),
+        report("era"),
+        report("fixes"),
+      )
+    };
+    let sequential = run_with("sequential", "1");
+    assert!(!sequential.1.as_array().unwrap().is_empty());
+    assert!(!sequential.3.as_array().unwrap().is_empty());
+    // 解析が終わる順番は実行ごとに変わるので、何度か比べる
+    for i in 0..4 {
+      assert_eq!(run_with(&format!("parallel_{i}"), "8"), sequential);
+    }
+  }
+}
+
+#[test]
+fn incremental_run_writes_same_output() {
+  let egov = FakeEgov::standard("cli_incremental");
+  let out = FakeEgov::new("cli_incremental_out");
+  let state = out.path().join("state.json");
+  let work = egov.work_dir();
+  let run_with = |output: &Path, fast: bool| {
+    let mut args = vec![
+      "--work",
+      work.as_str(),
+      "--skip-errors",
+      "--incremental",
+      "--state",
+      path_str(&state),
+      "--output",
+      path_str(output),
+    ];
+    if fast {
+      args.push("--fast");
+    }
+    run_ok(&args);
+  };
+
+  let first = out.path().join("first.json");
+  let second = out.path().join("second.json");
+  run_with(&first, false);
+  assert!(state.exists());
+  run_with(&second, false);
+  assert_eq!(read_output(&second), read_output(&first));
+
+  // 設定が違う記録は捨てて解析し直すので、`--fast`だけで読んだ場合と同じになる
+  let fast_incremental = out.path().join("fast_incremental.json");
+  let fast = out.path().join("fast.json");
+  run_with(&fast_incremental, true);
+  run_ok(&[
+    "--work",
+    work.as_str(),
+    "--skip-errors",
+    "--fast",
+    "--output",
+    path_str(&fast),
+  ]);
+  assert_eq!(read_output(&fast_incremental), read_output(&fast));
+}
+
+#[test]
+fn searches_several_lists_in_priority_order() {
+  let egov = FakeEgov::standard("cli_search");
+  let out = FakeEgov::new("cli_search_out");
+  let current = out.path().join("current.json");
+  let archive = out.path().join("archive.json");
+  run_ok(&[
+    "--work",
+    egov.work_dir().as_str(),
+    "--skip-errors",
+    "--output",
+    path_str(&current),
+  ]);
+  let mut value = read_output(&current);
+  for law in value["laws"].as_array_mut().unwrap() {
+    law["name"] = format!("旧{}", law["name"].as_str().unwrap()).into();
+  }
+  std::fs::write(&archive, value.to_string()).unwrap();
+
+  let search = |inputs: [&Path; 2]| {
+    let output = run(&[
+      "search",
+      "-i",
+      path_str(inputs[0]),
+      "-i",
+      path_str(inputs[1]),
+      "--name",
+      "旧",
+      "--json",
+    ]);
+    assert!(output.status.success());
+    serde_json::from_slice::<Value>(&output.stdout).unwrap()
+  };
+  // 同じ法令IDの法令は先に指定した一覧のものを使う
+  assert_eq!(search([&current, &archive]).as_array().unwrap().len(), 0);
+  assert_eq!(search([&archive, &current]).as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn splits_outputs_by_era() {
+  let egov = FakeEgov::standard("cli_split_by");
+  let out = FakeEgov::new("cli_split_by_out");
+  let all = out.path().join("all.json");
+  run_ok(&[
+    "--work",
+    egov.work_dir().as_str(),
+    "--skip-errors",
+    "--output",
+    path_str(&all),
+    "--split-by",
+    "era",
+  ]);
+
+  assert!(!all.exists());
+  let ids = |name: &str| {
+    read_output(&out.path().join(name))["laws"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|law| law["id"].as_str().unwrap().to_string())
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(ids("all_heisei.json"), [PRIVACY_ACT_ID]);
+  assert_eq!(ids("all_meiji.json"), [CIVIL_CODE_ID]);
+}
+
+#[test]
+fn writes_each_output_in_its_own_format() {
+  let egov = FakeEgov::standard("cli_formats");
+  let out = FakeEgov::new("cli_formats_out");
+  let jsonl = out.path().join("laws.jsonl");
+  let csv = out.path().join("heisei.csv");
+  let jsonl_spec = format!("{},format=jsonl", path_str(&jsonl));
+  let csv_spec = format!("{},format=csv,era=Heisei", path_str(&csv));
+  run_ok(&[
+    "--work",
+    egov.work_dir().as_str(),
+    "--skip-errors",
+    "--output",
+    &jsonl_spec,
+    "--output",
+    &csv_spec,
+  ]);
+
+  let lines = std::fs::read_to_string(&jsonl).unwrap();
+  let ids = lines
+    .lines()
+    .map(|line| serde_json::from_str::<Value>(line).unwrap()["id"].clone())
+    .collect::<Vec<_>>();
+  assert_eq!(ids, [CIVIL_CODE_ID, PRIVACY_ACT_ID]);
+  let csv_rows = std::fs::read_to_string(&csv).unwrap();
+  let csv_rows = csv_rows.lines().collect::<Vec<_>>();
+  assert_eq!(csv_rows.len(), 2);
+  assert!(csv_rows[1].starts_with(PRIVACY_ACT_ID));
+  assert!(out.path().join("heisei.patches.csv").exists());
+}
+
+#[test]
+fn validate_writes_missing_law_ids() {
+  let egov = FakeEgov::new("cli_missing_ids");
+  let out = FakeEgov::new("cli_missing_ids_out");
+  let csv = out.path().join("law_list.csv");
+  std::fs::write(
+    &csv,
+    format!(
+      "法令ID,法令番号,法令名\r\n{PRIVACY_ACT_ID},平成十五年法律第五十七号,個人情報の保護に関する法律\r\n{CIVIL_CODE_ID},明治二十九年法律第八十九号,民法\r\n"
+    ),
+  )
+  .unwrap();
+  egov.add_law(
+    PRIVACY_ACT_ID,
+    "20030530",
+    NO_PATCH_ID,
+    &LawXml {
+      era: "Heisei",
+      year: 15,
+      month: 5,
+      day: 30,
+      num: "平成十五年法律第五十七号",
+      title: "個人情報の保護に関する法律",
+      suppl: "この法律は、公布の日から施行する。",
+    }
+    .render(),
+  );
+  let missing_ids = out.path().join("missing_ids.txt");
+  run_ok(&[
+    "validate",
+    "--work",
+    &egov.work_dir(),
+    "--csv",
+    path_str(&csv),
+    "--missing-ids",
+    path_str(&missing_ids),
+  ]);
+  assert_eq!(
+    std::fs::read_to_string(&missing_ids).unwrap(),
+    format!("{CIVIL_CODE_ID}\n")
+  );
+}
@@ -0,0 +1,160 @@
+//! 出力の形式が意図せず変わっていないかを、期待する出力と比べて調べる
+//!
+//! `cargo test --features golden`で実行する。
+//! 期待する出力は`tests/golden/v{SCHEMA_VERSION}`に置き、
+//! 環境変数`UPDATE_GOLDEN=1`を付けて実行すると今の出力で書き換える。
+//! `listup_law`を実行して書き出されたファイルを、実行ごとに変わる作成日時とこのソフトウェアの版、
+//! 一時ディレクトリのpathを除いて比べる。
+
+#![cfg(feature = "golden")]
+
+mod harness;
+
+use harness::FakeEgov;
+use listup_law::record::SCHEMA_VERSION;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn golden_path(name: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/golden")
+    .join(format!("v{SCHEMA_VERSION}"))
+    .join(name)
+}
+
+/// 文字列の中の作業ディレクトリへのpathを`$WORK`にし、pathの区切りを`/`にそろえる
+fn normalize_path(s: &str, work_dir: &str) -> String {
+  s.replace(work_dir, "$WORK").replace('\\', "/")
+}
+
+/// JSONの値の中の文字列をすべて`normalize_path`でそろえる
+///
+/// JSONの文字列ではWindowsのpathの`\`がエスケープされるので、読み込んだ後の値に対して行う
+fn normalize_value(value: &mut Value, work_dir: &str) {
+  match value {
+    Value::String(s) => *s = normalize_path(s, work_dir),
+    Value::Array(values) => values.iter_mut().for_each(|v| normalize_value(v, work_dir)),
+    Value::Object(map) => map.values_mut().for_each(|v| normalize_value(v, work_dir)),
+    _ => (),
+  }
+}
+
+/// 期待する出力のファイルを読み込む。`UPDATE_GOLDEN=1`の場合は`actual`で書き換えて`None`を返す
+fn read_golden(name: &str, actual: &str) -> Option<String> {
+  let path = golden_path(name);
+  if std::env::var("UPDATE_GOLDEN").as_deref() == Ok("1") {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, actual).unwrap();
+    return None;
+  }
+  Some(std::fs::read_to_string(&path).unwrap_or_else(|_| {
+    panic!(
+      "{} does not exist; run with UPDATE_GOLDEN=1 to create it",
+      path.display()
+    )
+  }))
+}
+
+fn mismatch_message(name: &str) -> String {
+  format!(
+    "output differs from {}; if the change is intended, bump SCHEMA_VERSION or run with UPDATE_GOLDEN=1",
+    golden_path(name).display()
+  )
+}
+
+/// JSONファイルを、作成日時とこのソフトウェアの版を除いて期待する出力と比べる
+///
+/// 空白やキーの順番の違いは無視し、JSONの値として比べる
+fn assert_golden_json(egov: &FakeEgov, name: &str, path: &Path) {
+  let mut actual: Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+  if let Some(header) = actual.as_object_mut() {
+    header.remove("generated_at");
+    header.remove("tool_version");
+  }
+  normalize_value(&mut actual, &egov.work_dir());
+  let Some(expected) = read_golden(name, &serde_json::to_string_pretty(&actual).unwrap()) else {
+    return;
+  };
+  let expected = serde_json::from_str::<Value>(&expected).unwrap();
+  assert_eq!(expected, actual, "{}", mismatch_message(name));
+}
+
+/// JSON Linesのファイルを、1行ずつJSONの値として期待する出力と比べる
+fn assert_golden_jsonl(egov: &FakeEgov, name: &str, path: &Path) {
+  let parse = |text: &str| {
+    text
+      .lines()
+      .map(|line| serde_json::from_str::<Value>(line).unwrap())
+      .collect::<Vec<_>>()
+  };
+  let mut actual = parse(&std::fs::read_to_string(path).unwrap());
+  for value in actual.iter_mut() {
+    normalize_value(value, &egov.work_dir());
+  }
+  let text = actual
+    .iter()
+    .map(|value| format!("{value}\n"))
+    .collect::<String>();
+  let Some(expected) = read_golden(name, &text) else {
+    return;
+  };
+  assert_eq!(parse(&expected), actual, "{}", mismatch_message(name));
+}
+
+/// CSVファイルを、pathをそろえたうえで1行ずつ期待する出力と比べる
+fn assert_golden_csv(egov: &FakeEgov, name: &str, path: &Path) {
+  let actual = normalize_path(&std::fs::read_to_string(path).unwrap(), &egov.work_dir());
+  let Some(expected) = read_golden(name, &actual) else {
+    return;
+  };
+  assert_eq!(
+    expected.lines().collect::<Vec<_>>(),
+    actual.lines().collect::<Vec<_>>(),
+    "{}",
+    mismatch_message(name)
+  );
+}
+
+/// 附則の施行日はすべて過去の日付なので、効力の状態は実行した日によらない
+#[test]
+fn standard_corpus() {
+  let egov = FakeEgov::standard("golden");
+  let out = FakeEgov::new("golden_out");
+  let path = |name: &str| out.path().join(name);
+  let spec = |name: &str, format: &str| format!("{},format={format}", path(name).to_str().unwrap());
+  let (work, json, jsonl, csv) = (
+    egov.work_dir(),
+    spec("standard.json", "json"),
+    spec("standard.jsonl", "jsonl"),
+    spec("standard.csv", "csv"),
+  );
+  let errors = path("standard.errors.json");
+  let output = Command::new(env!("CARGO_BIN_EXE_listup_law"))
+    .args([
+      "--work",
+      work.as_str(),
+      "--skip-errors",
+      "--output",
+      json.as_str(),
+      "--output",
+      jsonl.as_str(),
+      "--output",
+      csv.as_str(),
+      "--error-report",
+      errors.to_str().unwrap(),
+    ])
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  assert_golden_json(&egov, "standard.json", &path("standard.json"));
+  assert_golden_jsonl(&egov, "standard.jsonl", &path("standard.jsonl"));
+  assert_golden_csv(&egov, "standard.csv", &path("standard.csv"));
+  assert_golden_csv(&egov, "standard.patches.csv", &path("standard.patches.csv"));
+  assert_golden_json(&egov, "standard.errors.json", &path("standard.errors.json"));
+}
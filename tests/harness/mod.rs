@@ -0,0 +1,211 @@
+//! e-govで配布されている法令データ一式を小さく真似た作業ディレクトリを作る
+//!
+//! 新しい機能の結合テストは、ここで作った作業ディレクトリに対してライブラリのAPIを通して書く。
+
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 一時ディレクトリに作った作業ディレクトリ
+///
+/// dropしたときに削除する
+pub struct FakeEgov {
+  root: PathBuf,
+}
+
+/// 法令XMLの中身
+pub struct LawXml<'a> {
+  /// "Heisei"のようなXMLの`Era`属性での表記
+  pub era: &'a str,
+  pub year: usize,
+  pub month: usize,
+  pub day: usize,
+  /// 法令番号
+  pub num: &'a str,
+  /// 法令名
+  pub title: &'a str,
+  /// 附則の文言
+  pub suppl: &'a str,
+}
+
+impl LawXml<'_> {
+  /// XMLの文字列にする
+  pub fn render(&self) -> String {
+    format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<Law Era="{era}" Year="{year}" Num="1" LawType="Act" Lang="ja" PromulgateMonth="{month:02}" PromulgateDay="{day:02}">
+  <LawNum>{num}</LawNum>
+  <LawBody>
+    <LawTitle>{title}</LawTitle>
+    <MainProvision>
+      <Article Num="1">
+        <ArticleTitle>第一条</ArticleTitle>
+        <Paragraph Num="1">
+          <ParagraphNum/>
+          <ParagraphSentence>
+            <Sentence Num="1">この法律は、テストのためのものである。</Sentence>
+          </ParagraphSentence>
+        </Paragraph>
+      </Article>
+    </MainProvision>
+    <SupplProvision>
+      <Paragraph Num="1">
+        <ParagraphNum/>
+        <ParagraphSentence>
+          <Sentence Num="1">{suppl}</Sentence>
+        </ParagraphSentence>
+      </Paragraph>
+    </SupplProvision>
+  </LawBody>
+</Law>
+"#,
+      era = self.era,
+      year = self.year,
+      month = self.month,
+      day = self.day,
+      num = self.num,
+      title = self.title,
+      suppl = self.suppl,
+    )
+  }
+}
+
+/// 個人情報の保護に関する法律（平成十五年法律第五十七号）
+pub const PRIVACY_ACT_ID: &str = "415AC0000000057";
+/// 民法（明治二十九年法律第八十九号）
+pub const CIVIL_CODE_ID: &str = "129AC0000000089";
+/// 改正法令の法令IDが無いことを表すファイル名の部分
+pub const NO_PATCH_ID: &str = "000000000000000";
+
+/// 法令ID・法令番号・法令名の列を持つall_law_list.csvをShift_JISで書いたもの
+pub const SJIS_LAW_LIST_CSV: &[u8] = b"\x96\x40\x97\xdfID,\x96\x40\x97\xdf\x94\xd4\x8d\x86,\x96\x40\x97\xdf\x96\xbc\x0d\x0a415AC0000000057,\x95\xbd\x90\xac\x8f\x5c\x8c\xdc\x94N\x96\x40\x97\xa5\x91\xe6\x8c\xdc\x8f\x5c\x8e\xb5\x8d\x86,\x8c\xc2\x90l\x8f\xee\x95\xf1\x82\xcc\x95\xdb\x8c\xec\x82\xc9\x8a\xd6\x82\xb7\x82\xe9\x96\x40\x97\xa5\x0d\x0a";
+
+impl FakeEgov {
+  /// 空の作業ディレクトリを作る
+  pub fn new(name: &str) -> Self {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let root = std::env::temp_dir().join(format!(
+      "listup_law_harness_{name}_{}_{}",
+      std::process::id(),
+      COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::create_dir_all(&root).unwrap();
+    FakeEgov { root }
+  }
+
+  /// 2つの法令と、そのうち1つの改正後の版、壊れたXMLファイルを含む作業ディレクトリを作る
+  pub fn standard(name: &str) -> Self {
+    let egov = FakeEgov::new(name);
+    let privacy = LawXml {
+      era: "Heisei",
+      year: 15,
+      month: 5,
+      day: 30,
+      num: "平成十五年法律第五十七号",
+      title: "個人情報の保護に関する法律",
+      suppl: "この法律は、公布の日から施行する。",
+    };
+    egov.add_law(PRIVACY_ACT_ID, "20030530", NO_PATCH_ID, &privacy.render());
+    egov.add_law(
+      PRIVACY_ACT_ID,
+      "20170530",
+      NO_PATCH_ID,
+      &LawXml {
+        suppl: "この法律は、平成二十九年五月三十日から施行する。",
+        ..privacy
+      }
+      .render(),
+    );
+    egov.add_law(
+      CIVIL_CODE_ID,
+      "18960427",
+      NO_PATCH_ID,
+      &LawXml {
+        era: "Meiji",
+        year: 29,
+        month: 4,
+        day: 27,
+        num: "明治二十九年法律第八十九号",
+        title: "民法",
+        suppl: "この法律は、明治三十一年七月十六日から施行する。",
+      }
+      .render(),
+    );
+    egov.add_file(
+      "broken",
+      &format!("{CIVIL_CODE_ID}_20200401_{NO_PATCH_ID}.xml"),
+      b"<Law><LawNum>",
+    );
+    egov
+  }
+
+  /// 作業ディレクトリへのpath
+  pub fn path(&self) -> &Path {
+    &self.root
+  }
+
+  /// 作業ディレクトリへのpathの文字列
+  pub fn work_dir(&self) -> String {
+    self.root.to_str().unwrap().to_string()
+  }
+
+  /// "法令ID_改正の日付_改正法令の法令ID.xml"という名前で、法令IDのディレクトリにXMLファイルを置く
+  pub fn add_law(&self, id: &str, patch_date: &str, patch_id: &str, xml: &str) -> PathBuf {
+    self.add_file(
+      &format!("{id}_{patch_date}_{patch_id}"),
+      &format!("{id}_{patch_date}_{patch_id}.xml"),
+      xml.as_bytes(),
+    )
+  }
+
+  /// ディレクトリ`dir`にファイルを置く
+  pub fn add_file(&self, dir: &str, file_name: &str, content: &[u8]) -> PathBuf {
+    let dir = self.root.join(dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(file_name);
+    std::fs::write(&path, content).unwrap();
+    path
+  }
+
+  /// 作業ディレクトリの外に、all_law_list.csvをShift_JISで置く
+  pub fn write_sjis_csv(&self) -> PathBuf {
+    let path = self.root.with_extension("csv");
+    std::fs::write(&path, SJIS_LAW_LIST_CSV).unwrap();
+    path
+  }
+
+  /// 作業ディレクトリの外に、作業ディレクトリのファイルをすべて含むZIPファイルを置く
+  ///
+  /// ZIPファイルの中のpathは作業ディレクトリからの相対path
+  pub fn write_zip(&self) -> PathBuf {
+    let zip_path = self.root.with_extension("zip");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+    let mut dirs = vec![self.root.clone()];
+    while let Some(dir) = dirs.pop() {
+      for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+          dirs.push(path);
+          continue;
+        }
+        let name = path.strip_prefix(&self.root).unwrap().to_str().unwrap();
+        zip
+          .start_file(name.replace('\\', "/"), zip::write::FileOptions::default())
+          .unwrap();
+        zip.write_all(&std::fs::read(&path).unwrap()).unwrap();
+      }
+    }
+    zip.finish().unwrap();
+    zip_path
+  }
+}
+
+impl Drop for FakeEgov {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_dir_all(&self.root);
+    let _ = std::fs::remove_file(self.root.with_extension("csv"));
+    let _ = std::fs::remove_file(self.root.with_extension("zip"));
+  }
+}
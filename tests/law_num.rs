@@ -0,0 +1,43 @@
+use listup_law::calendar::EraExt;
+use listup_law::kansuji::to_kansuji;
+use listup_law::law_num::LawNum;
+
+#[test]
+fn parses_and_regenerates_law_numbers() {
+  for s in [
+    "平成十五年法律第五十七号",
+    "明治二十九年法律第八十九号",
+    "令和元年政令第一号",
+    "平成十二年厚生省令第百二十七号",
+    "平成三十年財務省・厚生労働省令第一号",
+    "明治六年太政官布告第六十五号",
+  ] {
+    let num: LawNum = s.parse().unwrap();
+    assert_eq!(num.to_string(), s);
+  }
+
+  let num: LawNum = "平成15年法律第57号".parse().unwrap();
+  assert_eq!(
+    (num.era.attr(), num.year, num.category.as_str(), num.number),
+    ("Heisei", 15, "法律", 57)
+  );
+  assert_eq!(num.to_string(), "平成十五年法律第五十七号");
+
+  assert!("人事院規則九―一二九".parse::<LawNum>().is_err());
+  assert!("平成十五年法律".parse::<LawNum>().is_err());
+}
+
+#[test]
+fn writes_kansuji() {
+  for (n, s) in [
+    (0, "〇"),
+    (10, "十"),
+    (57, "五十七"),
+    (127, "百二十七"),
+    (1001, "千一"),
+    (2019, "二千十九"),
+    (12345, "一万二千三百四十五"),
+  ] {
+    assert_eq!(to_kansuji(n), s);
+  }
+}
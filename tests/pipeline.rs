@@ -0,0 +1,786 @@
+mod harness;
+
+use harness::{FakeEgov, LawXml, CIVIL_CODE_ID, NO_PATCH_ID, PRIVACY_ACT_ID};
+use listup_law::calendar::{to_iso8601, AdDate};
+use listup_law::consistency::find_dangling_patches;
+use listup_law::index::read_law_index;
+use listup_law::listup::{
+  get_law_info_lst, get_law_info_lst_from_zip, ConflictPolicy, ListupOptions, XmlFileReader,
+};
+use listup_law::output::{patches_csv_path, write_law_lst, OutputFormat};
+use listup_law::record::{resolve_patch_laws, LawRecord, OutputHeader, SortKey, SCHEMA_VERSION};
+use listup_law::schema::output_schema;
+use listup_law::snapshot::snapshot_records;
+use listup_law::spill::{spill_law_info_lst, LawWriter, SpilledLawList};
+use listup_law::state::{FileStamp, ListupState, STATE_VERSION};
+use listup_law::tags::unknown_tag_report;
+use listup_law::validate::read_law_list_csv;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[tokio::test]
+async fn lists_laws_and_skips_broken_files() {
+  let egov = FakeEgov::standard("lists_laws");
+  let options = ListupOptions {
+    jobs: 2,
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap();
+
+  assert_eq!(law_list.laws.len(), 2);
+  let privacy = law_list
+    .laws
+    .values()
+    .find(|law| law.id.to_string() == PRIVACY_ACT_ID)
+    .unwrap();
+  assert_eq!(privacy.name, "個人情報の保護に関する法律");
+  assert_eq!(privacy.num, "平成十五年法律第五十七号");
+  assert_eq!(privacy.patch.len(), 2);
+  // 法令名などは最も新しい版のものを使う
+  assert_eq!(privacy.enforcement_dates.len(), 1);
+  assert_eq!(to_iso8601(&privacy.enforcement_dates[0]), "2017-05-30");
+
+  assert_eq!(law_list.errors.len(), 1);
+  assert!(law_list.errors[0].file.contains(CIVIL_CODE_ID));
+}
+
+#[tokio::test]
+async fn stops_at_broken_file_without_skip_errors() {
+  let egov = FakeEgov::standard("fail_fast");
+  let res = get_law_info_lst(&egov.work_dir(), &ListupOptions::default(), None).await;
+  assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn records_empty_and_truncated_files() {
+  let egov = FakeEgov::standard("truncated");
+  egov.add_file(
+    "empty",
+    &format!("{PRIVACY_ACT_ID}_20200401_{NO_PATCH_ID}.xml"),
+    b"",
+  );
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap();
+
+  assert_eq!(law_list.errors.len(), 2);
+  assert!(law_list.errors.iter().all(|err| err.kind == "truncated"));
+}
+
+#[tokio::test]
+async fn fast_mode_reads_same_headers() {
+  let egov = FakeEgov::standard("fast");
+  let options = ListupOptions {
+    skip_errors: true,
+    fast: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap();
+
+  let privacy = law_list
+    .laws
+    .values()
+    .find(|law| law.id.to_string() == PRIVACY_ACT_ID)
+    .unwrap();
+  assert_eq!(privacy.name, "個人情報の保護に関する法律");
+  assert_eq!(privacy.num, "平成十五年法律第五十七号");
+  assert_eq!(privacy.patch.len(), 2);
+  assert!(privacy.enforcement_dates.is_empty());
+}
+
+#[tokio::test]
+async fn reads_extra_eras_as_the_last_known_era() {
+  let egov = FakeEgov::new("extra_era");
+  let law = LawXml {
+    era: "Future",
+    year: 2,
+    month: 4,
+    day: 1,
+    num: "未来二年法律第一号",
+    title: "未来の法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  egov.add_law(PRIVACY_ACT_ID, "20410401", NO_PATCH_ID, &law.render());
+
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap();
+  assert!(law_list.laws.is_empty());
+  assert_eq!(law_list.errors.len(), 1);
+  assert_eq!(law_list.errors[0].kind, "unknown-era");
+
+  for fast in [false, true] {
+    let options = ListupOptions {
+      fast,
+      extra_eras: vec!["Future:2040".parse().unwrap()],
+      ..Default::default()
+    };
+    let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+      .await
+      .unwrap();
+    let law = law_list.laws.values().next().unwrap();
+    assert_eq!(law.name, "未来の法律");
+    // 未来2年は2041年なので令和23年とする
+    assert_eq!(to_iso8601(&law.date), "2041");
+    assert_eq!(
+      to_iso8601(law.promulgation_date.as_ref().unwrap()),
+      "2041-04-01"
+    );
+  }
+}
+
+#[test]
+fn reads_snapshot_digests_from_zip() {
+  let egov = FakeEgov::standard("snapshot_zip");
+  let zip = egov.write_zip();
+  let options = ListupOptions {
+    skip_errors: true,
+    snapshots: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst_from_zip(zip.to_str().unwrap(), &options).unwrap();
+  assert_eq!(law_list.errors.len(), 1);
+  let snapshots = snapshot_records(&law_list.into_laws(SortKey::Id)).unwrap();
+
+  assert_eq!(snapshots.len(), 3);
+  let first = snapshots
+    .iter()
+    .find(|snapshot| snapshot.id == PRIVACY_ACT_ID && snapshot.patch_date == "2003-05-30")
+    .unwrap();
+  let xml = std::fs::read(
+    egov
+      .path()
+      .join(format!("{PRIVACY_ACT_ID}_20030530_{NO_PATCH_ID}"))
+      .join(format!("{PRIVACY_ACT_ID}_20030530_{NO_PATCH_ID}.xml")),
+  )
+  .unwrap();
+  // ZIPファイルの中のXMLファイルは開き直せないので、解析したときの中身から求める
+  assert!(first.file.starts_with(zip.to_str().unwrap()));
+  assert_eq!(first.size, xml.len() as u64);
+  assert_eq!(first.sha256, format!("{:x}", Sha256::digest(&xml)));
+  assert_eq!(first.in_force_at_snapshot, Some(true));
+}
+
+#[test]
+fn reports_unknown_tags_from_zip() {
+  let egov = FakeEgov::standard("tags_zip");
+  let zip = egov.write_zip();
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst_from_zip(zip.to_str().unwrap(), &options).unwrap();
+  let report = unknown_tag_report(&law_list.into_laws(SortKey::Id)).unwrap();
+
+  // 本則の条と附則の文言は読み取っているので数えない
+  let expected = [
+    ("Paragraph", 6),
+    ("ParagraphNum", 6),
+    ("ParagraphSentence", 6),
+  ]
+  .into_iter()
+  .map(|(name, count)| (name.to_string(), count))
+  .collect::<BTreeMap<_, _>>();
+  assert_eq!(report, expected);
+}
+
+#[test]
+fn reads_back_zipped_files_by_listed_path() {
+  let egov = FakeEgov::standard("reader_zip");
+  let zip = egov.write_zip();
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let law_list = get_law_info_lst_from_zip(zip.to_str().unwrap(), &options).unwrap();
+  let mut reader = XmlFileReader::default();
+  for law in law_list.into_laws(SortKey::Id) {
+    for patch in &law.patch {
+      let entry_name = patch.file.rsplit('/').next().unwrap();
+      let dir_name = entry_name.trim_end_matches(".xml");
+      let expected = std::fs::read(egov.path().join(dir_name).join(entry_name)).unwrap();
+      assert_eq!(reader.read(&patch.file).unwrap(), expected);
+    }
+  }
+  assert!(reader
+    .read(&format!("{}/missing.xml", zip.display()))
+    .is_err());
+}
+
+#[tokio::test]
+async fn incremental_run_reuses_matching_state() {
+  let egov = FakeEgov::standard("incremental");
+  let out = FakeEgov::new("incremental_state");
+  let state_path = out.path().join("state.json");
+  let state_path = state_path.to_str().unwrap();
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+
+  let mut state = ListupState::load(state_path, &options).await.unwrap();
+  assert!(state.is_empty());
+  let first = get_law_info_lst(&egov.work_dir(), &options, Some(&mut state))
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  state.save(state_path).await.unwrap();
+
+  let mut state = ListupState::load(state_path, &options).await.unwrap();
+  // 壊れたファイルは記録しない
+  assert_eq!(state.len(), 3);
+  let file = &first[0].patch[0].file;
+  let stamp = FileStamp::new(&std::fs::metadata(file).unwrap()).unwrap();
+  assert!(state.get(file, &stamp).is_some());
+  let second = get_law_info_lst(&egov.work_dir(), &options, Some(&mut state))
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  assert_eq!(
+    serde_json::to_value(&second).unwrap(),
+    serde_json::to_value(&first).unwrap()
+  );
+  assert_eq!(state.len(), 3);
+
+  // 解析の設定が違う記録は捨てる
+  let fast = ListupOptions {
+    fast: true,
+    ..options.clone()
+  };
+  assert!(ListupState::load(state_path, &fast)
+    .await
+    .unwrap()
+    .is_empty());
+
+  // 形式の版が違う記録も捨てる
+  let mut value: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(state_path).unwrap()).unwrap();
+  value["version"] = (STATE_VERSION + 1).into();
+  std::fs::write(state_path, value.to_string()).unwrap();
+  assert!(ListupState::load(state_path, &options)
+    .await
+    .unwrap()
+    .is_empty());
+  value.as_object_mut().unwrap().remove("version");
+  std::fs::write(state_path, value.to_string()).unwrap();
+  assert!(ListupState::load(state_path, &options)
+    .await
+    .unwrap()
+    .is_empty());
+
+  // 読み込めない記録は、エラーにせずに捨てる
+  std::fs::write(state_path, "{\"files\":").unwrap();
+  let state = ListupState::load(state_path, &options).await.unwrap();
+  assert!(state.is_empty());
+  assert!(state.matches(&options));
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn skips_non_utf8_file_names_by_default() {
+  use std::os::unix::ffi::OsStrExt;
+
+  let egov = FakeEgov::new("non_utf8_name");
+  let privacy = egov.add_law(
+    PRIVACY_ACT_ID,
+    "20030530",
+    NO_PATCH_ID,
+    &LawXml {
+      era: "Heisei",
+      year: 15,
+      month: 5,
+      day: 30,
+      num: "平成十五年法律第五十七号",
+      title: "個人情報の保護に関する法律",
+      suppl: "この法律は、公布の日から施行する。",
+    }
+    .render(),
+  );
+  let non_utf8 = privacy
+    .parent()
+    .unwrap()
+    .join(std::ffi::OsStr::from_bytes(b"\x96\x40\x97\xdf.xml"));
+  std::fs::write(non_utf8, b"").unwrap();
+
+  let law_list = get_law_info_lst(&egov.work_dir(), &ListupOptions::default(), None)
+    .await
+    .unwrap();
+  assert_eq!(law_list.laws.len(), 1);
+  assert_eq!(law_list.errors.len(), 1);
+  assert_eq!(law_list.errors[0].kind, "file-name");
+}
+
+#[test]
+fn reads_shift_jis_law_list() {
+  let egov = FakeEgov::new("sjis_csv");
+  let csv = egov.write_sjis_csv();
+  let entries = read_law_list_csv(csv.to_str().unwrap()).unwrap();
+  assert_eq!(entries.len(), 1);
+  assert_eq!(entries[0].id, PRIVACY_ACT_ID);
+  assert_eq!(entries[0].num, "平成十五年法律第五十七号");
+  assert_eq!(entries[0].name, "個人情報の保護に関する法律");
+}
+
+#[test]
+fn reads_utf8_law_list_with_bom() {
+  let egov = FakeEgov::new("bom_csv");
+  let csv = egov.path().join("law_list.csv");
+  std::fs::write(
+    &csv,
+    "\u{feff}法令ID,法令番号,法令名\r\n129AC0000000089,明治二十九年法律第八十九号,民法\r\n",
+  )
+  .unwrap();
+  let entries = read_law_list_csv(csv.to_str().unwrap()).unwrap();
+  assert_eq!(entries.len(), 1);
+  assert_eq!(entries[0].id, CIVIL_CODE_ID);
+  assert_eq!(entries[0].name, "民法");
+  assert_eq!(entries[0].not_yet_in_force, None);
+}
+
+#[test]
+fn reads_not_yet_in_force_column() {
+  let egov = FakeEgov::new("not_yet_csv");
+  let csv = egov.path().join("law_list.csv");
+  std::fs::write(
+    &csv,
+    "法令ID,法令番号,法令名,未施行\r\n129AC0000000089,明治二十九年法律第八十九号,民法,\r\n415AC0000000057,平成十五年法律第五十七号,個人情報の保護に関する法律,〇\r\n",
+  )
+  .unwrap();
+  let entries = read_law_list_csv(csv.to_str().unwrap()).unwrap();
+  assert_eq!(entries[0].not_yet_in_force, Some(false));
+  assert_eq!(entries[1].not_yet_in_force, Some(true));
+}
+
+#[test]
+fn reads_name_kana_column() {
+  let egov = FakeEgov::new("kana_csv");
+  let csv = egov.path().join("law_list.csv");
+  std::fs::write(
+    &csv,
+    "法令ID,法令番号,法令名,法令名読み\r\n129AC0000000089,明治二十九年法律第八十九号,民法,みんぽう\r\n415AC0000000057,平成十五年法律第五十七号,個人情報の保護に関する法律,\r\n",
+  )
+  .unwrap();
+  let entries = read_law_list_csv(csv.to_str().unwrap()).unwrap();
+  assert_eq!(entries[0].name_kana.as_deref(), Some("みんぽう"));
+  // 空の欄は読み仮名が無いものとする
+  assert_eq!(entries[1].name_kana, None);
+}
+
+#[tokio::test]
+async fn reads_back_enveloped_and_legacy_json() {
+  let egov = FakeEgov::standard("envelope");
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let laws = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  let header = OutputHeader::new(vec![egov.work_dir()]);
+
+  let enveloped = egov.path().join("enveloped.json");
+  let enveloped = enveloped.to_str().unwrap();
+  write_law_lst(enveloped, OutputFormat::Json, laws.clone(), Some(&header))
+    .await
+    .unwrap();
+  let value: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(enveloped).unwrap()).unwrap();
+  assert_eq!(value["schema_version"], SCHEMA_VERSION);
+  assert_eq!(value["input"][0], egov.work_dir());
+  assert_eq!(read_law_index(enveloped).await.unwrap().len(), laws.len());
+
+  let legacy = egov.path().join("legacy.json");
+  let legacy = legacy.to_str().unwrap();
+  write_law_lst(legacy, OutputFormat::Json, laws.clone(), None)
+    .await
+    .unwrap();
+  assert_eq!(read_law_index(legacy).await.unwrap().len(), laws.len());
+}
+
+#[tokio::test]
+async fn writes_jsonl_and_csv() {
+  let egov = FakeEgov::standard("jsonl_csv");
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let laws = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+
+  let jsonl = egov.path().join("laws.jsonl");
+  let jsonl = jsonl.to_str().unwrap();
+  write_law_lst(jsonl, OutputFormat::Jsonl, laws.clone(), None)
+    .await
+    .unwrap();
+  assert_eq!(std::fs::read_to_string(jsonl).unwrap().lines().count(), 2);
+  assert_eq!(
+    serde_json::to_value(read_law_index(jsonl).await.unwrap()).unwrap(),
+    serde_json::to_value(&laws).unwrap()
+  );
+
+  let csv_path = egov.path().join("laws.csv");
+  let csv_path = csv_path.to_str().unwrap();
+  write_law_lst(csv_path, OutputFormat::Csv, laws.clone(), None)
+    .await
+    .unwrap();
+  let read_csv = |path: &str| {
+    let mut rdr = csv::Reader::from_path(path).unwrap();
+    let header = rdr.headers().unwrap().clone();
+    rdr
+      .records()
+      .map(|record| {
+        header
+          .iter()
+          .zip(record.unwrap().iter())
+          .map(|(key, value)| (key.to_string(), value.to_string()))
+          .collect::<HashMap<_, _>>()
+      })
+      .collect::<Vec<_>>()
+  };
+  let rows = read_csv(csv_path);
+  assert_eq!(rows.len(), 2);
+  let privacy = rows.iter().find(|row| row["id"] == PRIVACY_ACT_ID).unwrap();
+  assert_eq!(privacy["name"], "個人情報の保護に関する法律");
+  assert_eq!(
+    (privacy["era"].as_str(), privacy["year"].as_str()),
+    ("Heisei", "15")
+  );
+  assert_eq!(privacy["latest_patch_date"], "2017-05-30");
+  let patches = read_csv(&patches_csv_path(csv_path));
+  let dates = patches
+    .iter()
+    .filter(|row| row["id"] == PRIVACY_ACT_ID)
+    .map(|row| row["patch_date"].as_str())
+    .collect::<Vec<_>>();
+  assert_eq!(dates, ["2003-05-30", "2017-05-30"]);
+  assert_eq!(patches.len(), 3);
+}
+
+#[tokio::test]
+async fn reads_zip_like_directory() {
+  let egov = FakeEgov::standard("zip_like_dir");
+  let zip = egov.write_zip();
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let summary = |laws: Vec<LawRecord>| {
+    laws
+      .into_iter()
+      .map(|law| {
+        let dates = law
+          .patch
+          .iter()
+          .map(|patch| to_iso8601(&patch.info.patch_date))
+          .collect::<Vec<_>>();
+        (law.id.to_string(), law.name, law.num, dates)
+      })
+      .collect::<Vec<_>>()
+  };
+  let from_zip = get_law_info_lst_from_zip(zip.to_str().unwrap(), &options).unwrap();
+  assert_eq!(from_zip.errors.len(), 1);
+  let from_dir = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    summary(from_zip.into_laws(SortKey::Id)),
+    summary(from_dir.into_laws(SortKey::Id))
+  );
+}
+
+#[tokio::test]
+async fn spills_into_same_laws_as_in_memory() {
+  let egov = FakeEgov::standard("spill");
+  let out = FakeEgov::new("spill_dir");
+  let spill_dir = out.path().join("spill");
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let in_memory = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+
+  assert!(SpilledLawList::new(&spill_dir, 0).await.is_err());
+  let mut spilled = spill_law_info_lst(&[egov.work_dir()], &options, &spill_dir, 1)
+    .await
+    .unwrap();
+  spilled.check(&options).await.unwrap();
+  assert_eq!(spilled.errors.len(), 1);
+  assert!(spilled.conflicts.is_empty());
+  let mut laws = Vec::new();
+  while let Some(mut chunk) = spilled.next_laws(&options).await.unwrap() {
+    laws.append(&mut chunk);
+  }
+  // `check`で集めたものを読み直すときに重ねて集めない
+  assert_eq!(spilled.errors.len(), 1);
+  assert_eq!(
+    serde_json::to_value(&laws).unwrap(),
+    serde_json::to_value(&in_memory).unwrap()
+  );
+  // 読み終えた一時ファイルは消す
+  assert_eq!(std::fs::read_dir(&spill_dir).unwrap().count(), 0);
+
+  let csv_path = out.path().join("laws.csv");
+  assert!(
+    LawWriter::create(csv_path.to_str().unwrap(), OutputFormat::Csv, None)
+      .await
+      .is_err()
+  );
+}
+
+#[tokio::test]
+async fn schema_covers_written_fields() {
+  let egov = FakeEgov::standard("schema");
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let laws = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  let schema = output_schema(false);
+  let defs = &schema["$defs"];
+  for law in serde_json::to_value(&laws).unwrap().as_array().unwrap() {
+    for key in law.as_object().unwrap().keys() {
+      assert!(
+        defs["LawRecord"]["properties"].get(key).is_some(),
+        "{key} is missing in the schema"
+      );
+    }
+    for patch in law["patch"].as_array().unwrap() {
+      for key in patch.as_object().unwrap().keys() {
+        assert!(
+          defs["PatchRecord"]["properties"].get(key).is_some(),
+          "{key} is missing in the schema"
+        );
+      }
+    }
+  }
+}
+
+#[tokio::test]
+async fn detects_conflicting_law_numbers() {
+  let egov = FakeEgov::new("conflict");
+  let privacy = LawXml {
+    era: "Heisei",
+    year: 15,
+    month: 5,
+    day: 30,
+    num: "平成十五年法律第五十七号",
+    title: "個人情報の保護に関する法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  egov.add_law(PRIVACY_ACT_ID, "20030530", NO_PATCH_ID, &privacy.render());
+  egov.add_law(
+    PRIVACY_ACT_ID,
+    "20170530",
+    NO_PATCH_ID,
+    &LawXml {
+      num: "平成十五年法律第五十八号",
+      ..privacy
+    }
+    .render(),
+  );
+
+  for (policy, patches, errors) in [
+    (ConflictPolicy::LatestPatch, 2, 0),
+    (ConflictPolicy::First, 1, 0),
+    (ConflictPolicy::Error, 1, 1),
+  ] {
+    let options = ListupOptions {
+      skip_errors: true,
+      on_conflict: policy,
+      ..Default::default()
+    };
+    let law_list = get_law_info_lst(&egov.work_dir(), &options, None)
+      .await
+      .unwrap();
+    assert_eq!(law_list.conflicts.len(), 1, "{policy:?}");
+    assert_eq!(law_list.conflicts[0].field, "num");
+    assert_eq!(law_list.laws.values().next().unwrap().patch.len(), patches);
+    assert_eq!(law_list.errors.len(), errors);
+  }
+}
+
+#[tokio::test]
+async fn as_of_selects_the_version_in_force_on_that_day() {
+  let egov = FakeEgov::standard("as_of");
+  let options = ListupOptions {
+    skip_errors: true,
+    as_of: Some(AdDate::new(2010, 1, 1)),
+    ..Default::default()
+  };
+  let mut laws = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  for law in laws.iter_mut() {
+    law.retain_latest_patch();
+  }
+  let privacy = laws
+    .iter()
+    .find(|law| law.id.to_string() == PRIVACY_ACT_ID)
+    .unwrap();
+  assert_eq!(privacy.patch.len(), 1);
+  assert_eq!(to_iso8601(&privacy.patch[0].info.patch_date), "2003-05-30");
+  // 2017年の版の附則にある施行日は読まない
+  assert!(privacy
+    .enforcement_dates
+    .iter()
+    .all(|date| to_iso8601(date) != "2017-05-30"));
+}
+
+#[tokio::test]
+async fn resolves_patch_ids_into_amending_laws() {
+  let egov = FakeEgov::standard("patch_laws");
+  let privacy = LawXml {
+    era: "Heisei",
+    year: 15,
+    month: 5,
+    day: 30,
+    num: "平成十五年法律第五十七号",
+    title: "個人情報の保護に関する法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  // 読み込んだ法令の中にある改正法令と、一覧にしか無い改正法令
+  egov.add_law(PRIVACY_ACT_ID, "20180101", CIVIL_CODE_ID, &privacy.render());
+  egov.add_law(
+    PRIVACY_ACT_ID,
+    "20220401",
+    "502AC0000000044",
+    &privacy.render(),
+  );
+  let options = ListupOptions {
+    skip_errors: true,
+    ..Default::default()
+  };
+  let mut laws = get_law_info_lst(&egov.work_dir(), &options, None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+  let extra = HashMap::from([(
+    "502AC0000000044".to_string(),
+    (
+      "個人情報の保護に関する法律等の一部を改正する法律".to_string(),
+      "令和二年法律第四十四号".to_string(),
+    ),
+  )]);
+  resolve_patch_laws(&mut laws, &extra);
+
+  let privacy = laws
+    .iter()
+    .find(|law| law.id.to_string() == PRIVACY_ACT_ID)
+    .unwrap();
+  let resolved = privacy
+    .patch
+    .iter()
+    .map(|patch| (patch.patch_name.as_deref(), patch.patch_num.as_deref()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    resolved,
+    vec![
+      (None, None),
+      (None, None),
+      (Some("民法"), Some("明治二十九年法律第八十九号")),
+      (
+        Some("個人情報の保護に関する法律等の一部を改正する法律"),
+        Some("令和二年法律第四十四号")
+      ),
+    ]
+  );
+
+  // 一覧に無い改正法令は、存在しない法令への参照として見つかる
+  let known = laws
+    .iter()
+    .map(|law| law.id.to_string())
+    .collect::<HashSet<_>>();
+  let dangling = find_dangling_patches(laws.iter().flat_map(|law| &law.patch), &known);
+  assert_eq!(dangling.len(), 1);
+  assert_eq!(dangling[0].id, PRIVACY_ACT_ID);
+  assert_eq!(dangling[0].patch_id, "502AC0000000044");
+  assert!(dangling[0].file.ends_with("_20220401_502AC0000000044.xml"));
+}
+
+#[tokio::test]
+async fn tracks_law_renames_across_versions() {
+  let egov = FakeEgov::new("renames");
+  let privacy = LawXml {
+    era: "Heisei",
+    year: 15,
+    month: 5,
+    day: 30,
+    num: "平成十五年法律第五十七号",
+    title: "個人情報の保護に関する法律",
+    suppl: "この法律は、公布の日から施行する。",
+  };
+  let renamed = LawXml {
+    title: "個人情報保護法",
+    ..privacy
+  };
+  // 読み込む順番によらないように、新しい版を先に置く
+  egov.add_law(PRIVACY_ACT_ID, "20220401", NO_PATCH_ID, &renamed.render());
+  egov.add_law(PRIVACY_ACT_ID, "20170530", NO_PATCH_ID, &renamed.render());
+  egov.add_law(PRIVACY_ACT_ID, "20030530", NO_PATCH_ID, &privacy.render());
+  egov.add_law(
+    CIVIL_CODE_ID,
+    "18960427",
+    NO_PATCH_ID,
+    &LawXml {
+      era: "Meiji",
+      year: 29,
+      month: 4,
+      day: 27,
+      num: "明治二十九年法律第八十九号",
+      title: "民法",
+      suppl: "この法律は、明治三十一年七月十六日から施行する。",
+    }
+    .render(),
+  );
+  let laws = get_law_info_lst(&egov.work_dir(), &ListupOptions::default(), None)
+    .await
+    .unwrap()
+    .into_laws(SortKey::Id);
+
+  let privacy = laws
+    .iter()
+    .find(|law| law.id.to_string() == PRIVACY_ACT_ID)
+    .unwrap();
+  assert_eq!(privacy.name, "個人情報保護法");
+  let history = privacy
+    .historical_names
+    .iter()
+    .map(|(date, name)| (to_iso8601(date), name.as_str()))
+    .collect::<Vec<_>>();
+  assert_eq!(
+    history,
+    vec![
+      ("2003-05-30".to_string(), "個人情報の保護に関する法律"),
+      ("2017-05-30".to_string(), "個人情報保護法"),
+    ]
+  );
+  let civil = laws
+    .iter()
+    .find(|law| law.id.to_string() == CIVIL_CODE_ID)
+    .unwrap();
+  assert!(civil.historical_names.is_empty());
+}